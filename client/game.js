@@ -11,8 +11,10 @@ let spriteSheetLoaded = false;
 let statusDiv = null;
 let myPlayerId = null;  // Store this client's own player ID
 let gameConfig = null;  // Game config for sprite lookups
+let serverError = null;  // Fatal error reported by the server (stops reconnecting)
 let spriteLookup = new Map();  // object_id -> { sprite_x, sprite_y, sprite_sheet }
 let webglLighting = null;  // WebGL lighting overlay
+let tintedSprites = new Map();  // "sheet:x:y:r:g:b" -> canvas holding the tinted sprite
 
 // Load a sprite sheet
 function loadSpriteSheet(name) {
@@ -31,7 +33,8 @@ function loadSpriteSheet(name) {
             console.error(`Failed to load sprite sheet "${name}":`, e);
             reject(e);
         };
-        img.src = `/assets/${name}`;
+        const sheet = findSpriteSheet(name);
+        img.src = `/assets/${sheet ? sheet.path : name}`;
     });
 }
 
@@ -44,6 +47,46 @@ function getSpriteSheet(name) {
     return spriteSheets[DEFAULT_SPRITE_SHEET] || null;
 }
 
+// The config's sprite_sheets entry called name (null before the config is loaded)
+function findSpriteSheet(name) {
+    const sheets = (gameConfig && gameConfig.sprite_sheets) || [];
+    return sheets.find(sheet => sheet.name === name) || null;
+}
+
+// Pixel size of one sprite on a sheet, from the config's sprite_sheets (default square tiles)
+function getSpriteSize(name) {
+    const sheet = findSpriteSheet(name);
+    if (sheet && sheet.sprite_size) {
+        return sheet.sprite_size;
+    }
+    return { width: SPRITE_SHEET_TILE_SIZE, height: SPRITE_SHEET_TILE_SIZE };
+}
+
+// Sprite cut from a sheet with its colors multiplied by an entity's tint ({ r, g, b }),
+// rendered once per sprite and tint and reused after that
+function getTintedSprite(sheet, sheetName, srcX, srcY, srcSize, tint) {
+    const key = `${sheetName}:${srcX}:${srcY}:${tint.r}:${tint.g}:${tint.b}`;
+    if (tintedSprites.has(key)) {
+        return tintedSprites.get(key);
+    }
+    
+    const canvas = document.createElement('canvas');
+    canvas.width = srcSize.width;
+    canvas.height = srcSize.height;
+    const tintCtx = canvas.getContext('2d');
+    tintCtx.drawImage(sheet, srcX, srcY, srcSize.width, srcSize.height, 0, 0, srcSize.width, srcSize.height);
+    // Multiply the colors, then cut the result back down to the sprite's own pixels
+    tintCtx.globalCompositeOperation = 'multiply';
+    const channel = (value) => Math.round(Math.min(Math.max(value, 0), 1) * 255);
+    tintCtx.fillStyle = `rgb(${channel(tint.r)}, ${channel(tint.g)}, ${channel(tint.b)})`;
+    tintCtx.fillRect(0, 0, srcSize.width, srcSize.height);
+    tintCtx.globalCompositeOperation = 'destination-in';
+    tintCtx.drawImage(sheet, srcX, srcY, srcSize.width, srcSize.height, 0, 0, srcSize.width, srcSize.height);
+    
+    tintedSprites.set(key, canvas);
+    return canvas;
+}
+
 // Load game config for sprite lookups
 async function loadGameConfig() {
     try {
@@ -55,8 +98,8 @@ async function loadGameConfig() {
             for (const obj of gameConfig.game_objects) {
                 const sprites = obj.sprites || [];
                 if (sprites.length > 0) {
-                    // For interactable objects, store both states
-                    if (obj.interactable) {
+                    // For interactable objects (chests and doors are, even unmarked), store both states
+                    if (obj.interactable || obj.object_type === 'chest' || obj.object_type === 'door') {
                         // Before state (sprites[0])
                         if (sprites[0]) {
                             spriteLookup.set(`${obj.id}_before`, {
@@ -163,7 +206,14 @@ function render() {
     // Use our stored player ID, not the broadcast one
     const playerIdToFollow = myPlayerId || gameState.current_player_id;
     
-    if (gameState.entities && playerIdToFollow) {
+    // The server includes our position directly when the update was built for us
+    if (gameState.my_position && gameState.current_player_id === playerIdToFollow) {
+        playerX = gameState.my_position[0];
+        playerY = gameState.my_position[1];
+        playerFound = true;
+    }
+    
+    if (!playerFound && gameState.entities && playerIdToFollow) {
         // Find the current player entity (the one this client controls)
         const currentPlayer = gameState.entities.find(
             e => e.id === playerIdToFollow && 
@@ -290,8 +340,9 @@ function render() {
             } else {
                 spriteInfo = getSpriteInfo('wall_dirt_top');
             }
-            const srcX = spriteInfo.sprite_x * SPRITE_SHEET_TILE_SIZE;
-            const srcY = spriteInfo.sprite_y * SPRITE_SHEET_TILE_SIZE;
+            const srcSize = getSpriteSize(spriteInfo.sprite_sheet);
+            const srcX = spriteInfo.sprite_x * srcSize.width;
+            const srcY = spriteInfo.sprite_y * srcSize.height;
             
             // Check if sprite sheet is loaded
             const tileSpriteSheet = getSpriteSheet(spriteInfo.sprite_sheet);
@@ -301,7 +352,7 @@ function render() {
                 ctx.imageSmoothingEnabled = false;
                 ctx.drawImage(
                     tileSpriteSheet,
-                    srcX, srcY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                    srcX, srcY, srcSize.width, srcSize.height,
                     destX, destY, TILE_SIZE, TILE_SIZE
                 );
             } else {
@@ -342,15 +393,16 @@ function render() {
                 const chestSpriteSheet = getSpriteSheet(spriteInfo.sprite_sheet);
                 
                 if (chestSpriteSheet && chestSpriteSheet.complete) {
-                    const srcX = spriteInfo.sprite_x * SPRITE_SHEET_TILE_SIZE;
-                    const srcY = spriteInfo.sprite_y * SPRITE_SHEET_TILE_SIZE;
+                    const srcSize = getSpriteSize(spriteInfo.sprite_sheet);
+                    const srcX = spriteInfo.sprite_x * srcSize.width;
+                    const srcY = spriteInfo.sprite_y * srcSize.height;
                     
                     ctx.globalCompositeOperation = 'source-over';
                     ctx.imageSmoothingEnabled = false;
                     
                     ctx.drawImage(
                         chestSpriteSheet,
-                        srcX, srcY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                        srcX, srcY, srcSize.width, srcSize.height,
                         destX, destY, TILE_SIZE, TILE_SIZE
                     );
                 } else {
@@ -388,15 +440,16 @@ function render() {
                 const chestSpriteSheet = getSpriteSheet(spriteInfo.sprite_sheet);
                 
                 if (chestSpriteSheet && chestSpriteSheet.complete) {
-                    const srcX = spriteInfo.sprite_x * SPRITE_SHEET_TILE_SIZE;
-                    const srcY = spriteInfo.sprite_y * SPRITE_SHEET_TILE_SIZE;
+                    const srcSize = getSpriteSize(spriteInfo.sprite_sheet);
+                    const srcX = spriteInfo.sprite_x * srcSize.width;
+                    const srcY = spriteInfo.sprite_y * srcSize.height;
                     
                     ctx.globalCompositeOperation = 'source-over';
                     ctx.imageSmoothingEnabled = false;
                     
                     ctx.drawImage(
                         chestSpriteSheet,
-                        srcX, srcY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                        srcX, srcY, srcSize.width, srcSize.height,
                         destX, destY, TILE_SIZE, TILE_SIZE
                     );
                 } else {
@@ -429,8 +482,17 @@ function render() {
             const entitySpriteSheet = getSpriteSheet(spriteInfo.sprite_sheet);
             
             if (entitySpriteSheet && entitySpriteSheet.complete) {
-                const srcX = spriteInfo.sprite_x * SPRITE_SHEET_TILE_SIZE;
-                const srcY = spriteInfo.sprite_y * SPRITE_SHEET_TILE_SIZE;
+                const srcSize = getSpriteSize(spriteInfo.sprite_sheet);
+                let srcX = spriteInfo.sprite_x * srcSize.width;
+                let srcY = spriteInfo.sprite_y * srcSize.height;
+                let spriteSource = entitySpriteSheet;
+                
+                // Color variants draw from a pre-tinted copy of the sprite instead of the sheet
+                if (entity.tint) {
+                    spriteSource = getTintedSprite(entitySpriteSheet, spriteInfo.sprite_sheet, srcX, srcY, srcSize, entity.tint);
+                    srcX = 0;
+                    srcY = 0;
+                }
                 
                 // Save canvas state
                 ctx.save();
@@ -445,15 +507,15 @@ function render() {
                     ctx.translate(destX + TILE_SIZE, destY);
                     ctx.scale(-1, 1);
                     ctx.drawImage(
-                        entitySpriteSheet,
-                        srcX, srcY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                        spriteSource,
+                        srcX, srcY, srcSize.width, srcSize.height,
                         0, 0, TILE_SIZE, TILE_SIZE
                     );
                 } else {
                     // Facing left - draw normally (sprite naturally faces left)
                     ctx.drawImage(
-                        entitySpriteSheet,
-                        srcX, srcY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                        spriteSource,
+                        srcX, srcY, srcSize.width, srcSize.height,
                         destX, destY, TILE_SIZE, TILE_SIZE
                     );
                 }
@@ -505,14 +567,15 @@ function render() {
                 const consumableSpriteSheet = getSpriteSheet(spriteInfo.sprite_sheet);
                 
                 if (consumableSpriteSheet && consumableSpriteSheet.complete) {
-                    const srcX = spriteInfo.sprite_x * SPRITE_SHEET_TILE_SIZE;
-                    const srcY = spriteInfo.sprite_y * SPRITE_SHEET_TILE_SIZE;
+                    const srcSize = getSpriteSize(spriteInfo.sprite_sheet);
+                    const srcX = spriteInfo.sprite_x * srcSize.width;
+                    const srcY = spriteInfo.sprite_y * srcSize.height;
                     
                     ctx.globalCompositeOperation = 'source-over';
                     ctx.imageSmoothingEnabled = false;
                     ctx.drawImage(
                         consumableSpriteSheet,
-                        srcX, srcY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                        srcX, srcY, srcSize.width, srcSize.height,
                         destX, destY, TILE_SIZE, TILE_SIZE
                     );
                 } else {
@@ -544,14 +607,15 @@ function render() {
             const stairsSpriteSheet = getSpriteSheet('tiles.png');
             if (stairsSpriteSheet && stairsSpriteSheet.complete) {
                 // Stairs sprite coordinates from config (x=7, y=16)
-                const stairsSpriteX = 7 * SPRITE_SHEET_TILE_SIZE;
-                const stairsSpriteY = 16 * SPRITE_SHEET_TILE_SIZE;
+                const stairsSize = getSpriteSize('tiles.png');
+                const stairsSpriteX = 7 * stairsSize.width;
+                const stairsSpriteY = 16 * stairsSize.height;
                 
                 ctx.globalCompositeOperation = 'source-over';
                 ctx.imageSmoothingEnabled = false;
                 ctx.drawImage(
                     stairsSpriteSheet,
-                    stairsSpriteX, stairsSpriteY, SPRITE_SHEET_TILE_SIZE, SPRITE_SHEET_TILE_SIZE,
+                    stairsSpriteX, stairsSpriteY, stairsSize.width, stairsSize.height,
                     destX, destY, TILE_SIZE, TILE_SIZE
                 );
                 
@@ -576,6 +640,30 @@ function render() {
         }
     }
     
+    // Draw stairs up (back to the previous level) if this level has them
+    if (gameState.stairs_up_position && Array.isArray(gameState.stairs_up_position) && gameState.stairs_up_position.length === 2) {
+        const upX = gameState.stairs_up_position[0];
+        const upY = gameState.stairs_up_position[1];
+        const stairsSpriteSheet = getSpriteSheet('tiles.png');
+        
+        if (upX >= viewportMinX && upX <= viewportMaxX &&
+            upY >= viewportMinY && upY <= viewportMaxY &&
+            stairsSpriteSheet && stairsSpriteSheet.complete) {
+            const destX = (upX - viewportMinX) * TILE_SIZE + offsetX;
+            const destY = (upY - viewportMinY) * TILE_SIZE + offsetY;
+            
+            // Stairs up sprite coordinates from config (x=8, y=16)
+            const stairsSize = getSpriteSize('tiles.png');
+            ctx.globalCompositeOperation = 'source-over';
+            ctx.imageSmoothingEnabled = false;
+            ctx.drawImage(
+                stairsSpriteSheet,
+                8 * stairsSize.width, 16 * stairsSize.height, stairsSize.width, stairsSize.height,
+                destX, destY, TILE_SIZE, TILE_SIZE
+            );
+        }
+    }
+    
     // Draw death overlay if all players are dead
     if (gameState.all_players_dead) {
         ctx.fillStyle = 'rgba(0, 0, 0, 0.7)';
@@ -585,11 +673,12 @@ function render() {
         ctx.font = 'bold 32px Arial';
         ctx.textAlign = 'center';
         ctx.textBaseline = 'middle';
-        ctx.fillText('YOU DIED', canvas.width / 2, canvas.height / 2 - 20);
+        ctx.fillText(gameState.game_over ? 'GAME OVER' : 'YOU DIED', canvas.width / 2, canvas.height / 2 - 20);
         
         ctx.fillStyle = '#ffffff';
         ctx.font = '20px Arial';
-        ctx.fillText('Waiting for all players to confirm restart...', canvas.width / 2, canvas.height / 2 + 20);
+        const deathHint = gameState.game_over ? 'The run is over.' : 'Waiting for all players to confirm restart...';
+        ctx.fillText(deathHint, canvas.width / 2, canvas.height / 2 + 20);
     }
     
     // Apply WebGL lighting overlay (after all Canvas 2D rendering)
@@ -630,12 +719,40 @@ function updatePlayerList() {
             }
         }
         
+        // Show who is waiting at the stairs
+        if (player.confirmed_stairs) {
+            turnStatus += ' <span style="color: #00ffff; font-size: 10px;">(ready ↓)</span>';
+        } else if (player.confirmed_stairs_up) {
+            turnStatus += ' <span style="color: #00ffff; font-size: 10px;">(ready ↑)</span>';
+        }
+        
         html += `<div style="color: ${color}; ${style}">${status} ${player.name}${turnStatus}</div>`;
     }
     
+    // Party summary once someone wants to change level (only alive players have to confirm)
+    const alivePlayers = gameState.players.filter(p => p.is_alive);
+    const readyDown = alivePlayers.filter(p => p.confirmed_stairs).length;
+    const readyUp = alivePlayers.filter(p => p.confirmed_stairs_up).length;
+    if (readyDown > 0) {
+        html += `<div style="color: #00ffff; font-size: 10px;">${readyDown}/${alivePlayers.length} ready to descend</div>`;
+    }
+    if (readyUp > 0) {
+        html += `<div style="color: #00ffff; font-size: 10px;">${readyUp}/${alivePlayers.length} ready to go up</div>`;
+    }
+    
     playerListDiv.innerHTML = html;
 }
 
+// Goal suffix for the status line on levels not won by the stairs
+function winProgressText(progress) {
+    if (!progress) return '';
+    const condition = progress.condition;
+    if (condition === 'kill_boss') return ` | Goal: slay the boss (${progress.remaining} left)`;
+    if (condition.survive_turns !== undefined) return ` | Goal: survive ${progress.remaining} more turns`;
+    if (condition.collect_items !== undefined) return ` | Goal: collect ${progress.remaining} more items`;
+    return '';
+}
+
 // Update health bar
 function updateHealthBar() {
     if (!gameState || !gameState.entities) {
@@ -755,6 +872,11 @@ async function handleGameStateUpdate(newGameState) {
         } else {
             statusDiv.textContent = `Turn ${gameState.current_turn} - Waiting for other players...`;
         }
+        statusDiv.textContent += winProgressText(gameState.win_progress);
+        if (gameState.waiting_for_players) {
+            const lobby = gameState.waiting_for_players;
+            statusDiv.textContent = `Lobby - ${lobby.ready}/${lobby.connected} ready (${lobby.min_players} players needed)`;
+        }
     }
     
     // Stop movement if it's not the player's turn
@@ -786,11 +908,42 @@ async function handleGameStateUpdate(newGameState) {
     
     // All players dead - level will auto-restart (no confirmation needed)
     
+    // Lobby - the game starts once enough players are connected and ready
+    if (gameState.waiting_for_players && !window.readyConfirmationShown) {
+        window.readyConfirmationShown = true;
+        const lobby = gameState.waiting_for_players;
+        if (confirm(`Waiting for players (${lobby.connected} connected, ${lobby.min_players} needed). Ready to start?`)) {
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                ws.send(JSON.stringify({ action: 'ready' }));
+            }
+        } else {
+            window.readyConfirmationShown = false;
+        }
+        return;
+    } else if (!gameState.waiting_for_players) {
+        window.readyConfirmationShown = false;
+    }
+    
+    // Run is over (permadeath) - only a new game gets out of it
+    if (gameState.game_over && !window.newGameConfirmationShown) {
+        window.newGameConfirmationShown = true;
+        if (confirm('Game over! Start a new run?')) {
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                ws.send(JSON.stringify({ action: 'new_game', new_game: true }));
+            }
+        }
+        return;
+    } else if (!gameState.game_over) {
+        window.newGameConfirmationShown = false;
+    }
+    
     // Check if player is on stairs and show confirmation dialog
     if (gameState.on_stairs && !window.stairsConfirmationShown) {
         window.stairsConfirmationShown = true;
-        const confirmed = confirm('You found the stairs! Are you sure you are done here?');
-        if (confirmed) {
+        if (gameState.monsters_remaining > 0) {
+            // This level has to be cleared before the stairs open
+            alert(`Defeat all enemies first. (${gameState.monsters_remaining} remaining)`);
+        } else if (confirm('You found the stairs! Are you sure you are done here?')) {
             // Send confirmation to server
             if (ws && ws.readyState === WebSocket.OPEN) {
                 ws.send(JSON.stringify({ action: 'move_up', confirm_stairs: true }));
@@ -802,6 +955,20 @@ async function handleGameStateUpdate(newGameState) {
         window.stairsConfirmationShown = false;
     }
     
+    // Same for the stairs back up to the previous level
+    if (gameState.on_stairs_up && !window.stairsUpConfirmationShown) {
+        window.stairsUpConfirmationShown = true;
+        if (confirm('Go back up to the previous level?')) {
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                ws.send(JSON.stringify({ action: 'move_up', confirm_stairs_up: true }));
+            }
+        } else {
+            window.stairsUpConfirmationShown = false;
+        }
+    } else if (!gameState.on_stairs_up) {
+        window.stairsUpConfirmationShown = false;
+    }
+    
     // Ensure default sprite sheet is loaded first
     if (!spriteSheetLoaded) {
         try {
@@ -867,6 +1034,11 @@ async function handleGameStateUpdate(newGameState) {
         } else {
             statusDiv.textContent = `Turn ${gameState.current_turn} - Waiting for other players...`;
         }
+        statusDiv.textContent += winProgressText(gameState.win_progress);
+        if (gameState.waiting_for_players) {
+            const lobby = gameState.waiting_for_players;
+            statusDiv.textContent = `Lobby - ${lobby.ready}/${lobby.connected} ready (${lobby.min_players} players needed)`;
+        }
     }
     
     // Update health bar
@@ -942,6 +1114,20 @@ function connect() {
         console.log('[CLIENT] First 100 chars:', event.data.substring(0, 100));
         try {
             const newGameState = JSON.parse(event.data);
+            if (newGameState.type === 'Error') {
+                // Fatal server-side problem - show it and stop reconnecting
+                console.error('[CLIENT] Server error:', newGameState.message);
+                serverError = newGameState.message;
+                if (statusDiv) {
+                    statusDiv.textContent = 'Server error: ' + newGameState.message;
+                }
+                return;
+            }
+            if (newGameState.type === 'Welcome') {
+                // Server tells us our player ID before the first update
+                myPlayerId = newGameState.player_id;
+                return;
+            }
             console.log('[CLIENT] Parsed game state:', {
                 hasMap: !!newGameState.map,
                 mapSize: newGameState.map ? `${newGameState.width}x${newGameState.height}` : 'none',
@@ -967,6 +1153,9 @@ function connect() {
     
     ws.onclose = (event) => {
         console.log('[CLIENT] WebSocket closed:', event.code, event.reason, event.wasClean);
+        if (serverError) {
+            return;
+        }
         if (statusDiv) {
             statusDiv.textContent = 'Disconnected. Reconnecting...';
         }
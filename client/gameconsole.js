@@ -46,6 +46,11 @@ function addMessage(message) {
             className = '';
     }
     
+    // Rare and epic pickups get their own color
+    if (message.rarity && message.rarity !== 'common') {
+        className = `rarity-${message.rarity}`;
+    }
+    
     messageDiv.className = `message ${className}`;
     
     // Use the pre-formatted text from server, or format it ourselves if needed
@@ -1,54 +1,172 @@
-use crate::entity::{Entity, EntityController};
+use crate::entity::{Direction, Entity, EntityController, StatusEffectKind};
 use crate::dungeon::Dungeon;
 use crate::message::GameMessage;
-use crate::combat::attack_entity;
+use crate::combat::{attack_entity, attack_entity_scaled, heal_allies};
 use crate::consumable::Consumable;
 use crate::game_object::GameObjectRegistry;
+use crate::logging;
+use rand::Rng;
+use rand::seq::SliceRandom;
 
+/// Run one AI phase. `pathfinding_budget` caps the tiles pathfinding may explore across all
+/// monsters this turn (None = unlimited); monsters closest to a player get to spend it first.
+/// Monsters never step onto `blocked_tiles` (e.g., stairs and chests, depending on the config).
+/// Every random choice is drawn from `rng`.
 pub fn process_ai_turns(
     entities: &mut [Entity],
     dungeon: &Dungeon,
     object_registry: &GameObjectRegistry,
     consumables: &mut Vec<Consumable>,
+    pathfinding_budget: Option<u32>,
+    blocked_tiles: &[(usize, usize)],
+    rng: &mut impl Rng,
 ) -> Vec<GameMessage> {
     let mut messages = Vec::new();
+    let mut budget_left = pathfinding_budget.map(|budget| budget as usize);
     
-    // Get all player positions for AI to chase
-    let player_positions: Vec<(usize, usize)> = entities
+    // Get all player positions (and health, for focus fire) for AI to chase
+    let player_positions: Vec<(usize, usize, u32)> = entities
         .iter()
         .filter(|e| e.controller == EntityController::Player && e.is_alive())
-        .map(|e| (e.x, e.y))
+        .map(|e| (e.x, e.y, e.current_health))
         .collect();
     
     // Process each AI entity
-    let ai_indices: Vec<usize> = entities
+    let mut ai_indices: Vec<usize> = entities
         .iter()
         .enumerate()
         .filter(|(_, e)| e.controller == EntityController::AI && e.is_alive())
         .map(|(idx, _)| idx)
         .collect();
+    if budget_left.is_some() {
+        ai_indices.sort_by_key(|&idx| {
+            player_positions.iter()
+                .map(|&(px, py, _)| entities[idx].x.abs_diff(px).max(entities[idx].y.abs_diff(py)))
+                .min()
+                .unwrap_or(usize::MAX)
+        });
+    }
     
     for ai_idx in ai_indices {
+        // May have been killed earlier this phase by a trapped ally lashing out
+        if !entities[ai_idx].is_alive() {
+            continue;
+        }
+        
+        // Channeling monsters stand still until the attack is released or fizzles
+        if entities[ai_idx].channel_turns_remaining > 0 {
+            messages.extend(continue_channel(entities, dungeon, ai_idx, object_registry, consumables, rng));
+            continue;
+        }
+        
+        // Confused monsters stagger in a random direction instead of acting
+        if entities[ai_idx].has_status(StatusEffectKind::Confused) {
+            if let Some((dx, dy)) = random_legal_direction(&entities[ai_idx], dungeon, rng) {
+                messages.push(GameMessage::level_event(format!(
+                    "{} staggers around in confusion.",
+                    entity_name(&entities[ai_idx], object_registry)
+                )));
+                move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+            }
+            continue;
+        }
+        
+        // Boxed in: nowhere to step and no player to hit
+        let adjacent = adjacent_entities(entities, ai_idx);
+        let player_adjacent = adjacent.iter().any(|&idx| entities[idx].controller == EntityController::Player);
+        if !player_adjacent && !has_legal_move(entities, dungeon, ai_idx) {
+            // Logged once when it gets stuck, not on every turn it stays stuck
+            if !entities[ai_idx].boxed_in {
+                logging::debug!("[AI] {} at ({}, {}) is boxed in with no legal moves",
+                    entities[ai_idx].id, entities[ai_idx].x, entities[ai_idx].y);
+            }
+            entities[ai_idx].boxed_in = true;
+            
+            let lashes_out = object_registry.get_object(&entities[ai_idx].object_id)
+                .and_then(|obj| obj.attacks_when_trapped)
+                .unwrap_or(false);
+            if lashes_out && !adjacent.is_empty() {
+                // Break the stalemate by attacking whatever is next to it, allies included
+                let target_idx = adjacent[rng.gen_range(0..adjacent.len())];
+                if let Some(msg) = attack_entity(entities, dungeon, ai_idx, target_idx, object_registry, consumables, rng) {
+                    messages.push(msg);
+                }
+            }
+            continue;
+        }
+        entities[ai_idx].boxed_in = false;
+        
+        // Support monsters tend to wounded allies instead of fighting
+        if let Some(msgs) = try_heal_allies(entities, ai_idx, object_registry) {
+            messages.extend(msgs);
+            continue;
+        }
+        
         let ai_entity = &entities[ai_idx];
         let ai_x = ai_entity.x;
         let ai_y = ai_entity.y;
         
-        // Find nearest player within 5 tile radius
-        let mut nearest_player: Option<(usize, usize)> = None;
-        let mut min_distance = 6; // 5 + 1 to check if within range
+        // Passive monsters ignore players until one of them attacks
+        let hostile = entities[ai_idx].provoked || object_registry.get_object(&entities[ai_idx].object_id)
+            .and_then(|obj| obj.hostile_on_sight)
+            .unwrap_or(true);
+        
+        // Monsters with a vision cone miss players behind them, unless the player is close
+        // enough to hear or has already attacked them
+        let vision_cone = object_registry.get_object(&entities[ai_idx].object_id)
+            .and_then(|obj| obj.vision_cone_degrees)
+            .filter(|_| !entities[ai_idx].provoked);
+        let facing = entities[ai_idx].facing;
+        let can_see = |px: usize, py: usize| {
+            let (dx, dy) = (px as i32 - ai_x as i32, py as i32 - ai_y as i32);
+            vision_cone.is_none_or(|degrees| dx.abs().max(dy.abs()) <= 1 || in_vision_cone(facing, dx, dy, degrees))
+        };
+        
+        // Taunted: go for the taunter wherever they are, until the taunt wears off
+        let taunter = entities[ai_idx].forced_target.as_ref()
+            .filter(|_| entities[ai_idx].taunt_turns_remaining > 0)
+            .and_then(|taunter_id| entities.iter().find(|e| &e.id == taunter_id && e.is_alive()))
+            .map(|e| (e.x, e.y));
+        entities[ai_idx].taunt_turns_remaining = entities[ai_idx].taunt_turns_remaining.saturating_sub(1);
+        if entities[ai_idx].taunt_turns_remaining == 0 {
+            entities[ai_idx].forced_target = None;
+        }
+        
+        // Find nearest player within 5 tile radius - or with focus fire, the one with the least
+        // health left, nearest first among equally hurt ones
+        let focus_fire = object_registry.get_object(&entities[ai_idx].object_id)
+            .and_then(|obj| obj.focus_fire)
+            .unwrap_or(false);
+        let mut nearest_player: Option<(usize, usize)> = taunter;
+        let mut best_rank: Option<(u32, usize)> = None;
         
-        for (px, py) in &player_positions {
+        for (px, py, health) in player_positions.iter().filter(|_| hostile && taunter.is_none()).filter(|&&(px, py, _)| can_see(px, py)) {
             let dx = if ai_x > *px { ai_x - *px } else { *px - ai_x };
             let dy = if ai_y > *py { ai_y - *py } else { *py - ai_y };
             let distance = dx.max(dy); // Chebyshev distance (max of dx, dy)
             
-            if distance <= 5 && distance < min_distance {
-                min_distance = distance;
+            let rank = (if focus_fire { *health } else { 0 }, distance);
+            if distance <= 5 && best_rank.is_none_or(|best| rank < best) {
+                best_rank = Some(rank);
                 nearest_player = Some((*px, *py));
             }
         }
         
         if let Some((target_x, target_y)) = nearest_player {
+            // Wounded and alone: run instead of fighting (stands its ground if cornered)
+            if breaks_morale(entities, ai_idx, object_registry) {
+                if let Some((dx, dy)) = flee_step(entities, dungeon, ai_idx, target_x, target_y) {
+                    entities[ai_idx].last_seen_player = None;
+                    entities[ai_idx].aggro_turns_remaining = None;
+                    move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+                    continue;
+                }
+            }
+            
+            entities[ai_idx].last_seen_player = Some((target_x, target_y));
+            entities[ai_idx].aggro_turns_remaining = object_registry.get_object(&entities[ai_idx].object_id)
+                .and_then(|obj| obj.aggro_persist_turns);
+            
             // Check if player is adjacent (orthogonal only, no diagonal attacks)
             let dx = target_x as i32 - ai_x as i32;
             let dy = target_y as i32 - ai_y as i32;
@@ -62,36 +180,309 @@ pub fn process_ai_turns(
                     e.is_alive() &&
                     e.controller == EntityController::Player
                 }) {
-                    // Attack player
-                    if let Some(msg) = attack_entity(entities, ai_idx, target_idx, object_registry, consumables) {
+                    let channel_turns = object_registry.get_object(&entities[ai_idx].object_id)
+                        .and_then(|obj| obj.channel_turns)
+                        .unwrap_or(0);
+                    
+                    if channel_turns > 0 {
+                        // Telegraph a channeled attack instead of hitting right away
+                        entities[ai_idx].channel_turns_remaining = channel_turns;
+                        entities[ai_idx].channel_target = Some(entities[target_idx].id.clone());
+                        messages.push(GameMessage::level_event(format!(
+                            "{} begins channeling a powerful attack!",
+                            entity_name(&entities[ai_idx], object_registry)
+                        )));
+                    } else if let Some(msg) = attack_entity(entities, dungeon, ai_idx, target_idx, object_registry, consumables, rng) {
+                        // Attack player
                         messages.push(msg);
                     }
                 }
             } else {
                 // Use pathfinding to find the best move towards player
-                if let Some((dx, dy)) = find_path_step(entities, dungeon, ai_x, ai_y, target_x, target_y, ai_idx) {
+                if let Some((dx, dy)) = budgeted_path_step(entities, dungeon, blocked_tiles, ai_idx, (target_x, target_y), &mut budget_left) {
                     let new_x = (ai_x as i32 + dx) as usize;
                     let new_y = (ai_y as i32 + dy) as usize;
                     
                     // Only move if not attacking (we already checked for adjacent attacks above)
-                    move_entity(entities, dungeon, ai_idx, dx, dy);
+                    move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
                 }
             }
             // If pathfinding fails, monster stays in place (blocked)
-        } else {
-            // No player nearby, wander randomly
-            let directions = [(0, -1), (0, 1), (-1, 0), (1, 0)];
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
-            let (dx, dy) = directions[rng.gen_range(0..directions.len())];
+        } else if let Some((last_x, last_y)) = entities[ai_idx].last_seen_player {
+            // Lost sight of the player - hunt or drift toward where they were last seen
+            if (ai_x, ai_y) == (last_x, last_y) || entities[ai_idx].aggro_turns_remaining == Some(0) {
+                // Arrived, or hunted long enough without finding them: give up
+                entities[ai_idx].last_seen_player = None;
+                entities[ai_idx].aggro_turns_remaining = None;
+            } else if let Some(turns) = entities[ai_idx].aggro_turns_remaining {
+                entities[ai_idx].aggro_turns_remaining = Some(turns - 1);
+                if let Some((dx, dy)) = budgeted_path_step(entities, dungeon, blocked_tiles, ai_idx, (last_x, last_y), &mut budget_left) {
+                    move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+                }
+            } else {
+                let bias_percent = object_registry.get_object(&entities[ai_idx].object_id)
+                    .and_then(|obj| obj.wander_bias_percent)
+                    .unwrap_or(75);
+                let (dx, dy) = biased_wander_step(ai_x, ai_y, last_x, last_y, bias_percent, rng);
+                move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+            }
+        } else if let Some((waypoint_x, waypoint_y)) = entities[ai_idx].next_patrol_waypoint() {
+            // No player nearby, walk the patrol route (resumes here after a chase)
+            if (ai_x, ai_y) != (waypoint_x, waypoint_y) {
+                if let Some((dx, dy)) = budgeted_path_step(entities, dungeon, blocked_tiles, ai_idx, (waypoint_x, waypoint_y), &mut budget_left) {
+                    move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+                }
+            }
+        } else if let Some(home_room) = entities[ai_idx].home_room.clone() {
+            // Leashed: head back home after a chase, otherwise wander without leaving the room
+            if !home_room.contains(ai_x, ai_y) {
+                let (home_x, home_y) = home_room.center();
+                if let Some((dx, dy)) = budgeted_path_step(entities, dungeon, blocked_tiles, ai_idx, (home_x, home_y), &mut budget_left) {
+                    move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+                }
+            } else if !pauses_wander(object_registry, &entities[ai_idx], rng) {
+                let directions: Vec<(i32, i32)> = [(0, -1), (0, 1), (-1, 0), (1, 0)].into_iter()
+                    .filter(|&(dx, dy)| home_room.contains((ai_x as i32 + dx) as usize, (ai_y as i32 + dy) as usize))
+                    .collect();
+                if !directions.is_empty() {
+                    let (dx, dy) = directions[rng.gen_range(0..directions.len())];
+                    move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+                }
+            }
+        } else if !pauses_wander(object_registry, &entities[ai_idx], rng) {
+            // No player nearby, wander - mostly carrying on the way it was already going
+            let persist_percent = object_registry.get_object(&entities[ai_idx].object_id)
+                .and_then(|obj| obj.wander_persist_percent)
+                .unwrap_or(60);
+            let (dx, dy) = wander_step(entities[ai_idx].last_wander_dir, persist_percent, rng);
             
-            move_entity(entities, dungeon, ai_idx, dx, dy);
+            move_entity(entities, dungeon, blocked_tiles, ai_idx, dx, dy);
+            // Bumping into something ends the streak so the next step picks a fresh direction
+            let moved = (entities[ai_idx].x, entities[ai_idx].y) != (ai_x, ai_y);
+            entities[ai_idx].last_wander_dir = moved.then_some((dx, dy));
         }
     }
     
     messages
 }
 
+/// Heal nearby monsters if this is a support monster (has healing power) and another
+/// monster within its heal radius (default 1) is hurt. None if it has nothing to do.
+fn try_heal_allies(
+    entities: &mut [Entity],
+    ai_idx: usize,
+    object_registry: &GameObjectRegistry,
+) -> Option<Vec<GameMessage>> {
+    let obj = object_registry.get_object(&entities[ai_idx].object_id)?;
+    let healing_power = obj.healing_power.filter(|&power| power > 0)?;
+    let radius = obj.heal_radius.unwrap_or(1);
+    
+    let healer = &entities[ai_idx];
+    let ally_hurt = entities.iter().enumerate().any(|(idx, e)| {
+        idx != ai_idx
+            && e.controller == EntityController::AI
+            && e.is_alive()
+            && e.current_health < e.max_health
+            && e.x.abs_diff(healer.x).max(e.y.abs_diff(healer.y)) <= radius as usize
+    });
+    if !ally_hurt {
+        return None;
+    }
+    
+    Some(heal_allies(entities, ai_idx, radius, healing_power, &obj.name, object_registry))
+}
+
+/// Whether a monster with a flee threshold is below it and has no living ally within its morale radius
+fn breaks_morale(entities: &[Entity], ai_idx: usize, object_registry: &GameObjectRegistry) -> bool {
+    let Some(obj) = object_registry.get_object(&entities[ai_idx].object_id) else {
+        return false;
+    };
+    let Some(flee_percent) = obj.flee_health_percent else {
+        return false;
+    };
+    let monster = &entities[ai_idx];
+    if monster.current_health * 100 >= monster.max_health * flee_percent {
+        return false;
+    }
+    
+    let radius = obj.morale_radius.unwrap_or(3) as usize;
+    !entities.iter().enumerate().any(|(idx, e)| {
+        idx != ai_idx
+            && e.controller == EntityController::AI
+            && e.is_alive()
+            && e.x.abs_diff(monster.x).max(e.y.abs_diff(monster.y)) <= radius
+    })
+}
+
+/// Legal step that takes the monster furthest from (from_x, from_y), if any step increases the distance
+fn flee_step(entities: &[Entity], dungeon: &Dungeon, ai_idx: usize, from_x: usize, from_y: usize) -> Option<(i32, i32)> {
+    let monster = &entities[ai_idx];
+    // Chebyshev distance first, Manhattan to break ties
+    let distance = |x: usize, y: usize| {
+        let (dx, dy) = (x.abs_diff(from_x), y.abs_diff(from_y));
+        (dx.max(dy), dx + dy)
+    };
+    let current = distance(monster.x, monster.y);
+    
+    [(0, -1), (0, 1), (-1, 0), (1, 0)].into_iter()
+        .filter(|&(dx, dy)| dungeon.validate_move(monster, dx, dy))
+        .map(|(dx, dy)| (dx, dy, (monster.x as i32 + dx) as usize, (monster.y as i32 + dy) as usize))
+        .filter(|&(_, _, x, y)| !entities.iter().any(|e| e.x == x && e.y == y && e.is_alive() && !passes_through(monster, e)))
+        .map(|(dx, dy, x, y)| (dx, dy, distance(x, y)))
+        .filter(|&(_, _, d)| d > current)
+        .max_by_key(|&(_, _, d)| d)
+        .map(|(dx, dy, _)| (dx, dy))
+}
+
+/// Whether the entity can step onto any orthogonal neighbour (walkable and not occupied)
+pub fn has_legal_move(entities: &[Entity], dungeon: &Dungeon, entity_idx: usize) -> bool {
+    let entity = &entities[entity_idx];
+    [(0, -1), (0, 1), (-1, 0), (1, 0)].iter().any(|&(dx, dy)| {
+        if !dungeon.validate_move(entity, dx, dy) {
+            return false;
+        }
+        let x = (entity.x as i32 + dx) as usize;
+        let y = (entity.y as i32 + dy) as usize;
+        !entities.iter().any(|e| e.x == x && e.y == y && e.is_alive() && !passes_through(entity, e))
+    })
+}
+
+/// A random orthogonal direction the entity could step in, ignoring other entities (walls only)
+pub fn random_legal_direction(entity: &Entity, dungeon: &Dungeon, rng: &mut impl Rng) -> Option<(i32, i32)> {
+    let directions: Vec<(i32, i32)> = [(0, -1), (0, 1), (-1, 0), (1, 0)].into_iter()
+        .filter(|&(dx, dy)| dungeon.validate_move(entity, dx, dy))
+        .collect();
+    directions.choose(rng).copied()
+}
+
+/// Whether an offset from a monster lies within `cone_degrees` centered on the way it faces
+fn in_vision_cone(facing: Direction, dx: i32, dy: i32, cone_degrees: u32) -> bool {
+    let (face_x, face_y) = match facing {
+        Direction::Up => (0.0, -1.0),
+        Direction::Down => (0.0, 1.0),
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0),
+    };
+    let length = ((dx * dx + dy * dy) as f64).sqrt();
+    if length == 0.0 {
+        return true;
+    }
+    let cos_angle = (face_x * dx as f64 + face_y * dy as f64) / length;
+    cos_angle.clamp(-1.0, 1.0).acos().to_degrees() <= cone_degrees as f64 / 2.0
+}
+
+/// Whether `mover` may share a tile with `other`: noclip entities pass through their own
+/// side (e.g. ghosts through other monsters), but never stand on an enemy
+pub fn passes_through(mover: &Entity, other: &Entity) -> bool {
+    mover.noclip && mover.controller == other.controller
+}
+
+/// Indices of alive entities orthogonally adjacent to the given entity
+pub fn adjacent_entities(entities: &[Entity], entity_idx: usize) -> Vec<usize> {
+    let (x, y) = (entities[entity_idx].x, entities[entity_idx].y);
+    entities.iter()
+        .enumerate()
+        .filter(|(idx, e)| *idx != entity_idx && e.is_alive() && e.x.abs_diff(x) + e.y.abs_diff(y) == 1)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Random step that, `bias_percent` of the time, is one of the steps bringing the monster
+/// closer to the target; otherwise uniformly random
+fn biased_wander_step(x: usize, y: usize, target_x: usize, target_y: usize, bias_percent: u32, rng: &mut impl Rng) -> (i32, i32) {
+    let directions = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+    
+    let closer: Vec<(i32, i32)> = directions.iter()
+        .copied()
+        .filter(|&(dx, dy)| {
+            (dx != 0 && dx == (target_x as i32 - x as i32).signum())
+                || (dy != 0 && dy == (target_y as i32 - y as i32).signum())
+        })
+        .collect();
+    
+    if !closer.is_empty() && rng.gen_range(0..100) < bias_percent {
+        closer[rng.gen_range(0..closer.len())]
+    } else {
+        directions[rng.gen_range(0..directions.len())]
+    }
+}
+
+/// Whether an idle monster stands still this turn instead of wandering (its `wander_pause_percent`)
+fn pauses_wander(object_registry: &GameObjectRegistry, entity: &Entity, rng: &mut impl Rng) -> bool {
+    let pause_percent = object_registry.get_object(&entity.object_id)
+        .and_then(|obj| obj.wander_pause_percent)
+        .unwrap_or(0);
+    pause_percent > 0 && rng.gen_range(0..100) < pause_percent
+}
+
+/// Idle step that, `persist_percent` of the time, repeats the last wander step; otherwise
+/// any direction but straight back, so wandering monsters don't pace on the spot
+fn wander_step(last_dir: Option<(i32, i32)>, persist_percent: u32, rng: &mut impl Rng) -> (i32, i32) {
+    let directions = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+    
+    let Some((last_dx, last_dy)) = last_dir else {
+        return directions[rng.gen_range(0..directions.len())];
+    };
+    if rng.gen_range(0..100) < persist_percent {
+        return (last_dx, last_dy);
+    }
+    let turns: Vec<(i32, i32)> = directions.iter()
+        .copied()
+        .filter(|&dir| dir != (-last_dx, -last_dy))
+        .collect();
+    turns[rng.gen_range(0..turns.len())]
+}
+
+fn entity_name(entity: &Entity, object_registry: &GameObjectRegistry) -> String {
+    entity.display_name(object_registry)
+}
+
+// Advance a channeled attack by one turn: release it when the channel completes
+// if the target is still orthogonally adjacent, otherwise let it fizzle
+fn continue_channel(
+    entities: &mut [Entity],
+    dungeon: &Dungeon,
+    ai_idx: usize,
+    object_registry: &GameObjectRegistry,
+    consumables: &mut Vec<Consumable>,
+    rng: &mut impl Rng,
+) -> Vec<GameMessage> {
+    let mut messages = Vec::new();
+    let name = entity_name(&entities[ai_idx], object_registry);
+    let (ai_x, ai_y) = (entities[ai_idx].x, entities[ai_idx].y);
+    
+    let target_idx = entities[ai_idx].channel_target.as_ref()
+        .and_then(|target_id| entities.iter().position(|e| &e.id == target_id && e.is_alive()));
+    let target_in_range = target_idx.is_some_and(|idx| {
+        entities[idx].x.abs_diff(ai_x) + entities[idx].y.abs_diff(ai_y) == 1
+    });
+    
+    entities[ai_idx].channel_turns_remaining -= 1;
+    
+    if !target_in_range {
+        entities[ai_idx].channel_turns_remaining = 0;
+        entities[ai_idx].channel_target = None;
+        messages.push(GameMessage::level_event(format!("{}'s channeled attack fizzles.", name)));
+    } else if entities[ai_idx].channel_turns_remaining == 0 {
+        entities[ai_idx].channel_target = None;
+        let damage_percent = object_registry.get_object(&entities[ai_idx].object_id)
+            .and_then(|obj| obj.channel_damage_percent)
+            .unwrap_or(300);
+        if let Some(target_idx) = target_idx {
+            if let Some(msg) = attack_entity_scaled(entities, dungeon, ai_idx, target_idx, object_registry, consumables, damage_percent, rng) {
+                messages.push(msg);
+            }
+        }
+    } else {
+        messages.push(GameMessage::level_event(format!(
+            "{} is channeling... ({} turns left)",
+            name, entities[ai_idx].channel_turns_remaining
+        )));
+    }
+    
+    messages
+}
+
 // BFS pathfinding to find the next step towards target
 pub fn find_path_step(
     entities: &[Entity],
@@ -102,25 +493,66 @@ pub fn find_path_step(
     target_y: usize,
     entity_idx: usize,
 ) -> Option<(i32, i32)> {
+    path_step_within(entities, dungeon, &[], entity_idx, (start_x, start_y), (target_x, target_y), usize::MAX).0
+}
+
+/// Path step for a monster in the AI phase, charged against the turn's pathfinding budget
+/// (None = unlimited). Once the budget is spent, monsters fall back to a greedy step.
+fn budgeted_path_step(
+    entities: &[Entity],
+    dungeon: &Dungeon,
+    blocked_tiles: &[(usize, usize)],
+    entity_idx: usize,
+    target: (usize, usize),
+    budget_left: &mut Option<usize>,
+) -> Option<(i32, i32)> {
+    let start = (entities[entity_idx].x, entities[entity_idx].y);
+    let (step, expanded) = path_step_within(entities, dungeon, blocked_tiles, entity_idx, start, target, budget_left.unwrap_or(usize::MAX));
+    if let Some(left) = budget_left {
+        *left = left.saturating_sub(expanded);
+    }
+    step
+}
+
+/// BFS for the next step from `start` towards `target` that avoids `blocked_tiles`, giving up
+/// after expanding `max_expansions` tiles and taking a greedy step instead. Also returns the
+/// tiles expanded.
+fn path_step_within(
+    entities: &[Entity],
+    dungeon: &Dungeon,
+    blocked_tiles: &[(usize, usize)],
+    entity_idx: usize,
+    start: (usize, usize),
+    target: (usize, usize),
+    max_expansions: usize,
+) -> (Option<(i32, i32)>, usize) {
     use std::collections::{VecDeque, HashSet, HashMap};
+    let (start_x, start_y) = start;
+    let (target_x, target_y) = target;
     
     // If already adjacent, return direct move
     let dx = target_x as i32 - start_x as i32;
     let dy = target_y as i32 - start_y as i32;
     
     if dx.abs() <= 1 && dy.abs() <= 1 {
-        return Some((dx.signum(), dy.signum()));
+        return (Some((dx.signum(), dy.signum())), 0);
     }
     
     // BFS to find path
     let mut queue = VecDeque::new();
     let mut visited = HashSet::new();
     let mut parent = HashMap::new();
+    let mut expanded = 0;
     
     queue.push_back((start_x, start_y));
     visited.insert((start_x, start_y));
     
     while let Some((x, y)) = queue.pop_front() {
+        if expanded >= max_expansions {
+            return (greedy_step(entities, dungeon, blocked_tiles, entity_idx, target), expanded);
+        }
+        expanded += 1;
+        
         if x == target_x && y == target_y {
             // Reconstruct path to find first step
             let mut current = (target_x, target_y);
@@ -138,7 +570,7 @@ pub fn find_path_step(
             if let Some(&(first_x, first_y)) = path.last() {
                 let step_dx = first_x as i32 - start_x as i32;
                 let step_dy = first_y as i32 - start_y as i32;
-                return Some((step_dx.signum(), step_dy.signum()));
+                return (Some((step_dx.signum(), step_dy.signum())), expanded);
             }
             break;
         }
@@ -165,14 +597,10 @@ pub fn find_path_step(
                 continue;
             }
             
-            // Check if position is occupied by another entity (except target)
-            if entities.iter().any(|e| {
-                e.id != entities[entity_idx].id && 
-                e.x == *nx && 
-                e.y == *ny && 
-                e.is_alive() &&
-                !(e.x == target_x && e.y == target_y) // Allow target position
-            }) {
+            if blocks_path(entities, entity_idx, (*nx, *ny), target) {
+                continue;
+            }
+            if blocked_tiles.contains(&(*nx, *ny)) && (*nx, *ny) != target {
                 continue;
             }
             
@@ -184,16 +612,45 @@ pub fn find_path_step(
     
     // If no path found, try direct movement
     if dx != 0 || dy != 0 {
-        Some((dx.signum(), dy.signum()))
+        (Some((dx.signum(), dy.signum())), expanded)
     } else {
-        None
+        (None, expanded)
+    }
+}
+
+/// Whether another entity stands on `tile` in the mover's way (the target's own tile never counts)
+fn blocks_path(entities: &[Entity], entity_idx: usize, tile: (usize, usize), target: (usize, usize)) -> bool {
+    tile != target && entities.iter().any(|e| {
+        e.id != entities[entity_idx].id &&
+        !passes_through(&entities[entity_idx], e) &&
+        (e.x, e.y) == tile &&
+        e.is_alive()
+    })
+}
+
+/// Cheap step towards the target without searching: along the axis with the larger gap
+/// if that tile is free, otherwise along the other one (None if both are blocked)
+fn greedy_step(entities: &[Entity], dungeon: &Dungeon, blocked_tiles: &[(usize, usize)], entity_idx: usize, target: (usize, usize)) -> Option<(i32, i32)> {
+    let (x, y) = (entities[entity_idx].x, entities[entity_idx].y);
+    let dx = target.0 as i32 - x as i32;
+    let dy = target.1 as i32 - y as i32;
+    let mut steps = [(dx.signum(), 0), (0, dy.signum())];
+    if dy.abs() > dx.abs() {
+        steps.reverse();
     }
+    steps.into_iter()
+        .filter(|&step| step != (0, 0))
+        .find(|&(step_x, step_y)| {
+            let tile = ((x as i32 + step_x) as usize, (y as i32 + step_y) as usize);
+            dungeon.is_walkable(tile.0, tile.1) && !blocked_tiles.contains(&tile) && !blocks_path(entities, entity_idx, tile, target)
+        })
 }
 
 // Helper function to move an entity (extracted from GameState for reuse)
 fn move_entity(
     entities: &mut [Entity],
     dungeon: &Dungeon,
+    blocked_tiles: &[(usize, usize)],
     entity_idx: usize,
     dx: i32,
     dy: i32,
@@ -202,36 +659,22 @@ fn move_entity(
         return;
     }
     
-    // Update facing direction based on horizontal movement
-    if dx > 0 {
-        // Moving right
-        entities[entity_idx].facing_right = true;
-    } else if dx < 0 {
-        // Moving left
-        entities[entity_idx].facing_right = false;
-    }
-    // If dx == 0, keep current facing direction
+    // Update facing direction based on movement
+    entities[entity_idx].face(dx, dy);
     
     let entity = &entities[entity_idx];
-    let new_x = entity.x as i32 + dx;
-    let new_y = entity.y as i32 + dy;
     
-    if new_x >= 0 && new_y >= 0 {
-        let new_x = new_x as usize;
-        let new_y = new_y as usize;
+    // Check range, bounds and walkability
+    if dungeon.validate_move(entity, dx, dy) {
+        let new_x = (entity.x as i32 + dx) as usize;
+        let new_y = (entity.y as i32 + dy) as usize;
         
-        // Check bounds
-        if new_x >= dungeon.width || new_y >= dungeon.height {
-            return;
-        }
-        
-        // Check if tile is walkable
-        if !dungeon.is_walkable(new_x, new_y) {
+        if blocked_tiles.contains(&(new_x, new_y)) {
             return;
         }
         
         // Check if there's another entity at the target position
-        if entities.iter().any(|e| e.id != entities[entity_idx].id && e.x == new_x && e.y == new_y && e.is_alive()) {
+        if entities.iter().any(|e| e.id != entity.id && !passes_through(entity, e) && e.x == new_x && e.y == new_y && e.is_alive()) {
             return;  // Can't move through other entities
         }
         
@@ -241,3 +684,353 @@ fn move_entity(
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_default_config;
+    use crate::dungeon::tests::dungeon_from;
+    use crate::game_object::GameObject;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    
+    fn orc(id: &str, x: usize, y: usize) -> Entity {
+        Entity::new(id.to_string(), x, y, "orc".to_string(), 5, 0, 0, 0, 100, 50, EntityController::AI)
+    }
+    
+    /// The default config's objects, with the orc changed by `edit`
+    fn registry_with(edit: impl Fn(&mut GameObject)) -> GameObjectRegistry {
+        let mut config = create_default_config();
+        config.game_objects.iter_mut().filter(|obj| obj.id == "orc").for_each(edit);
+        GameObjectRegistry::load_from_config(&config)
+    }
+    
+    /// The default config's objects, with orcs lashing out when trapped or not
+    fn registry(attacks_when_trapped: bool) -> GameObjectRegistry {
+        registry_with(|orc| orc.attacks_when_trapped = Some(attacks_when_trapped))
+    }
+    
+    fn player(x: usize, y: usize) -> Entity {
+        Entity::new("p1".to_string(), x, y, "player".to_string(), 10, 0, 0, 0, 100, 100, EntityController::Player)
+    }
+    
+    fn ai_turn(entities: &mut [Entity], dungeon: &Dungeon, registry: &GameObjectRegistry) {
+        process_ai_turns(entities, dungeon, registry, &mut Vec::new(), None, &[], &mut StdRng::seed_from_u64(1));
+    }
+    
+    #[test]
+    fn boxed_in_monsters_are_flagged_until_they_can_move() {
+        // Two orcs filling a dead-end corridor block each other in
+        let dungeon = dungeon_from(&["####", "#..#", "####"]);
+        let registry = registry(false);
+        let mut entities = vec![orc("a", 1, 1), orc("b", 2, 1)];
+        
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert!(entities.iter().all(|e| e.boxed_in));
+        assert!(entities.iter().all(|e| e.current_health == e.max_health));
+        
+        entities[1].current_health = 0;
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert!(!entities[0].boxed_in);
+    }
+    
+    #[test]
+    fn boxed_in_monsters_can_attack_allies_to_break_the_stalemate() {
+        let dungeon = dungeon_from(&["####", "#..#", "####"]);
+        let registry = registry(true);
+        let mut entities = vec![orc("a", 1, 1), orc("b", 2, 1)];
+        
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert!(entities.iter().any(|e| e.current_health < e.max_health));
+    }
+    
+    #[test]
+    fn a_monster_next_to_a_player_is_not_boxed_in() {
+        let dungeon = dungeon_from(&["####", "#..#", "####"]);
+        let registry = registry(false);
+        let mut entities = vec![orc("a", 1, 1), player(2, 1)];
+        
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert!(!entities[0].boxed_in);
+        assert!(entities[1].current_health < entities[1].max_health);
+    }
+    
+    #[test]
+    fn an_idle_patroller_loops_through_its_waypoints() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#...#", "#...#", "#####"]);
+        let registry = registry(false);
+        let route = vec![(1, 1), (3, 1), (3, 3), (1, 3)];
+        let mut patroller = orc("a", 1, 1);
+        patroller.patrol_route = route.clone();
+        let mut entities = vec![patroller];
+        
+        let mut visited = Vec::new();
+        for _ in 0..12 {
+            ai_turn(&mut entities, &dungeon, &registry);
+            let position = (entities[0].x, entities[0].y);
+            if route.contains(&position) && visited.last() != Some(&position) {
+                visited.push(position);
+            }
+        }
+        assert_eq!(visited, vec![(3, 1), (3, 3), (1, 3), (1, 1), (3, 1), (3, 3)]);
+    }
+    
+    #[test]
+    fn a_completed_channel_releases_a_heavy_hit() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#####"]);
+        let registry = registry_with(|orc| orc.channel_turns = Some(2));
+        let mut entities = vec![orc("a", 1, 1), player(2, 1)];
+        
+        for turns_left in [2, 1] {
+            ai_turn(&mut entities, &dungeon, &registry);
+            assert_eq!(entities[0].channel_turns_remaining, turns_left);
+            assert_eq!(entities[0].channel_target.as_deref(), Some("p1"));
+            assert_eq!(entities[1].current_health, 100);
+        }
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert_eq!(entities[0].channel_turns_remaining, 0);
+        // Three times the orc's 5 attack
+        assert_eq!(entities[1].current_health, 85);
+    }
+    
+    #[test]
+    fn a_channel_fizzles_when_the_target_steps_away() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#####"]);
+        let registry = registry_with(|orc| orc.channel_turns = Some(2));
+        let mut entities = vec![orc("a", 1, 1), player(2, 1)];
+        
+        ai_turn(&mut entities, &dungeon, &registry);
+        entities[1].x = 3;
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert_eq!(entities[0].channel_turns_remaining, 0);
+        assert!(entities[0].channel_target.is_none());
+        assert_eq!((entities[0].x, entities[0].y), (1, 1));
+        assert_eq!(entities[1].current_health, 100);
+    }
+    
+    #[test]
+    fn a_cleric_heals_a_wounded_neighbour_instead_of_attacking() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#####"]);
+        let mut config = create_default_config();
+        let mut cleric = GameObject::new("cleric".to_string(), "Cleric".to_string(), "character".to_string(), true, 0, 0);
+        cleric.monster = Some(true);
+        cleric.healing_power = Some(10);
+        config.game_objects.push(cleric);
+        let registry = GameObjectRegistry::load_from_config(&config);
+        let mut healer = orc("c", 2, 1);
+        healer.object_id = "cleric".to_string();
+        let mut wounded = orc("a", 1, 1);
+        wounded.current_health = 20;
+        let mut entities = vec![healer, wounded, player(3, 1)];
+        
+        let messages = process_ai_turns(&mut entities, &dungeon, &registry, &mut Vec::new(), None, &[], &mut StdRng::seed_from_u64(1));
+        assert_eq!(entities[1].current_health, 30);
+        assert_eq!(entities[2].current_health, entities[2].max_health);
+        assert!(messages.iter().any(|msg| msg.text.contains("Cleric") && msg.target.as_deref() == Some("Orc")));
+    }
+    
+    #[test]
+    fn an_idle_monster_drifts_to_where_it_last_saw_a_player() {
+        let dungeon = dungeon_from(&["##########", "#........#", "#........#", "##########"]);
+        let registry = registry(false);
+        let mut monster = orc("a", 1, 1);
+        monster.last_seen_player = Some((8, 2));
+        let mut entities = vec![monster];
+        
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..60 {
+            if entities[0].last_seen_player.is_none() {
+                break;
+            }
+            process_ai_turns(&mut entities, &dungeon, &registry, &mut Vec::new(), None, &[], &mut rng);
+        }
+        // The memory is only cleared on arrival
+        assert!(entities[0].last_seen_player.is_none());
+        assert_eq!((entities[0].x, entities[0].y), (8, 2));
+    }
+    
+    #[test]
+    fn a_noclip_monster_passes_an_ally_that_stops_a_normal_one() {
+        let dungeon = dungeon_from(&["#######", "#.....#", "#######"]);
+        let registry = registry(false);
+        
+        for noclip in [false, true] {
+            let mut chaser = orc("a", 1, 1);
+            chaser.noclip = noclip;
+            let mut entities = vec![chaser, orc("b", 2, 1), player(5, 1)];
+            ai_turn(&mut entities, &dungeon, &registry);
+            let expected_x = if noclip { 2 } else { 1 };
+            assert_eq!((entities[0].x, entities[0].y), (expected_x, 1), "noclip: {}", noclip);
+        }
+    }
+    
+    #[test]
+    fn a_leashed_idle_monster_stays_in_its_room() {
+        // A room with a corridor leading out of it
+        let dungeon = dungeon_from(&["##########", "#...######", "#........#", "#...######", "##########"]);
+        let registry = registry(false);
+        let room = crate::dungeon::Room { x: 1, y: 1, width: 3, height: 3 };
+        let mut monster = orc("a", 3, 2);
+        monster.home_room = Some(room.clone());
+        let mut entities = vec![monster];
+        
+        let mut rng = StdRng::seed_from_u64(1);
+        for turn in 0..100 {
+            process_ai_turns(&mut entities, &dungeon, &registry, &mut Vec::new(), None, &[], &mut rng);
+            assert!(room.contains(entities[0].x, entities[0].y), "left the room on turn {}", turn);
+        }
+    }
+    
+    #[test]
+    fn a_passive_monster_ignores_players_until_hit() {
+        let dungeon = dungeon_from(&["########", "#......#", "########"]);
+        let registry = registry_with(|orc| orc.hostile_on_sight = Some(false));
+        let mut entities = vec![orc("a", 1, 1), player(2, 1)];
+        
+        for _ in 0..10 {
+            ai_turn(&mut entities, &dungeon, &registry);
+        }
+        assert_eq!(entities[1].current_health, entities[1].max_health);
+        
+        attack_entity(&mut entities, &dungeon, 1, 0, &registry, &mut Vec::new(), &mut StdRng::seed_from_u64(1));
+        assert!(entities[0].provoked);
+        (entities[0].x, entities[1].x) = (1, 3);
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert_eq!(entities[0].x, 2);
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert!(entities[1].current_health < entities[1].max_health);
+    }
+    
+    #[test]
+    fn a_lone_wounded_monster_flees_while_a_supported_one_fights() {
+        let dungeon = dungeon_from(&["########", "#......#", "#......#", "########"]);
+        let registry = registry_with(|orc| orc.flee_health_percent = Some(50));
+        let wounded = || {
+            let mut monster = orc("a", 3, 1);
+            monster.current_health = 10;
+            monster
+        };
+        
+        let mut alone = vec![wounded(), player(2, 1)];
+        ai_turn(&mut alone, &dungeon, &registry);
+        assert_eq!((alone[0].x, alone[0].y), (4, 1));
+        assert_eq!(alone[1].current_health, alone[1].max_health);
+        
+        let mut supported = vec![wounded(), player(2, 1), orc("b", 6, 2)];
+        ai_turn(&mut supported, &dungeon, &registry);
+        assert_eq!((supported[0].x, supported[0].y), (3, 1));
+        assert!(supported[1].current_health < supported[1].max_health);
+    }
+    
+    #[test]
+    fn a_monster_with_a_vision_cone_only_spots_players_in_front() {
+        let registry = registry_with(|orc| {
+            orc.vision_cone_degrees = Some(90);
+            orc.wander_pause_percent = Some(100);
+        });
+        let dungeon = dungeon_from(&["###########", "#.........#", "###########"]);
+        for (player_x, spotted) in [(8, true), (2, false)] {
+            let mut entities = vec![player(player_x, 1), orc("orc", 5, 1)];
+            entities[1].facing = Direction::Right;
+            ai_turn(&mut entities, &dungeon, &registry);
+            assert_eq!(entities[1].last_seen_player.is_some(), spotted, "player at x {}", player_x);
+            assert_eq!(entities[1].x, if spotted { 6 } else { 5 });
+        }
+    }
+    
+    #[test]
+    fn the_pathfinding_budget_caps_the_work_across_monsters() {
+        let dungeon = dungeon_from(&[
+            "####################",
+            "#..................#",
+            "#..................#",
+            "#..................#",
+            "#..................#",
+            "####################",
+        ]);
+        let entities = vec![player(18, 4), orc("near", 10, 1), orc("far", 1, 1)];
+        let (_, unlimited) = path_step_within(&entities, &dungeon, &[], 1, (10, 1), (18, 4), usize::MAX);
+        assert!(unlimited > 10);
+        
+        // The first monster spends the budget, the next gets only a greedy step
+        let mut budget_left = Some(10);
+        assert!(budgeted_path_step(&entities, &dungeon, &[], 1, (18, 4), &mut budget_left).is_some());
+        assert_eq!(budget_left, Some(0));
+        assert_eq!(budgeted_path_step(&entities, &dungeon, &[], 2, (18, 4), &mut budget_left), Some((1, 0)));  // Along the longer axis
+        assert_eq!(budget_left, Some(0));
+    }
+    
+    #[test]
+    fn a_monster_keeps_hunting_for_a_while_after_losing_the_player() {
+        let registry = registry_with(|orc| {
+            orc.aggro_persist_turns = Some(3);
+            orc.wander_pause_percent = Some(100);
+        });
+        let dungeon = dungeon_from(&["########################", "#......................#", "########################"]);
+        let mut entities = vec![player(3, 1), orc("orc", 8, 1)];
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert_eq!(entities[1].x, 7);
+        
+        // The player slips away out of range; the orc carries on to where it last saw them
+        entities[0].x = 21;
+        for x in [6, 5, 4] {
+            ai_turn(&mut entities, &dungeon, &registry);
+            assert_eq!(entities[1].x, x);
+            assert_eq!(entities[1].last_seen_player, Some((3, 1)));
+        }
+        ai_turn(&mut entities, &dungeon, &registry);
+        assert_eq!(entities[1].x, 4);
+        assert_eq!(entities[1].last_seen_player, None);
+    }
+    
+    #[test]
+    fn wandering_monsters_tend_to_keep_their_direction() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let steps: Vec<(i32, i32)> = (0..1000).map(|_| wander_step(Some((1, 0)), 75, &mut rng)).collect();
+        let kept = steps.iter().filter(|&&step| step == (1, 0)).count();
+        
+        assert!(kept > 750, "kept going only {} times", kept);  // 75% plus a third of the turns
+        assert!(!steps.contains(&(-1, 0)));  // Never straight back
+        
+        // Without persistence, carrying on is just one of the three ways that aren't back
+        let kept = (0..1000).filter(|_| wander_step(Some((1, 0)), 0, &mut rng) == (1, 0)).count();
+        assert!((250..420).contains(&kept), "kept going {} times", kept);
+    }
+    
+    #[test]
+    fn an_idle_monster_that_always_pauses_never_wanders() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#...#", "#...#", "#####"]);
+        for (pause_percent, stays) in [(100, true), (0, false)] {
+            let registry = registry_with(|orc| orc.wander_pause_percent = Some(pause_percent));
+            let mut entities = vec![orc("a", 2, 2)];
+            
+            let mut moved = false;
+            for _ in 0..100 {
+                ai_turn(&mut entities, &dungeon, &registry);
+                moved |= (entities[0].x, entities[0].y) != (2, 2);
+            }
+            assert_eq!(moved, !stays);
+        }
+    }
+    
+    #[test]
+    fn a_focus_fire_monster_goes_for_the_weakest_player_it_sees() {
+        let dungeon = dungeon_from(&["##########", "#........#", "##########"]);
+        for focus_fire in [false, true] {
+            let registry = registry_with(|orc| orc.focus_fire = Some(focus_fire));
+            let mut wounded = player(8, 1);
+            wounded.id = "p2".to_string();
+            wounded.current_health = 20;
+            let mut entities = vec![orc("a", 4, 1), player(3, 1), wounded];
+            
+            ai_turn(&mut entities, &dungeon, &registry);
+            if focus_fire {
+                assert_eq!(entities[0].x, 5);  // Walks away from the healthy player next to it
+                assert_eq!(entities[1].current_health, 100);
+            } else {
+                assert_eq!(entities[0].x, 4);
+                assert!(entities[1].current_health < 100);
+            }
+        }
+    }
+}
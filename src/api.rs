@@ -1,32 +1,59 @@
 use axum::{
-    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    http::StatusCode,
     response::{Html, Json, Response},
 };
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
-use std::fs::OpenOptions;
-use std::io::Write;
-
-fn log_debug(msg: &str) {
-    eprintln!("{}", msg);
-    if let Ok(mut file) = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("server_debug.log")
-    {
-        let _ = writeln!(file, "{}", msg);
-    }
-}
 
-use crate::game_state::GameState;
-use crate::message::{GameMessage, PlayerCommand};
-use crate::entity::EntityController;
-use crate::game_object::schema;
+use crate::game_state::{GameState, GameStatus, LobbyStatus, RoomContents, RunRecord, WinProgress};
+use crate::message::{GameMessage, PlayerCommand, ServerMessage};
+use crate::entity::{Direction, Entity, EntityController, StatusEffect};
+use crate::consumable::Consumable;
+use crate::chest::Chest;
+use crate::player::InventoryItem;
+use crate::combat::estimate_damage;
+use crate::game_object::{schema, Tint};
+use crate::metrics::{MetricsSnapshot, METRICS};
+use crate::replay::{Replay, ReplayAction};
+use crate::wire_format::WireFormat;
+use crate::logging;
 
 pub type SharedState = Arc<Mutex<GameState>>;
-pub type Tx = broadcast::Sender<String>;
+pub type Tx = broadcast::Sender<UpdateEvent>;
+
+/// Result of one handled command, broadcast to every connection. Each
+/// connection turns it into a GameUpdate built for its own player.
+#[derive(Debug, Clone)]
+pub struct UpdateEvent {
+    pub messages: Vec<GameMessage>,
+    pub level_complete: bool,
+    pub restart_confirmed: bool,
+    pub player_id: String,  // Player whose command caused this event
+    pub command_id: Option<String>,  // That command's ID, acked only to its sender
+    pub examine_result: Option<ExamineResult>,  // Answer to that player's examine command, sent only to them
+}
+
+impl UpdateEvent {
+    /// Fold a later event into this one so both go out as a single update: messages are kept
+    /// in order, flags stick once set, and `for_player`'s own command stays the one acked
+    fn absorb(&mut self, later: UpdateEvent, for_player: &str) {
+        self.messages.extend(later.messages);
+        self.level_complete |= later.level_complete;
+        self.restart_confirmed |= later.restart_confirmed;
+        let mut examine_result = if self.player_id == for_player { self.examine_result.take() } else { None };
+        if later.player_id == for_player && later.examine_result.is_some() {
+            examine_result = later.examine_result;
+        }
+        if later.player_id == for_player || self.player_id != for_player {
+            self.player_id = later.player_id;
+            self.command_id = later.command_id;
+        }
+        self.examine_result = examine_result;
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EntityData {
@@ -37,11 +64,56 @@ pub struct EntityData {
     pub controller: EntityController,
     pub current_health: u32,
     pub max_health: u32,
-    pub attack: i32,
-    pub defense: i32,
+    pub attack: i32,  // Including equipment and active buffs
+    pub defense: i32,  // Including equipment and active buffs
     pub crit_chance_percent: u32,
     pub crit_damage_percent: u32,
     pub facing_right: bool,  // true = facing right, false = facing left (needs mirroring)
+    pub facing: Direction,  // "up", "down", "left" or "right" - where attack animations point
+    pub channel_turns_remaining: u32,  // Turns until a telegraphed attack lands (0 = not channeling)
+    pub channel_target: Option<String>,  // Entity ID the telegraphed attack is aimed at
+    pub dying: bool,  // Killed this tick - play the death animation, it's gone in the next update
+    pub inventory: Vec<InventoryItem>,  // Carried item stacks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weapon: Option<InventoryItem>,  // Equipped weapon, with its durability left
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub armor: Option<InventoryItem>,  // Equipped armor, with its durability left
+    pub resistances: std::collections::HashMap<String, i32>,  // Damage type -> percent reduction (negative = weakness)
+    pub status_effects: Vec<StatusEffect>,  // Active temporary effects (e.g., confused, shrine buffs) with turns left
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique_name: Option<String>,  // Flavor name ("Orc: Grolok") for monsters with a name pool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tint: Option<Tint>,  // Color multipliers to draw the sprite with (None = as drawn)
+}
+
+/// Expected outcome of trading blows with an adjacent monster (normal hits, no crits)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CombatPreview {
+    pub entity_id: String,  // The adjacent monster
+    pub x: usize,
+    pub y: usize,
+    pub damage_dealt: (u32, u32),  // Min/max damage the current player would deal to it
+    pub damage_taken: (u32, u32),  // Min/max damage it would deal back
+}
+
+/// Everything about one tile, the answer to an `examine` command
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExamineResult {
+    pub x: usize,
+    pub y: usize,
+    pub tile: Option<String>,  // Tile's name (None = outside the map)
+    pub walkable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<EntityData>,  // Whoever stands there, with full stats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_name: Option<String>,  // Their display name (unique name for named monsters)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chest: Option<ChestData>,  // Chest, door or lever there
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<ConsumableData>,  // Item lying there
+    pub is_stairs: bool,  // Stairs down to the next level
+    pub is_stairs_up: bool,  // Stairs back to the previous level
+    pub description: String,  // All of the above as readable text (for screen readers and tooltips)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -50,6 +122,8 @@ pub struct ConsumableData {
     pub object_id: String,  // Reference to GameObject - client looks up sprites from this
     pub x: usize,
     pub y: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<String>,  // Item rarity from its GameObject (None = unspecified, treat as common)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -68,6 +142,17 @@ pub struct PlayerData {
     pub name: String,  // Display name (from GameObject or player_id)
     pub is_alive: bool,
     pub has_acted_this_turn: bool,  // Whether this player has taken their turn this round
+    pub confirmed_stairs: bool,  // Confirmed descending - the party goes once every alive player has
+    pub confirmed_stairs_up: bool,  // Confirmed going back up a level
+}
+
+/// One step of the current round: an alive player, or the monsters' phase (always last)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TurnOrderEntry {
+    pub id: String,  // Player ID, or "ai" for the monsters' phase
+    pub name: String,
+    pub is_ai: bool,
+    pub has_acted: bool,  // Done for this round (the AI phase never shows as done - it ends the round)
 }
 
 // Lightweight tile data for transmission (without sprites array)
@@ -85,17 +170,49 @@ pub struct GameUpdate {
     pub chests: Vec<ChestData>,  // All chests on the map
     pub players: Vec<PlayerData>,  // List of all players
     pub current_player_id: Option<String>,  // ID of the current player (for highlighting)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub my_position: Option<(usize, usize)>,  // Current player's tile, for centering the viewport
     pub width: usize,
     pub height: usize,
     pub messages: Vec<GameMessage>,  // Game messages (combat, level events, system)
     pub stairs_position: Option<(usize, usize)>,  // Position of stairs (goal)
     pub on_stairs: bool,  // Whether the current player is on stairs
+    pub stairs_up_position: Option<(usize, usize)>,  // Position of stairs back to the previous level
+    pub on_stairs_up: bool,  // Whether the current player is on the stairs up
+    pub current_level: u32,  // Level number being played
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub music_track: Option<String>,  // Level's background music (None = client default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ambiance: Option<String>,  // Level's ambient sound loop (None = silence)
     pub level_complete: bool,  // Whether level is complete (all players confirmed)
     pub all_players_dead: bool,  // Whether all players are dead
     pub restart_confirmed: bool,  // Whether all players confirmed restart
+    pub game_over: bool,  // The run is over (permadeath) - only a new game command is accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waiting_for_players: Option<LobbyStatus>,  // In the lobby: who's connected and ready (None = playing)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scoreboard: Vec<RunRecord>,  // Finished runs, oldest first
     pub turn_phase: String,  // Current turn phase: "player" or "ai"
     pub current_turn: u32,  // Current turn number
+    pub server_time_ms: u64,  // Milliseconds since the server started when this update was built (never decreases)
     pub is_my_turn: bool,  // Whether it's the current player's turn (they haven't acted yet)
+    pub turn_order: Vec<TurnOrderEntry>,  // Who still has to act this round, ending with the AI phase
+    pub explored_rooms: Vec<crate::dungeon::Room>,  // Rooms the current player (or with shared vision, the party) has explored
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensed_loot: Vec<(usize, usize)>,  // Treasure sense: unopened chests and consumables outside explored rooms (current player only)
+    pub turns_remaining: Option<u32>,  // Turns left before the level's turn limit (None = no limit)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monsters_remaining: Option<usize>,  // Alive monsters, only on levels whose stairs need a full clear
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub win_progress: Option<WinProgress>,  // Level's win condition and what's left of it (None = reach the stairs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub combat_preview: Option<Vec<CombatPreview>>,  // Damage estimates against adjacent monsters (current player only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ack_command_id: Option<String>,  // command_id of this player's command that produced this update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examine_result: Option<ExamineResult>,  // Answer to this player's examine command
 }
 
 /// Convert GameState to GameUpdate for a specific player
@@ -105,49 +222,18 @@ pub fn game_state_to_update(
 ) -> GameUpdate {
     // Convert entities to EntityData
     let entities: Vec<EntityData> = game.entities.iter()
-        .filter(|e| e.is_alive())  // Only send alive entities
-        .map(|entity| {
-            EntityData {
-                id: entity.id.clone(),
-                object_id: entity.object_id.clone(),
-                x: entity.x,
-                y: entity.y,
-                controller: entity.controller,
-                current_health: entity.current_health,
-                max_health: entity.max_health,
-                attack: entity.attack,
-                defense: entity.defense,
-                crit_chance_percent: entity.crit_chance_percent,
-                crit_damage_percent: entity.crit_damage_percent,
-                facing_right: entity.facing_right,
-            }
-        })
+        .filter(|e| e.is_alive() || e.dying)  // Only send alive entities (and ones dying this tick)
+        .map(|entity| entity_data(game, entity))
         .collect();
     
     // Convert consumables to ConsumableData
     let consumables: Vec<ConsumableData> = game.consumables.iter()
-        .map(|consumable| {
-            ConsumableData {
-                id: consumable.id.clone(),
-                object_id: consumable.object_id.clone(),
-                x: consumable.x,
-                y: consumable.y,
-            }
-        })
+        .map(|consumable| consumable_data(game, consumable))
         .collect();
     
     // Convert chests to ChestData
     let chests: Vec<ChestData> = game.chests.iter()
-        .map(|chest| {
-            ChestData {
-                id: chest.id.clone(),
-                object_id: chest.object_id.clone(),  // Chest object ID (contains interactable data)
-                open_object_id: None,  // Deprecated - no longer used
-                x: chest.x,
-                y: chest.y,
-                is_open: chest.is_open,  // Current state: false = closed (sprites[0]), true = open (sprites[1])
-            }
-        })
+        .map(chest_data)
         .collect();
     
     // Check if current player is on stairs
@@ -158,6 +244,34 @@ pub fn game_state_to_update(
     } else {
         false
     };
+    let on_stairs_up = player_id.is_some_and(|pid| {
+        game.stairs_up_position.is_some_and(|(sx, sy)| {
+            game.entities.iter().any(|e| e.id == pid && e.x == sx && e.y == sy)
+        })
+    });
+    
+    // Current player's position so the client doesn't have to search the entity list
+    let my_position = player_id
+        .and_then(|pid| game.entities.iter().find(|e| e.id == pid && e.controller == EntityController::Player))
+        .map(|e| (e.x, e.y));
+    
+    // Damage estimates against monsters the current player can attack right now
+    let combat_preview = player_id
+        .and_then(|pid| game.entities.iter().position(|e| e.id == pid && e.controller == EntityController::Player && e.is_alive()))
+        .map(|player_idx| {
+            let player = &game.entities[player_idx];
+            crate::ai::adjacent_entities(&game.entities, player_idx).into_iter()
+                .map(|idx| &game.entities[idx])
+                .filter(|e| e.controller == EntityController::AI)
+                .map(|monster| CombatPreview {
+                    entity_id: monster.id.clone(),
+                    x: monster.x,
+                    y: monster.y,
+                    damage_dealt: estimate_damage(player, monster),
+                    damage_taken: estimate_damage(monster, player),
+                })
+                .collect()
+        });
     
     // Build players list
     let players: Vec<PlayerData> = game.entities.iter()
@@ -170,16 +284,69 @@ pub fn game_state_to_update(
             
             // Check if this player has acted this turn
             let has_acted = game.players_acted_this_turn.contains(&entity.id);
+            let player_state = game.players.get(&entity.id);
             
             PlayerData {
                 id: entity.id.clone(),
                 name,
                 is_alive: entity.is_alive(),
                 has_acted_this_turn: has_acted,
+                confirmed_stairs: player_state.is_some_and(|player| player.confirmed_stairs),
+                confirmed_stairs_up: player_state.is_some_and(|player| player.confirmed_stairs_up),
             }
         })
         .collect();
     
+    // Round order: players still to act, players who already acted, then the monsters
+    let mut turn_order: Vec<TurnOrderEntry> = players.iter()
+        .filter(|p| p.is_alive)
+        .map(|p| TurnOrderEntry {
+            id: p.id.clone(),
+            name: p.name.clone(),
+            is_ai: false,
+            has_acted: p.has_acted_this_turn,
+        })
+        .collect();
+    turn_order.sort_by_key(|entry| entry.has_acted);
+    turn_order.push(TurnOrderEntry {
+        id: "ai".to_string(),
+        name: "Monsters".to_string(),
+        is_ai: true,
+        has_acted: false,
+    });
+    
+    let mut explored_room_indices: Vec<usize> = player_id
+        .map(|pid| game.explored_rooms(pid).into_iter().collect())
+        .unwrap_or_default();
+    explored_room_indices.sort_unstable();
+    
+    // Treasure sense reveals loot the player hasn't come across yet
+    let senses_loot = player_id
+        .filter(|pid| game.players.get(*pid).is_some_and(|player| player.reveals_loot))
+        .and_then(|pid| game.entities.iter().find(|e| e.id == pid))
+        .is_some_and(|player| player.is_alive());
+    let sensed_loot = if senses_loot {
+        let unexplored = |&(x, y): &(usize, usize)| {
+            !explored_room_indices.iter()
+                .filter_map(|&idx| game.dungeon.rooms.get(idx))
+                .any(|room| room.contains(x, y))
+        };
+        game.chests.iter()
+            .filter(|chest| !chest.is_open)
+            .filter(|chest| game.object_registry.get_object(&chest.object_id)
+                .is_some_and(|obj| obj.object_type == "chest"))
+            .map(|chest| (chest.x, chest.y))
+            .chain(game.consumables.iter().map(|consumable| (consumable.x, consumable.y)))
+            .filter(unexplored)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    
+    let explored_rooms = explored_room_indices.into_iter()
+        .filter_map(|idx| game.dungeon.rooms.get(idx).cloned())
+        .collect();
+    
     // Determine if it's the current player's turn
     let is_my_turn = if let Some(pid) = player_id {
         game.turn_phase == crate::game_state::TurnPhase::PlayerPhase &&
@@ -206,20 +373,139 @@ pub fn game_state_to_update(
         chests,
         players,
         current_player_id: player_id.map(|s| s.to_string()),
+        my_position,
         width: game.dungeon.width,
         height: game.dungeon.height,
         messages: Vec::new(),
         stairs_position: game.stairs_position,
         on_stairs,
+        stairs_up_position: game.stairs_up_position,
+        on_stairs_up,
+        current_level: game.current_level,
+        music_track: game.level_config.as_ref().and_then(|level| level.music_track.clone()),
+        ambiance: game.level_config.as_ref().and_then(|level| level.ambiance.clone()),
         level_complete: false,
         all_players_dead: game.are_all_players_dead(),
         restart_confirmed: false,
+        game_over: game.status == GameStatus::GameOver,
+        waiting_for_players: game.lobby_status(),
+        scoreboard: game.scoreboard.clone(),
         turn_phase: match game.turn_phase {
             crate::game_state::TurnPhase::PlayerPhase => "player".to_string(),
             crate::game_state::TurnPhase::AIPhase => "ai".to_string(),
         },
         current_turn: game.current_turn,
+        server_time_ms: game.started_at.elapsed().as_millis() as u64,
         is_my_turn,
+        turn_order,
+        explored_rooms,
+        sensed_loot,
+        turns_remaining: game.turns_remaining(),
+        monsters_remaining: game.monsters_remaining(),
+        win_progress: game.win_progress(),
+        combat_preview,
+        ack_command_id: None,
+        examine_result: None,
+    }
+}
+
+fn entity_data(game: &GameState, entity: &Entity) -> EntityData {
+    EntityData {
+        id: entity.id.clone(),
+        object_id: entity.object_id.clone(),
+        x: entity.x,
+        y: entity.y,
+        controller: entity.controller,
+        current_health: entity.current_health,
+        max_health: entity.max_health,
+        attack: entity.effective_attack(),
+        defense: entity.effective_defense(),
+        crit_chance_percent: entity.crit_chance_percent,
+        crit_damage_percent: entity.crit_damage_percent,
+        facing_right: entity.facing_right,
+        facing: entity.facing,
+        channel_turns_remaining: entity.channel_turns_remaining,
+        channel_target: entity.channel_target.clone(),
+        dying: entity.dying,
+        inventory: game.players.get(&entity.id).map(|player| player.inventory.clone()).unwrap_or_default(),
+        weapon: game.players.get(&entity.id).and_then(|player| player.weapon.clone()),
+        armor: game.players.get(&entity.id).and_then(|player| player.armor.clone()),
+        resistances: entity.resistances.clone(),
+        status_effects: entity.status_effects.clone(),
+        unique_name: entity.unique_name.clone(),
+        tint: entity.tint,
+    }
+}
+
+fn consumable_data(game: &GameState, consumable: &Consumable) -> ConsumableData {
+    ConsumableData {
+        id: consumable.id.clone(),
+        object_id: consumable.object_id.clone(),
+        x: consumable.x,
+        y: consumable.y,
+        rarity: game.object_registry.get_object(&consumable.object_id).and_then(|obj| obj.rarity.clone()),
+    }
+}
+
+fn chest_data(chest: &Chest) -> ChestData {
+    ChestData {
+        id: chest.id.clone(),
+        object_id: chest.object_id.clone(),  // Chest object ID (contains interactable data)
+        open_object_id: None,  // Deprecated - no longer used
+        x: chest.x,
+        y: chest.y,
+        is_open: chest.is_open || chest.is_active,  // Current state: false = closed (sprites[0]), true = open or switched on (sprites[1])
+    }
+}
+
+/// Describe the tile at (x, y) for an `examine` command. Only reads the state, so it can be
+/// answered at any time, whoever's turn it is.
+pub fn examine_tile(game: &GameState, x: usize, y: usize) -> ExamineResult {
+    let registry = &game.object_registry;
+    let name_of = |object_id: &str| registry.get_object(object_id)
+        .map(|obj| obj.name.clone())
+        .unwrap_or_else(|| object_id.to_string());
+    
+    let tile = game.dungeon.tiles.get(y).and_then(|row| row.get(x));
+    let entity = game.entities.iter().find(|e| e.x == x && e.y == y && e.is_alive());
+    let chest = game.chests.iter().find(|chest| chest.x == x && chest.y == y);
+    let item = game.consumables.iter().find(|consumable| consumable.x == x && consumable.y == y);
+    let is_stairs = game.stairs_position == Some((x, y));
+    let is_stairs_up = game.stairs_up_position == Some((x, y));
+    
+    let mut parts = vec![match tile {
+        Some(tile) => format!("{} ({})", name_of(&tile.tile_id), if tile.walkable { "walkable" } else { "impassable" }),
+        None => "Outside the map".to_string(),
+    }];
+    if let Some(entity) = entity {
+        parts.push(format!("{}: {}/{} HP, attack {}, defense {}",
+            entity.display_name(registry), entity.current_health, entity.max_health, entity.effective_attack(), entity.effective_defense()));
+    }
+    if let Some(chest) = chest {
+        parts.push(format!("{} ({})", name_of(&chest.object_id), if chest.is_open { "open" } else { "closed" }));
+    }
+    if let Some(item) = item {
+        parts.push(name_of(&item.object_id));
+    }
+    if is_stairs {
+        parts.push("Stairs down".to_string());
+    }
+    if is_stairs_up {
+        parts.push("Stairs up".to_string());
+    }
+    
+    ExamineResult {
+        x,
+        y,
+        tile: tile.map(|tile| name_of(&tile.tile_id)),
+        walkable: tile.is_some_and(|tile| tile.walkable),
+        entity: entity.map(|entity| entity_data(game, entity)),
+        entity_name: entity.map(|entity| entity.display_name(registry)),
+        chest: chest.map(chest_data),
+        item: item.map(|item| consumable_data(game, item)),
+        is_stairs,
+        is_stairs_up,
+        description: parts.join(". "),
     }
 }
 
@@ -231,65 +517,282 @@ pub async fn schema_endpoint() -> Json<schema::GameObjectSchema> {
     Json(schema::GameObjectSchema::generate())
 }
 
+pub async fn update_schema_endpoint() -> Json<crate::update_schema::UpdateSchema> {
+    Json(crate::update_schema::UpdateSchema::generate())
+}
+
+/// Endpoint listing supported player actions and their parameters (for dynamic client UI)
+pub async fn commands_endpoint() -> Json<&'static [crate::command::CommandSpec]> {
+    Json(crate::command::COMMANDS)
+}
+
 /// Endpoint to get game config (for client-side sprite lookups)
 pub async fn config_endpoint() -> Json<crate::config::GameConfig> {
     let config = match crate::config::GameConfig::load("game_config.toml") {
         Ok(cfg) => cfg,
-        Err(_) => {
-            // If config doesn't exist, create default
+        Err(e) => {
+            // If config doesn't exist, create default (a broken file is left alone for the user to fix)
             let default_config = create_default_config();
-            let _ = default_config.save("game_config.toml");
+            if e.is_not_found() {
+                let _ = default_config.save("game_config.toml");
+            }
             default_config
         }
     };
     Json(config)
 }
 
+/// A sprite sheet the client can load, with how to cut it into sprites
+#[derive(Serialize, Debug)]
+pub struct SpriteSheetInfo {
+    pub name: String,
+    pub path: String,  // URL the image is served from
+    pub sprite_size: crate::config::SpriteSize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u32>,
+}
+
+/// Endpoint listing the config's sprite sheets, in the order they're defined
+pub async fn sprite_sheets_endpoint() -> Result<Json<Vec<SpriteSheetInfo>>, (StatusCode, String)> {
+    let config = crate::config::GameConfig::load("game_config.toml")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not load config: {}", e)))?;
+    let sheets = config.sprite_sheets.into_iter()
+        .map(|sheet| SpriteSheetInfo {
+            name: sheet.name,
+            path: format!("/assets/{}", sheet.path),
+            sprite_size: sheet.sprite_size,
+            columns: sheet.columns,
+            rows: sheet.rows,
+        })
+        .collect();
+    Ok(Json(sheets))
+}
+
+/// Endpoint listing object IDs the config references but doesn't define (for the editor)
+pub async fn dangling_references_endpoint() -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    let config = crate::config::GameConfig::load("game_config.toml")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not load config: {}", e)))?;
+    Ok(Json(config.find_dangling_references()))
+}
+
+/// Endpoint with server metrics for operators (JSON)
+pub async fn metrics_endpoint(
+    axum::extract::State((state, _tx)): axum::extract::State<(SharedState, Tx)>,
+) -> Json<MetricsSnapshot> {
+    // Only copy the numbers out under the lock
+    let (current_turn, current_level, entity_count, monster_count) = {
+        let game = state.lock().unwrap();
+        let monster_count = game.entities.iter()
+            .filter(|e| e.controller == EntityController::AI && e.is_alive())
+            .count();
+        (game.current_turn, game.current_level, game.entities.len(), monster_count)
+    };
+    Json(METRICS.snapshot(current_turn, current_level, entity_count, monster_count))
+}
+
+/// Start recording a replay (restarts an ongoing recording). The run restarts on a fresh
+/// seed so the recording covers it from the start. Only when the config enables admin commands.
+pub async fn replay_start_endpoint(
+    axum::extract::State((state, tx)): axum::extract::State<(SharedState, Tx)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let message = {
+        let mut game = state.lock().unwrap();
+        if !game.admin_commands {
+            return Err((StatusCode::FORBIDDEN, "Admin commands are disabled (set admin_commands in the config)".to_string()));
+        }
+        game.start_recording()
+    };
+    logging::info!("[REPLAY] Recording started");
+    
+    // A level change as far as clients are concerned, so they reload the map
+    let event = UpdateEvent {
+        messages: vec![message],
+        level_complete: true,
+        restart_confirmed: false,
+        player_id: "replay".to_string(),
+        command_id: None,
+        examine_result: None,
+    };
+    if tx.send(event).is_ok() {
+        METRICS.broadcast_sent();
+    }
+    Ok(StatusCode::OK)
+}
+
+/// Throw away the whole run and start again from level 1 with every connected player
+/// (only when the config enables admin commands)
+pub async fn admin_new_game_endpoint(
+    axum::extract::State((state, tx)): axum::extract::State<(SharedState, Tx)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let message = {
+        let mut game = state.lock().unwrap();
+        if !game.admin_commands {
+            return Err((StatusCode::FORBIDDEN, "Admin commands are disabled (set admin_commands in the config)".to_string()));
+        }
+        game.record("admin", ReplayAction::NewGame);
+        game.new_game()
+    };
+    logging::info!("[ADMIN] Run reset to level 1");
+    
+    // A level change as far as clients are concerned, so they reload the map
+    let event = UpdateEvent {
+        messages: vec![message],
+        level_complete: true,
+        restart_confirmed: false,
+        player_id: "admin".to_string(),
+        command_id: None,
+        examine_result: None,
+    };
+    if tx.send(event).is_ok() {
+        METRICS.broadcast_sent();
+    }
+    Ok(StatusCode::OK)
+}
+
+/// What lies in each room of the current level (only when the config enables admin commands,
+/// since it gives away every monster and item)
+pub async fn admin_rooms_endpoint(
+    axum::extract::State((state, _tx)): axum::extract::State<(SharedState, Tx)>,
+) -> Result<Json<Vec<RoomContents>>, (StatusCode, String)> {
+    let game = state.lock().unwrap();
+    if !game.admin_commands {
+        return Err((StatusCode::FORBIDDEN, "Admin commands are disabled (set admin_commands in the config)".to_string()));
+    }
+    Ok(Json(game.room_contents()))
+}
+
+/// Where a stopped recording was saved
+#[derive(Serialize, Debug)]
+pub struct ReplaySaved {
+    pub path: String,
+    pub steps: usize,  // Joins, leaves and commands recorded
+}
+
+/// Stop recording and save the replay file (only when the config enables admin commands)
+pub async fn replay_stop_endpoint(
+    axum::extract::State((state, _tx)): axum::extract::State<(SharedState, Tx)>,
+) -> Result<Json<ReplaySaved>, (StatusCode, String)> {
+    let replay = {
+        let mut game = state.lock().unwrap();
+        if !game.admin_commands {
+            return Err((StatusCode::FORBIDDEN, "Admin commands are disabled (set admin_commands in the config)".to_string()));
+        }
+        game.recording.take().ok_or((StatusCode::CONFLICT, "Not recording".to_string()))?
+    };
+    let path = replay.save()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not save replay: {}", e)))?;
+    logging::info!("[REPLAY] Saved {} steps to {}", replay.entries.len(), path);
+    Ok(Json(ReplaySaved { path, steps: replay.entries.len() }))
+}
+
+/// Play a replay (the body is a saved replay file) on a fresh game, separate from the live
+/// one, and return the final state as seen by the first recorded player. A replay that no
+/// longer matches the game (e.g. recorded with another config) is rejected.
+pub async fn replay_play_endpoint(
+    Json(replay): Json<Replay>,
+) -> Result<Json<GameUpdate>, (StatusCode, String)> {
+    let config = crate::config::GameConfig::load("game_config.toml")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not load config: {}", e)))?;
+    let mut game = GameState::from_config(&config);
+    
+    replay.play(&mut game).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    logging::info!("[REPLAY] Played {} steps, ended on turn {} of level {}", replay.entries.len(), game.current_turn, game.current_level);
+    
+    let player_id = replay.players.first()
+        .or_else(|| replay.entries.first().map(|entry| &entry.player_id))
+        .map(String::as_str);
+    Ok(Json(game_state_to_update(&game, player_id)))
+}
+
+/// Editor preview of a freshly generated map, for the level picked by `?level=<number>` or
+/// `?name=<level name>` (defaults without either). `?seed=<number>` gives the same layout
+/// every time, e.g. to keep it while placing fixed spawns.
 pub async fn generate_map_endpoint(
     axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Json<GameUpdate> {
+) -> Result<Json<GameUpdate>, (StatusCode, String)> {
     // Load config and generate a fresh map
     let config = match crate::config::GameConfig::load("game_config.toml") {
         Ok(cfg) => cfg,
-        Err(_) => {
-            // If config doesn't exist, create default
+        Err(e) => {
+            // If config doesn't exist, create default (a broken file is left alone for the user to fix)
             let default_config = create_default_config();
-            let _ = default_config.save("game_config.toml");
+            if e.is_not_found() {
+                let _ = default_config.save("game_config.toml");
+            }
             default_config
         }
     };
-
-    let tile_registry = crate::tile_registry::TileRegistry::load_from_config(&config);
-    let object_registry = crate::game_object::GameObjectRegistry::load_from_config(&config);
     
-    // Get level config if level parameter is provided
-    let level_config = if let Some(level_str) = params.get("level") {
+    let seed = params.get("seed")
+        .map(|seed| seed.parse::<u64>())
+        .transpose()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "seed must be a whole number".to_string()))?;
+    
+    let level_config = preview_level(&config, &params)?;
+    
+    Ok(Json(preview_update(&config, level_config, seed)))
+}
+
+/// The level picked by the preview's `?name=` or `?level=` parameter (`None` for the defaults);
+/// an unknown name is an error rather than a silent fallback
+fn preview_level<'a>(
+    config: &'a crate::config::GameConfig,
+    params: &std::collections::HashMap<String, String>,
+) -> Result<Option<&'a crate::config::LevelConfig>, (StatusCode, String)> {
+    // Get level config if a level name or number is provided
+    let level_config = if let Some(name) = params.get("name") {
+        let found = config.levels.iter().find(|l| l.name.as_ref() == Some(name));
+        if found.is_none() {
+            let names: Vec<&str> = config.levels.iter().filter_map(|l| l.name.as_deref()).collect();
+            return Err((StatusCode::NOT_FOUND, format!("No level named '{}' (named levels: {:?})", name, names)));
+        }
+        found
+    } else if let Some(level_str) = params.get("level") {
         if let Ok(level_num) = level_str.parse::<u32>() {
-            log_debug(&format!("[MAP API] Looking for level {} in {} levels", level_num, config.levels.len()));
+            logging::debug!("[MAP API] Looking for level {} in {} levels", level_num, config.levels.len());
             let found = config.levels.iter().find(|l| l.level_number == level_num);
             if let Some(level) = found {
-                log_debug(&format!("[MAP API] Found level {}: min_rooms={}, max_rooms={}", 
-                    level.level_number, level.min_rooms, level.max_rooms));
+                logging::debug!("[MAP API] Found level {}: min_rooms={}, max_rooms={}", 
+                    level.level_number, level.min_rooms, level.max_rooms);
             } else {
-                log_debug(&format!("[MAP API] Level {} not found! Available levels: {:?}", 
-                    level_num, config.levels.iter().map(|l| l.level_number).collect::<Vec<_>>()));
+                logging::warning!("[MAP API] Level {} not found! Available levels: {:?}", 
+                    level_num, config.levels.iter().map(|l| l.level_number).collect::<Vec<_>>());
             }
             found
         } else {
-            log_debug(&format!("[MAP API] Invalid level number: {}", level_str));
+            logging::warning!("[MAP API] Invalid level number: {}", level_str);
             None
         }
     } else {
-        log_debug("[MAP API] No level parameter provided, using defaults");
+        logging::debug!("[MAP API] No level parameter provided, using defaults");
         None
     };
+    Ok(level_config)
+}
+
+/// Generate a fresh map for the editor preview, laid out from `seed` when given (random otherwise)
+fn preview_game(config: &crate::config::GameConfig, level_config: Option<&crate::config::LevelConfig>, seed: Option<u64>) -> GameState {
+    let tile_registry = crate::tile_registry::TileRegistry::load_from_config(config);
+    let object_registry = crate::game_object::GameObjectRegistry::load_from_config(config);
     
     let mut game_state = if let Some(level) = level_config {
-        GameState::new_with_level(tile_registry, object_registry, Some(level))
+        GameState::new_with_level(tile_registry, object_registry, Some(level), config.monster_cap())
     } else {
-        log_debug("[MAP API] Using default map generation (8-12 rooms)");
+        logging::debug!("[MAP API] Using default map generation (8-12 rooms)");
         GameState::new_with_registry(tile_registry, object_registry)
     };
+    if let Some(seed) = seed {
+        game_state.reseed(seed);
+        game_state.restart_level();
+    }
+    game_state
+}
+
+/// Editor preview of a fresh map (see `preview_game`), with a preview player at the spawn
+fn preview_update(config: &crate::config::GameConfig, level_config: Option<&crate::config::LevelConfig>, seed: Option<u64>) -> GameUpdate {
+    let mut game_state = preview_game(config, level_config, seed);
     
     // Add a preview player for the map editor
     let preview_player_id = "preview_player".to_string();
@@ -302,17 +805,85 @@ pub async fn generate_map_endpoint(
         game_state.entities.iter().any(|e| e.id == preview_player_id && e.x == sx && e.y == sy)
     });
     
-    Json(update)
+    update
+}
+
+/// Body of `POST /api/level/spawn`: add (or remove) a fixed spawn on a level
+#[derive(Deserialize, Debug)]
+pub struct LevelSpawnRequest {
+    pub level: u32,  // Level number to edit
+    pub object_id: String,
+    pub x: usize,
+    pub y: usize,
+    pub seed: u64,  // Seed of the preview the tile was picked on (`/api/map?seed=...`), whose layout is kept
+    #[serde(default)]
+    pub remove: bool,  // Remove the matching entry instead of adding one
 }
 
+/// Endpoint for the editor to hand-place objects on a level (only when the config enables
+/// admin commands, since it rewrites the config). The object has to go on a floor tile of the
+/// preview layout it was picked on. Saves the change to the config and returns the same
+/// layout again with the level's fixed spawns placed.
+pub async fn level_spawn_endpoint(
+    axum::extract::State((state, _tx)): axum::extract::State<(SharedState, Tx)>,
+    Json(request): Json<LevelSpawnRequest>,
+) -> Result<Json<GameUpdate>, (StatusCode, String)> {
+    use crate::map_generator::{MapGenerator, MAP_HEIGHT, MAP_WIDTH};
+    
+    if !state.lock().unwrap().admin_commands {
+        return Err((StatusCode::FORBIDDEN, "Admin commands are disabled (set admin_commands in the config)".to_string()));
+    }
+    let config = crate::config::GameConfig::load("game_config.toml")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not load config: {}", e)))?;
+    
+    if request.x >= MAP_WIDTH || request.y >= MAP_HEIGHT {
+        return Err((StatusCode::BAD_REQUEST, format!("({}, {}) is outside the {}x{} map", request.x, request.y, MAP_WIDTH, MAP_HEIGHT)));
+    }
+    match config.game_objects.iter().find(|obj| obj.id == request.object_id) {
+        None => return Err((StatusCode::BAD_REQUEST, format!("Object '{}' does not exist", request.object_id))),
+        Some(obj) if !MapGenerator::is_fixed_spawnable(obj) => {
+            return Err((StatusCode::BAD_REQUEST, format!("'{}' is not a monster, item or interactable", request.object_id)));
+        }
+        Some(_) => {}
+    }
+    
+    let Some(level) = config.levels.iter().find(|l| l.level_number == request.level) else {
+        return Err((StatusCode::NOT_FOUND, format!("Level {} does not exist", request.level)));
+    };
+    let spawn = crate::config::FixedSpawn { object_id: request.object_id, x: request.x, y: request.y };
+    if request.remove {
+        if !level.fixed_spawns.iter().flatten().any(|s| *s == spawn) {
+            return Err((StatusCode::NOT_FOUND, format!("Level {} has no fixed spawn {:?}", request.level, spawn)));
+        }
+    } else {
+        // Fixed spawns don't change the layout, so it's the one the editor is looking at
+        let layout = preview_game(&config, Some(level), Some(request.seed));
+        if !layout.dungeon.is_walkable(spawn.x, spawn.y) {
+            return Err((StatusCode::BAD_REQUEST, format!("({}, {}) is not a floor tile on this layout", spawn.x, spawn.y)));
+        }
+    }
+    
+    crate::config::GameConfig::edit_fixed_spawn("game_config.toml", request.level, &spawn, request.remove)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not save config: {}", e)))?;
+    logging::debug!("[MAP API] Updated fixed spawns for level {}", request.level);
+    
+    let config = crate::config::GameConfig::load("game_config.toml")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not reload config: {}", e)))?;
+    let level_config = config.levels.iter().find(|l| l.level_number == request.level);
+    Ok(Json(preview_update(&config, level_config, Some(request.seed))))
+}
+
+/// Websocket upgrade; `?format=msgpack` makes the server send MessagePack binary frames instead of JSON text
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
     axum::extract::State((state, tx)): axum::extract::State<(SharedState, Tx)>,
 ) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, state, tx))
+    let format = WireFormat::from_param(params.get("format").map(String::as_str));
+    ws.on_upgrade(move |socket| handle_socket(socket, state, tx, format))
 }
 
-async fn handle_socket(socket: WebSocket, state: SharedState, tx: Tx) {
+async fn handle_socket(socket: WebSocket, state: SharedState, tx: Tx, format: WireFormat) {
     let (mut sender, mut receiver) = socket.split();
     let mut rx = tx.subscribe();
     
@@ -322,18 +893,45 @@ async fn handle_socket(socket: WebSocket, state: SharedState, tx: Tx) {
     let player_id = format!("player_{}", PLAYER_COUNTER.fetch_add(1, Ordering::Relaxed));
     
     // Add new player entity to game state
-    {
+    let added = {
         let mut game = state.lock().unwrap();
-        eprintln!("[WS] Adding new player: {}", player_id);
+        logging::info!("[WS] Adding new player: {}", player_id);
         let player_count_before = game.entities.iter()
             .filter(|e| e.controller == crate::entity::EntityController::Player)
             .count();
-        eprintln!("[WS] Players before add: {}", player_count_before);
-        game.add_player(player_id.clone());
+        logging::debug!("[WS] Players before add: {}", player_count_before);
+        let added = game.add_player(player_id.clone());
         let player_count_after = game.entities.iter()
             .filter(|e| e.controller == crate::entity::EntityController::Player)
             .count();
-        eprintln!("[WS] Players after add: {}", player_count_after);
+        logging::debug!("[WS] Players after add: {}", player_count_after);
+        added
+    };
+    
+    // Without a "player" object in the config there's nothing to play as - fail loudly
+    if added.is_none() {
+        logging::error!("[WS] Could not add {}: no \"player\" game object in game_config.toml", player_id);
+        let error = ServerMessage::Error {
+            message: "Server misconfigured: game_config.toml has no \"player\" game object".to_string(),
+        };
+        if let Some(frame) = format.encode(&error) {
+            let _ = sender.send(frame).await;
+        }
+        let _ = sender.send(Message::Close(Some(CloseFrame {
+            code: close_code::ERROR,
+            reason: "missing player object".into(),
+        }))).await;
+        return;
+    }
+    METRICS.player_connected();
+    
+    // Tell the client (or bot) who it is and what it can do before any state arrives
+    let welcome = ServerMessage::Welcome {
+        player_id: player_id.clone(),
+        commands: crate::command::COMMANDS,
+    };
+    if let Some(frame) = format.encode(&welcome) {
+        let _ = sender.send(frame).await;
     }
 
     // Prepare initial game state
@@ -341,93 +939,171 @@ async fn handle_socket(socket: WebSocket, state: SharedState, tx: Tx) {
         let game = state.lock().unwrap();
         let mut update = game_state_to_update(&game, Some(&player_id));
         update.all_players_dead = game.are_all_players_dead();
-        let json_str = serde_json::to_string(&update).unwrap();
-        log_debug(&format!("[WS] Prepared initial game state for {}: {} bytes, {} entities, {} players", 
-            player_id, json_str.len(), update.entities.len(), update.players.len()));
-        json_str
+        let frame = format.encode(&update).unwrap();
+        let size = match &frame {
+            Message::Text(text) => text.len(),
+            Message::Binary(bytes) => bytes.len(),
+            _ => 0,
+        };
+        logging::debug!("[WS] Prepared initial game state for {}: {} bytes ({:?}), {} entities, {} players", 
+            player_id, size, format, update.entities.len(), update.players.len());
+        frame
     };
 
     // Small delay to ensure WebSocket connection is fully established
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
     // Send initial state synchronously BEFORE spawning tasks to ensure it's sent
-    log_debug(&format!("[WS] Sending initial state to {} synchronously", player_id));
-    match sender.send(Message::Text(initial_state.clone())).await {
+    logging::debug!("[WS] Sending initial state to {} synchronously", player_id);
+    match sender.send(initial_state).await {
         Ok(_) => {
-            log_debug(&format!("[WS] Successfully sent initial state to {}", player_id));
+            logging::debug!("[WS] Successfully sent initial state to {}", player_id);
             match sender.flush().await {
                 Ok(_) => {
-                    log_debug(&format!("[WS] Successfully flushed initial state to {}", player_id));
+                    logging::debug!("[WS] Successfully flushed initial state to {}", player_id);
                 }
                 Err(e) => {
-                    log_debug(&format!("[WS] Failed to flush initial state to {}: {:?}", player_id, e));
+                    logging::warning!("[WS] Failed to flush initial state to {}: {:?}", player_id, e);
                 }
             }
         }
         Err(e) => {
-            log_debug(&format!("[WS] Failed to send initial state to {}: {:?}", player_id, e));
+            logging::warning!("[WS] Failed to send initial state to {}: {:?}", player_id, e);
         }
     }
 
     // Spawn task to send updates to client (from broadcast channel)
     let player_id_for_send_cleanup = player_id.clone();
     let state_for_send_cleanup = state.clone();
+    let coalesce = std::time::Duration::from_millis(state.lock().unwrap().broadcast_coalesce_ms);
     let mut send_task = tokio::spawn(async move {
-        // Handle updates from broadcast channel
-        while let Ok(msg) = rx.recv().await {
-            if sender.send(Message::Text(msg)).await.is_err() {
+        // Handle updates from broadcast channel, personalized for this player
+        loop {
+            let mut event = match rx.recv().await {
+                Ok(event) => Some(event),
+                // Too slow to keep up and events were dropped: resync with the full current state
+                // (their messages are lost) instead of carrying on from a gap
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    logging::warning!("[WS] {} lagged behind by {} updates, resyncing", player_id_for_send_cleanup, skipped);
+                    METRICS.lagged_resync();
+                    None
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            // Batch whatever else arrives in the coalescing window into the same update
+            if !coalesce.is_zero() {
+                tokio::time::sleep(coalesce).await;
+                loop {
+                    match rx.try_recv() {
+                        Ok(later) => {
+                            METRICS.update_coalesced();
+                            match event.as_mut() {
+                                Some(event) => event.absorb(later, &player_id_for_send_cleanup),
+                                None => event = Some(later),
+                            }
+                        }
+                        Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                            logging::warning!("[WS] {} lagged behind by {} updates, resyncing", player_id_for_send_cleanup, skipped);
+                            METRICS.lagged_resync();
+                            event = None;
+                        }
+                        // Empty - or Closed, which the next recv() picks up
+                        Err(_) => break,
+                    }
+                }
+            }
+            let msg = {
+                let game = state_for_send_cleanup.lock().unwrap();
+                let mut update = game_state_to_update(&game, Some(&player_id_for_send_cleanup));
+                if let Some(event) = event {
+                    update.messages = event.messages;
+                    update.level_complete = event.level_complete;
+                    update.restart_confirmed = event.restart_confirmed;
+                    if event.player_id == player_id_for_send_cleanup {
+                        update.ack_command_id = event.command_id;
+                        update.examine_result = event.examine_result;
+                    }
+                }
+                format.encode(&update).unwrap()
+            };
+            if sender.send(msg).await.is_err() {
                 break;
             }
         }
         // Clean up player when send task ends (connection closed)
         let mut game = state_for_send_cleanup.lock().unwrap();
-        eprintln!("[WS] Removing player {} (send task ended)", player_id_for_send_cleanup);
+        logging::info!("[WS] Removing player {} (send task ended)", player_id_for_send_cleanup);
         game.remove_player(&player_id_for_send_cleanup);
         let player_count = game.entities.iter()
             .filter(|e| e.controller == crate::entity::EntityController::Player)
             .count();
-        eprintln!("[WS] Players remaining: {}", player_count);
+        logging::info!("[WS] Players remaining: {}", player_count);
     });
 
     // Spawn task to receive messages from client
     let player_id_clone = player_id.clone();
     let state_for_recv = state.clone();
     let mut recv_task = tokio::spawn(async move {
-        log_debug(&format!("[WS] Starting receiver task for {}", player_id_clone));
+        logging::debug!("[WS] Starting receiver task for {}", player_id_clone);
         loop {
             match receiver.next().await {
                 Some(Ok(Message::Text(text))) => {
-                    log_debug(&format!("[WS] Received message from {}: {} bytes", player_id_clone, text.len()));
+                    logging::debug!("[WS] Received message from {}: {} bytes", player_id_clone, text.len());
                     // Handle ping messages
                     if text == r#"{"action":"ping"}"# {
-                        log_debug(&format!("[WS] Received ping from {}", player_id_clone));
+                        logging::debug!("[WS] Received ping from {}", player_id_clone);
                         continue;
                     }
                     if let Ok(cmd) = serde_json::from_str::<PlayerCommand>(&text) {
+                        // Examining only reads the state: answer the sender, no turn taken
+                        if crate::command::lookup(&cmd.action).is_some_and(|spec| spec.kind == crate::command::CommandKind::Examine) {
+                            let (Some(x), Some(y)) = (cmd.target_x, cmd.target_y) else {
+                                continue;
+                            };
+                            let examine_result = examine_tile(&state_for_recv.lock().unwrap(), x, y);
+                            let event = UpdateEvent {
+                                messages: Vec::new(),
+                                level_complete: false,
+                                restart_confirmed: false,
+                                player_id: player_id_clone.clone(),
+                                command_id: cmd.command_id.clone(),
+                                examine_result: Some(examine_result),
+                            };
+                            if tx.send(event).is_ok() {
+                                METRICS.broadcast_sent();
+                            }
+                            continue;
+                        }
                         let mut game = state_for_recv.lock().unwrap();
+                        let started = std::time::Instant::now();
                         let (combat_messages, level_complete, restart_confirmed) = game.handle_command(&cmd, &player_id_clone);
+                        METRICS.command_handled(started.elapsed());
+                        drop(game);
                         
-                        // Create update with messages
-                        let mut update = game_state_to_update(&game, Some(&player_id_clone));
-                        update.messages = combat_messages;
-                        update.level_complete = level_complete;
-                        update.restart_confirmed = restart_confirmed;
-                        update.all_players_dead = game.are_all_players_dead();
-                        
-                        let update_str = serde_json::to_string(&update).unwrap();
-                        let _ = tx.send(update_str);
+                        // Every connection builds its own update from this (see send task)
+                        let event = UpdateEvent {
+                            messages: combat_messages,
+                            level_complete,
+                            restart_confirmed,
+                            player_id: player_id_clone.clone(),
+                            command_id: cmd.command_id.clone(),
+                            examine_result: None,
+                        };
+                        if tx.send(event).is_ok() {
+                            METRICS.broadcast_sent();
+                        }
                     }
                 }
                 Some(Ok(Message::Close(_))) => {
-                    log_debug(&format!("[WS] Received close message from {}", player_id_clone));
+                    logging::debug!("[WS] Received close message from {}", player_id_clone);
                     break;
                 }
                 Some(Err(e)) => {
-                    log_debug(&format!("[WS] Error receiving message from {}: {:?}", player_id_clone, e));
+                    logging::warning!("[WS] Error receiving message from {}: {:?}", player_id_clone, e);
                     break;
                 }
                 None => {
-                    log_debug(&format!("[WS] Receiver stream ended for {}", player_id_clone));
+                    logging::debug!("[WS] Receiver stream ended for {}", player_id_clone);
                     break;
                 }
                 _ => {
@@ -444,20 +1120,18 @@ async fn handle_socket(socket: WebSocket, state: SharedState, tx: Tx) {
             recv_task.abort();
             // Also cleanup here in case recv_task cleanup didn't run
             let mut game = state_for_final_cleanup.lock().unwrap();
-            eprintln!("[WS] Removing player {} (send_task ended, final cleanup)", player_id_for_final_cleanup);
+            logging::info!("[WS] Removing player {} (send_task ended, final cleanup)", player_id_for_final_cleanup);
             game.remove_player(&player_id_for_final_cleanup);
         },
         _ = (&mut recv_task) => {
             send_task.abort();
             // Cleanup when recv_task ends
             let mut game = state_for_final_cleanup.lock().unwrap();
-            eprintln!("[WS] Removing player {} (recv_task ended, final cleanup)", player_id_for_final_cleanup);
-            game.remove_player(&player_id_for_final_cleanup);
-            // Also cleanup here in case send_task cleanup didn't run
-            let mut game = state_for_final_cleanup.lock().unwrap();
+            logging::info!("[WS] Removing player {} (recv_task ended, final cleanup)", player_id_for_final_cleanup);
             game.remove_player(&player_id_for_final_cleanup);
         },
     };
+    METRICS.player_disconnected();
 }
 
 pub fn create_default_config() -> crate::config::GameConfig {
@@ -574,6 +1248,17 @@ pub fn create_default_config() -> crate::config::GameConfig {
     stairs.sprite_sheet = Some("tiles.png".to_string());
     objects.push(stairs);
     
+    // Stairs up (back to the previous level, only on levels with stairs_up)
+    let mut stairs_up = GameObject::new(
+        "stairs_up".to_string(),
+        "Stairs Up".to_string(),
+        "goal".to_string(),
+        true,
+        8, 16,  // Stairs up sprite coordinates
+    );
+    stairs_up.sprite_sheet = Some("tiles.png".to_string());
+    objects.push(stairs_up);
+    
     // Health potion (consumable)
     let mut health_potion = GameObject::new(
         "health_potion".to_string(),
@@ -589,6 +1274,382 @@ pub fn create_default_config() -> crate::config::GameConfig {
     crate::config::GameConfig { 
         game_objects: objects,
         levels: Vec::new(),
+        difficulty_scaling: crate::config::DifficultyScaling::default(),
+        permadeath: false,
+        shared_party_vision: false,
+        wall_attacks_allowed: false,
+        friendly_fire: false,
+        broadcast_coalesce_ms: 0,
+        min_players_to_start: 0,
+        ai_pathfinding_budget: None,
+        max_entities: None,
+        rest_heal_percent: 0,
+        monsters_avoid_stairs: false,
+        monsters_avoid_chests: false,
+        admin_commands: false,
+        defaults: std::collections::HashMap::new(),
+        sprite_sheets: ["tiles.png", "rogues.png"].into_iter()
+            .map(|sheet| crate::config::SpriteSheet {
+                name: sheet.to_string(),
+                path: sheet.to_string(),
+                sprite_size: crate::config::SpriteSize::DEFAULT,
+                columns: None,
+                rows: None,
+            })
+            .collect(),
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::Room;
+    use crate::game_state::tests::{command, directed, flagged, game_on, game_with, level, stage};
+    
+    fn layout(game: &GameState) -> Vec<Vec<bool>> {
+        game.dungeon.tiles.iter().map(|row| row.iter().map(|tile| tile.walkable).collect()).collect()
+    }
+    
+    #[test]
+    fn a_seeded_preview_keeps_its_layout_when_fixed_spawns_change() {
+        let config = create_default_config();
+        let plain = level("");
+        let with_spawns = level("fixed_spawns = [{ object_id = \"orc\", x = 10, y = 10 }]");
+        
+        let first = preview_game(&config, Some(&plain), Some(7));
+        assert_eq!(layout(&preview_game(&config, Some(&plain), Some(7))), layout(&first));
+        assert_eq!(layout(&preview_game(&config, Some(&with_spawns), Some(7))), layout(&first));
+    }
+    
+    #[tokio::test]
+    async fn placing_spawns_needs_admin_commands() {
+        let game = GameState::from_config(&create_default_config());
+        let (tx, _rx) = broadcast::channel(1);
+        let request = LevelSpawnRequest { level: 1, object_id: "orc".to_string(), x: 10, y: 10, seed: 7, remove: false };
+        
+        let result = level_spawn_endpoint(axum::extract::State((Arc::new(Mutex::new(game)), tx)), Json(request)).await;
+        assert_eq!(result.err().map(|(status, _)| status), Some(StatusCode::FORBIDDEN));
+    }
+    
+    #[tokio::test]
+    async fn starting_a_replay_needs_admin_commands() {
+        let state = Arc::new(Mutex::new(GameState::from_config(&create_default_config())));
+        let (tx, _rx) = broadcast::channel(1);
+        
+        let result = replay_start_endpoint(axum::extract::State((state.clone(), tx))).await;
+        assert_eq!(result.err().map(|(status, _)| status), Some(StatusCode::FORBIDDEN));
+        assert!(state.lock().unwrap().recording.is_none());
+    }
+    
+    #[tokio::test]
+    async fn stopping_a_replay_needs_admin_commands() {
+        let state = Arc::new(Mutex::new(GameState::from_config(&create_default_config())));
+        state.lock().unwrap().start_recording();
+        let (tx, _rx) = broadcast::channel(1);
+        
+        let result = replay_stop_endpoint(axum::extract::State((state.clone(), tx))).await;
+        assert_eq!(result.err().map(|(status, _)| status), Some(StatusCode::FORBIDDEN));
+        assert!(state.lock().unwrap().recording.is_some());
+    }
+    
+    #[test]
+    fn a_killed_monster_is_sent_once_as_dying() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 2, 1)]);
+        game.entities[1].current_health = 1;
+        let sent = |game: &GameState| game_state_to_update(game, Some("p1")).entities.into_iter().find(|e| e.id == "monster_0");
+        
+        game.handle_command(&directed("attack", "right"), "p1");
+        assert!(sent(&game).is_some_and(|monster| monster.dying));
+        
+        game.handle_command(&command("move_right"), "p1");
+        assert!(sent(&game).is_none());
+        assert_eq!((game.entities[0].x, game.entities[0].y), (2, 1));
+    }
+    
+    /// Serve `game` over the WebSocket endpoint on a free local port, returning its URL and
+    /// the broadcast channel (holding `capacity` updates) its connections listen on
+    async fn serve(game: GameState, capacity: usize) -> (String, Tx) {
+        let (tx, _rx) = broadcast::channel(capacity);
+        let app = axum::Router::new()
+            .route("/ws", axum::routing::get(websocket_handler))
+            .with_state((Arc::new(Mutex::new(game)), tx.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("ws://{}/ws", address), tx)
+    }
+    
+    async fn next_json<S>(socket: &mut S) -> serde_json::Value
+    where
+        S: futures_util::Stream<Item = Result<tokio_tungstenite::tungstenite::Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await
+                .expect("the server answers")
+                .unwrap()
+                .unwrap();
+            if let tokio_tungstenite::tungstenite::Message::Text(text) = frame {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn a_bot_plays_a_turn_over_the_socket() {
+        // A seeded layout, so the bot always finds the same map
+        let mut game = GameState::from_config(&create_default_config());
+        game.reseed(1);
+        game.restart_level();
+        game.entities.retain(|e| e.controller == EntityController::Player);
+        let (url, _tx) = serve(game, 100).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        
+        let welcome = next_json(&mut socket).await;
+        assert_eq!(welcome["type"], "Welcome");
+        let player_id = welcome["player_id"].as_str().unwrap().to_string();
+        let update: GameUpdate = serde_json::from_value(next_json(&mut socket).await).unwrap();
+        assert_eq!(update.current_player_id.as_deref(), Some(player_id.as_str()));
+        assert!(update.is_my_turn);
+        
+        // Step onto the first free neighbouring tile, as a bot reading the map would
+        let (x, y) = update.my_position.unwrap();
+        let (action, target) = [("move_right", (x + 1, y)), ("move_left", (x - 1, y)), ("move_down", (x, y + 1)), ("move_up", (x, y - 1))]
+            .into_iter()
+            .find(|&(_, (tx, ty))| update.map[ty][tx].walkable)
+            .unwrap();
+        let command = serde_json::json!({ "action": action, "command_id": "bot-1" });
+        socket.send(tokio_tungstenite::tungstenite::Message::Text(command.to_string())).await.unwrap();
+        
+        let update: GameUpdate = serde_json::from_value(next_json(&mut socket).await).unwrap();
+        assert_eq!(update.ack_command_id.as_deref(), Some("bot-1"));
+        assert_eq!(update.my_position, Some(target));
+        assert!(update.is_my_turn);
+    }
+    
+    #[test]
+    fn the_turn_order_lists_players_still_to_act_first() {
+        let mut game = game_on(level(""));
+        for id in ["p2", "p3", "p4"] {
+            game.add_player(id.to_string());
+        }
+        stage(&mut game, &["#########", "#.......#", "#########"], &[(1, 1), (3, 1), (5, 1), (7, 1)], &[]);
+        game.entities[3].current_health = 0;  // p4 is dead and has no turn
+        game.handle_command(&command("move_right"), "p1");
+        
+        let order: Vec<(String, bool, bool)> = game_state_to_update(&game, Some("p2")).turn_order.into_iter()
+            .map(|entry| (entry.id, entry.is_ai, entry.has_acted))
+            .collect();
+        assert_eq!(order, vec![
+            ("p2".to_string(), false, false),
+            ("p3".to_string(), false, false),
+            ("p1".to_string(), false, true),
+            ("ai".to_string(), true, false),
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn a_lagging_connection_is_resynced_instead_of_skipping_ahead() {
+        let (url, tx) = serve(game_on(level("")), 4).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let welcome = next_json(&mut socket).await;
+        let player_id = welcome["player_id"].as_str().unwrap().to_string();
+        next_json(&mut socket).await;  // Initial state
+        let resyncs = METRICS.snapshot(0, 0, 0, 0).lagged_resyncs;
+        
+        // The test runtime is single-threaded, so the connection can't read any of these before
+        // the channel overflows
+        for i in 0..10 {
+            tx.send(UpdateEvent {
+                messages: vec![GameMessage::system(format!("event {}", i))],
+                level_complete: false,
+                restart_confirmed: false,
+                player_id: "p2".to_string(),
+                command_id: None,
+                examine_result: None,
+            }).unwrap();
+        }
+        
+        let resync: GameUpdate = serde_json::from_value(next_json(&mut socket).await).unwrap();
+        assert!(resync.messages.is_empty());
+        assert_eq!(resync.current_player_id.as_deref(), Some(player_id.as_str()));
+        assert!(METRICS.snapshot(0, 0, 0, 0).lagged_resyncs > resyncs);
+        
+        // Then it carries on with the updates still in the channel
+        let next: GameUpdate = serde_json::from_value(next_json(&mut socket).await).unwrap();
+        assert_eq!(next.messages.iter().map(|m| m.text.as_str()).collect::<Vec<_>>(), vec!["event 6"]);
+    }
+    
+    #[test]
+    fn only_a_treasure_sense_player_is_sent_distant_chests() {
+        let mut config = create_default_config();
+        config.game_objects.push(crate::game_object::GameObject::new("chest".to_string(), "Chest".to_string(), "chest".to_string(), false, 0, 0));
+        let mut game = game_with(config, level(""));
+        game.add_player("p2".to_string());
+        stage(&mut game, &["##############", "#...#....#...#", "#............#", "#...#....#...#", "##############"], &[(1, 2), (2, 2)], &[]);
+        game.dungeon.rooms = vec![
+            Room { x: 1, y: 1, width: 3, height: 3 },
+            Room { x: 10, y: 1, width: 3, height: 3 },
+        ];
+        game.chests.push(Chest { id: "chest_0".to_string(), x: 11, y: 2, object_id: "chest".to_string(), is_open: false, is_active: false });
+        game.players.entry("p2".to_string()).or_default().reveals_loot = true;
+        
+        assert_eq!(game_state_to_update(&game, Some("p2")).sensed_loot, vec![(11, 2)]);
+        assert!(game_state_to_update(&game, Some("p1")).sensed_loot.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn rapid_updates_are_coalesced_into_one() {
+        let mut game = game_on(level(""));
+        game.broadcast_coalesce_ms = 50;
+        let (url, tx) = serve(game, 100).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        next_json(&mut socket).await;  // Welcome
+        next_json(&mut socket).await;  // Initial state
+        
+        for i in 0..5 {
+            tx.send(UpdateEvent {
+                messages: vec![GameMessage::system(format!("event {}", i))],
+                level_complete: i == 2,
+                restart_confirmed: false,
+                player_id: "p2".to_string(),
+                command_id: None,
+                examine_result: None,
+            }).unwrap();
+            tokio::task::yield_now().await;
+        }
+        
+        // Every message arrives, in order, in a single update
+        let update: GameUpdate = serde_json::from_value(next_json(&mut socket).await).unwrap();
+        let texts: Vec<&str> = update.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["event 0", "event 1", "event 2", "event 3", "event 4"]);
+        assert!(update.level_complete);
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(200), socket.next()).await.is_err());
+    }
+    
+    #[test]
+    fn stairs_confirmations_are_shown_per_player() {
+        let mut game = game_on(level(""));
+        game.add_player("p2".to_string());
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1), (3, 1)], &[]);
+        game.stairs_position = Some((1, 1));
+        let confirm = flagged("confirm_stairs");
+        
+        game.handle_command(&confirm, "p1");
+        let confirmed: Vec<(String, bool)> = game_state_to_update(&game, Some("p2")).players.into_iter()
+            .map(|player| (player.id, player.confirmed_stairs))
+            .collect();
+        assert_eq!(confirmed, vec![("p1".to_string(), true), ("p2".to_string(), false)]);
+        assert_eq!(game.current_level, 1);
+    }
+    
+    #[test]
+    fn update_timestamps_never_go_backwards() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        let mut last = (0, 0);
+        for action in ["move_right", "move_right", "move_left", "move_left"] {
+            game.handle_command(&command(action), "p1");
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            let update = game_state_to_update(&game, Some("p1"));
+            let stamp = (update.server_time_ms, update.current_turn);
+            assert!(stamp.0 >= last.0 && stamp.1 > last.1, "{:?} came after {:?}", stamp, last);
+            last = stamp;
+        }
+        assert!(last.0 >= 8);
+    }
+    
+    #[test]
+    fn examining_a_monster_and_an_empty_floor() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 3, 1)]);
+        
+        let monster = examine_tile(&game, 3, 1);
+        let entity = monster.entity.expect("the orc is there");
+        assert_eq!((entity.id.as_str(), entity.current_health), ("monster_0", game.entities[1].current_health));
+        assert_eq!(monster.entity_name.as_deref(), Some("Orc"));
+        assert!(monster.walkable);
+        assert!(monster.description.contains("Orc: 50/50 HP"), "{}", monster.description);
+        
+        let floor = examine_tile(&game, 2, 1);
+        assert!(floor.walkable);
+        assert!(floor.entity.is_none() && floor.chest.is_none() && floor.item.is_none());
+        assert!(!floor.is_stairs && !floor.is_stairs_up);
+        assert_eq!(floor.description, format!("{} (walkable)", floor.tile.unwrap()));
+    }
+    
+    #[test]
+    fn preview_picks_a_level_by_name_and_rejects_unknown_names() {
+        let mut config = create_default_config();
+        config.levels = vec![level(""), level("level_number = 2\nname = \"Crypt\"")];
+        let params = |pairs: &[(&str, &str)]| pairs.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<std::collections::HashMap<_, _>>();
+        
+        let by_name = preview_level(&config, &params(&[("name", "Crypt")])).unwrap();
+        assert_eq!(by_name.map(|l| l.level_number), Some(2));
+        let by_number = preview_level(&config, &params(&[("level", "1")])).unwrap();
+        assert_eq!(by_number.map(|l| l.level_number), Some(1));
+        
+        let (status, message) = preview_level(&config, &params(&[("name", "Sewers")])).unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(message.contains("Sewers") && message.contains("Crypt"));  // Lists the named levels
+    }
+    
+    /// Every key in `value` has to be a field of the schema's `type_name`, and the same for
+    /// nested values whose field type is another struct in the schema
+    fn assert_keys_in_schema(schema: &crate::update_schema::UpdateSchema, type_name: &str, value: &serde_json::Value) {
+        let Some(schema_type) = schema.types.iter().find(|t| t.name == type_name && t.kind == "struct") else {
+            return;  // A primitive, tuple, map or enum
+        };
+        match value {
+            serde_json::Value::Array(items) => items.iter().for_each(|item| assert_keys_in_schema(schema, type_name, item)),
+            serde_json::Value::Object(fields) => {
+                for (key, field_value) in fields {
+                    let field = schema_type.fields.iter().find(|field| field.name == key)
+                        .unwrap_or_else(|| panic!("{}.{} is sent but missing from UpdateSchema", type_name, key));
+                    // Vec<Vec<TileData>> and Option<Vec<CombatPreview>> hold TileData and CombatPreview
+                    let mut inner = field.field_type;
+                    while let Some(rest) = inner.strip_prefix("Vec<").or_else(|| inner.strip_prefix("Option<")) {
+                        inner = rest.strip_suffix('>').unwrap_or(rest);
+                    }
+                    assert_keys_in_schema(schema, inner, field_value);
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    #[test]
+    fn every_key_of_an_update_is_in_the_update_schema() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 2, 1)]);
+        game.entities[0].add_buff(crate::entity::StatusEffectKind::Strengthened, 2, 3);
+        let mut update = game_state_to_update(&game, Some("p1"));
+        
+        // Fill in everything that's left out when empty, so those keys are checked too
+        let item = InventoryItem { object_id: "sword".to_string(), count: 1, rarity: Some("rare".to_string()), durability: Some(5) };
+        for entity in update.entities.iter_mut() {
+            entity.inventory = vec![item.clone()];
+            (entity.weapon, entity.armor) = (Some(item.clone()), Some(item.clone()));
+            entity.unique_name = Some("Orc: Grolok".to_string());
+            entity.tint = Some(Tint { r: 1.0, g: 0.5, b: 0.5 });
+        }
+        update.consumables.push(ConsumableData { id: "potion_0".to_string(), object_id: "potion".to_string(), x: 3, y: 1, rarity: Some("common".to_string()) });
+        update.chests.push(chest_data(&Chest { id: "chest_0".to_string(), x: 3, y: 1, object_id: "chest".to_string(), is_open: false, is_active: false }));
+        update.messages = vec![GameMessage::combat("p1".to_string(), "orc".to_string(), 3, 7, false), GameMessage::pickup("Sword".to_string(), 1, Some("rare".to_string()))];
+        update.my_position = Some((1, 1));
+        update.music_track = Some("crypt.ogg".to_string());
+        update.ambiance = Some("drips.ogg".to_string());
+        update.waiting_for_players = Some(LobbyStatus { connected: 1, ready: 0, min_players: 2 });
+        update.scoreboard = vec![RunRecord { players: vec!["p1".to_string()], level: 2, turns: 40 }];
+        update.win_progress = Some(WinProgress { condition: crate::config::WinCondition::KillBoss, remaining: 1 });
+        update.combat_preview = Some(vec![CombatPreview { entity_id: "orc".to_string(), x: 2, y: 1, damage_dealt: (1, 3), damage_taken: (0, 2) }]);
+        update.ack_command_id = Some("cmd_1".to_string());
+        update.examine_result = Some(examine_tile(&game, 2, 1));
+        
+        let schema = crate::update_schema::UpdateSchema::generate();
+        assert_keys_in_schema(&schema, schema.root, &serde_json::to_value(&update).unwrap());
+    }
+}
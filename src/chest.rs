@@ -7,5 +7,7 @@ pub struct Chest {
     pub y: usize,
     pub object_id: String,  // Reference to GameObject (which contains interactable data with before/after states)
     pub is_open: bool,  // Whether the chest is open
+    #[serde(default)]
+    pub is_active: bool,  // Whether a toggle interactable (lever) is switched on; it never opens
 }
 
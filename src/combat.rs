@@ -1,44 +1,289 @@
 use crate::entity::{Entity, EntityController};
+use crate::dungeon::{bresenham_line, Dungeon};
 use crate::consumable::Consumable;
 use crate::game_object::GameObjectRegistry;
+use crate::game_object::object::InflictedStatus;
 use crate::message::{GameMessage, CombatMessage};
+use rand::Rng;
+
+/// Min/max damage `attacker` would deal to `target` on a normal hit, given attack spread
+/// and the target's resistances and defense (crits ignored). Pure - uses the same formulas as `attack_entity`.
+pub fn estimate_damage(attacker: &Entity, target: &Entity) -> (u32, u32) {
+    let attack = attacker.effective_attack();
+    let spread_range = spread_range(attack, attacker.attack_spread_percent);
+    (
+        damage_against(attack - spread_range, &attacker.damage_type, target),
+        damage_against(attack + spread_range, &attacker.damage_type, target),
+    )
+}
+
+/// Damage a hit of `damage_type` does to `target`: scaled by the target's resistance to that
+/// type, then reduced by defense (minimum 1). Targets immune to the type (100%+) take none.
+pub fn damage_against(base_damage: i32, damage_type: &str, target: &Entity) -> u32 {
+    let resistance = target.resistance_to(damage_type);
+    if resistance >= 100 {
+        return 0;
+    }
+    damage_after_defense(base_damage * (100 - resistance) / 100, target.effective_defense())
+}
+
+// How far a hit can deviate from the base attack: ±spread_percent% of it
+fn spread_range(attack: i32, spread_percent: u32) -> i32 {
+    (attack as f32 * spread_percent as f32 / 100.0) as i32
+}
+
+// Damage left after the target's defense, minimum 1
+fn damage_after_defense(base_damage: i32, defense: i32) -> u32 {
+    (base_damage - defense).max(1) as u32
+}
+
+/// Roll for a hit to put `inflicted`'s status effect on a (still alive) target
+pub fn try_inflict_status(target: &mut Entity, inflicted: &InflictedStatus, rng: &mut impl Rng) {
+    let chance = inflicted.chance_percent.unwrap_or(100);
+    if target.is_alive() && rng.gen_range(0..100) < chance {
+        target.add_status(inflicted.effect, inflicted.turns);
+    }
+}
+
+/// Heal every alive entity on the same side as `center_idx` (itself included) within
+/// `radius` tiles (Chebyshev distance). Returns a healing message per entity that gained health.
+pub fn heal_allies(
+    entities: &mut [Entity],
+    center_idx: usize,
+    radius: u32,
+    amount: u32,
+    source_name: &str,
+    object_registry: &GameObjectRegistry,
+) -> Vec<GameMessage> {
+    let (center_x, center_y) = (entities[center_idx].x, entities[center_idx].y);
+    let side = entities[center_idx].controller;
+    let radius = radius as usize;
+    
+    let mut messages = Vec::new();
+    for entity in entities.iter_mut() {
+        let in_range = entity.x.abs_diff(center_x).max(entity.y.abs_diff(center_y)) <= radius;
+        if !in_range || !entity.is_alive() || entity.controller != side {
+            continue;
+        }
+        let old_health = entity.current_health;
+        entity.heal(amount);
+        if entity.current_health > old_health {
+            let target_name = entity.display_name(object_registry);
+            messages.push(GameMessage::healing(
+                source_name.to_string(),
+                target_name,
+                entity.current_health - old_health,
+                entity.current_health,
+            ));
+        }
+    }
+    messages
+}
+
+/// Piercing attack: hits every entity on the line from the attacker toward `direction` for up to
+/// its `line_attack_range` tiles, stopping at the first wall. Each hit resolves like `attack_entity`.
+/// The attacker's own side is passed over - for players only while `friendly_fire` is off,
+/// monsters never hit each other.
+#[allow(clippy::too_many_arguments)]
+pub fn attack_line(
+    entities: &mut [Entity],
+    dungeon: &Dungeon,
+    attacker_idx: usize,
+    direction: (i32, i32),
+    friendly_fire: bool,
+    object_registry: &GameObjectRegistry,
+    consumables: &mut Vec<Consumable>,
+    rng: &mut impl Rng,
+) -> Vec<GameMessage> {
+    let range = object_registry.get_object(&entities[attacker_idx].object_id)
+        .and_then(|obj| obj.line_attack_range)
+        .unwrap_or(0);
+    let (dx, dy) = direction;
+    let steps = dx.abs().max(dy.abs());
+    if steps == 0 || range == 0 {
+        return Vec::new();
+    }
+    
+    // Extend the direction far enough to cover the range, then walk it tile by tile
+    let start = (entities[attacker_idx].x as i32, entities[attacker_idx].y as i32);
+    let scale = (range as i32 + steps - 1) / steps;
+    let end = (start.0 + dx * scale, start.1 + dy * scale);
+    let attacker_side = entities[attacker_idx].controller;
+    let hits_own_side = friendly_fire && attacker_side == EntityController::Player;
+    
+    let mut target_ids = Vec::new();
+    for (x, y) in bresenham_line(start, end).into_iter().skip(1).take(range as usize) {
+        if x < 0 || y < 0 || dungeon.blocks_projectiles(x as usize, y as usize) {
+            break;
+        }
+        target_ids.extend(entities.iter()
+            .filter(|e| e.x == x as usize && e.y == y as usize && e.is_alive())
+            .filter(|e| hits_own_side || e.controller != attacker_side)
+            .map(|e| e.id.clone()));
+    }
+    
+    let mut messages = Vec::new();
+    for target_id in target_ids {
+        if let Some(target_idx) = entities.iter().position(|e| e.id == target_id && e.is_alive()) {
+            if let Some(msg) = attack_entity(entities, dungeon, attacker_idx, target_idx, object_registry, consumables, rng) {
+                messages.push(msg);
+            }
+        }
+    }
+    messages
+}
+
+/// Walkable tiles within 2 steps of (x, y), nearest first: the tile itself, then the rings around it
+fn tiles_around(dungeon: &Dungeon, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..=2i32).flat_map(move |radius| {
+        (-radius..=radius).flat_map(move |dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(move |(dx, dy)| dx.abs().max(dy.abs()) == radius)
+            .map(move |(dx, dy)| (x as i32 + dx, y as i32 + dy))
+    })
+    .filter(|&(tx, ty)| tx >= 0 && ty >= 0 && dungeon.is_walkable(tx as usize, ty as usize))
+    .map(|(tx, ty)| (tx as usize, ty as usize))
+}
+
+/// Drop items (by object ID) as pickups around (x, y), one per tile so none hide another:
+/// the tile itself first, then the nearest free ones. Once no free tile is left the rest pile
+/// up on (x, y) itself, so nothing (a door key least of all) is ever lost.
+/// The pickups' IDs are numbered after `source_id`, the entity dropping them.
+pub fn drop_items(dungeon: &Dungeon, consumables: &mut Vec<Consumable>, x: usize, y: usize, source_id: &str, object_ids: Vec<String>) {
+    let mut free_tiles: Vec<(usize, usize)> = tiles_around(dungeon, x, y)
+        .filter(|&(tx, ty)| !consumables.iter().any(|c| c.x == tx && c.y == ty))
+        .collect();
+    free_tiles.reverse();
+    for (i, object_id) in object_ids.into_iter().enumerate() {
+        let (tx, ty) = free_tiles.pop().unwrap_or((x, y));
+        consumables.push(Consumable {
+            id: format!("{}_drop_{}", source_id, i),
+            x: tx,
+            y: ty,
+            object_id,
+            dropped_turns_ago: Some(0),
+        });
+    }
+}
+
+/// Object IDs of the items a monster of type `object_id` leaves when killed: the item it
+/// carries (e.g., a door key) or else a 25% chance of a potion, plus each piece of its
+/// equipment that passes the drop roll
+fn death_drops(object_registry: &GameObjectRegistry, object_id: &str, rng: &mut impl Rng) -> Vec<String> {
+    let template = object_registry.get_object(object_id);
+    let mut drops = Vec::new();
+    
+    match template.and_then(|obj| obj.drops_on_death.clone()) {
+        Some(carried) => drops.push(carried),
+        // Rare potions drop less often
+        None if rng.gen_range(0..100) < 25 => {
+            if let Some(potion_template) = object_registry.random_loot(rng) {
+                drops.push(potion_template.id.clone());
+            }
+        }
+        None => {}
+    }
+    
+    if let Some(obj) = template {
+        let drop_percent = obj.equipment_drop_percent.unwrap_or(100);
+        for item in obj.equipment.iter().flatten() {
+            if rng.gen_range(0..100) < drop_percent {
+                drops.push(item.clone());
+            }
+        }
+    }
+    drops
+}
+
+/// Deal `damage` (already reduced by defense) to an entity, handling what a kill entails:
+/// dying state and item drops (on-death spawns come when `GameState` clears the body).
+/// Returns the target's health afterwards.
+pub fn apply_damage(
+    entities: &mut [Entity],
+    dungeon: &Dungeon,
+    target_idx: usize,
+    damage: u32,
+    object_registry: &GameObjectRegistry,
+    consumables: &mut Vec<Consumable>,
+    rng: &mut impl Rng,
+) -> u32 {
+    // Get target position before mutable borrow
+    let target_y = entities[target_idx].y;
+    
+    // Apply damage to target
+    let target = &mut entities[target_idx];
+    let target_x = target.x;
+    
+    if damage >= target.current_health {
+        target.current_health = 0;
+    } else {
+        target.current_health -= damage;
+    }
+    
+    let health_after = target.current_health;
+    let target_died = health_after == 0;
+    let was_monster = target.controller == EntityController::AI;
+    
+    // Dead monsters linger for one update so the client can play a death animation
+    if target_died && was_monster {
+        target.dying = true;
+    }
+    
+    if target_died && was_monster {
+        let drops = death_drops(object_registry, &target.object_id, rng);
+        drop_items(dungeon, consumables, target_x, target_y, &target.id, drops);
+    }
+    
+    health_after
+}
 
 pub fn attack_entity(
     entities: &mut [Entity],
+    dungeon: &Dungeon,
+    attacker_idx: usize,
+    target_idx: usize,
+    object_registry: &GameObjectRegistry,
+    consumables: &mut Vec<Consumable>,
+    rng: &mut impl Rng,
+) -> Option<CombatMessage> {
+    attack_entity_scaled(entities, dungeon, attacker_idx, target_idx, object_registry, consumables, 100, rng)
+}
+
+/// Same as `attack_entity`, with the base damage multiplied by `damage_percent` (100 = normal)
+#[allow(clippy::too_many_arguments)]
+pub fn attack_entity_scaled(
+    entities: &mut [Entity],
+    dungeon: &Dungeon,
     attacker_idx: usize,
     target_idx: usize,
     object_registry: &GameObjectRegistry,
     consumables: &mut Vec<Consumable>,
+    damage_percent: u32,
+    rng: &mut impl Rng,
 ) -> Option<CombatMessage> {
     if attacker_idx >= entities.len() || target_idx >= entities.len() {
         return None;
     }
     
     // Get attacker's values before mutable borrow
-    let attacker_attack = entities[attacker_idx].attack;
+    let attacker_attack = entities[attacker_idx].effective_attack();
     let attacker_spread = entities[attacker_idx].attack_spread_percent;
     let attacker_crit_chance = entities[attacker_idx].crit_chance_percent;
     let attacker_crit_damage = entities[attacker_idx].crit_damage_percent;
-    let attacker_id = entities[attacker_idx].id.clone();
-    let attacker_x = entities[attacker_idx].x;
-    
-    // Get target's defense
-    let target_defense = entities[target_idx].defense;
+    let (attacker_x, attacker_y) = (entities[attacker_idx].x, entities[attacker_idx].y);
+    let attacker_damage_type = entities[attacker_idx].damage_type.clone();
     
     // Calculate base damage with variance
     // Apply percentage spread: base_attack * (1 ± spread_percent/100)
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
     let spread_amount = if attacker_spread > 0 {
         // Calculate spread range: ±spread_percent% of base attack
-        let spread_range = (attacker_attack as f32 * attacker_spread as f32 / 100.0) as i32;
+        let spread_range = spread_range(attacker_attack, attacker_spread);
         // Random value between -spread_range and +spread_range
         rng.gen_range(-spread_range..=spread_range)
     } else {
         0
     };
     
-    let base_damage = attacker_attack + spread_amount;
+    let base_damage = (attacker_attack + spread_amount) * damage_percent as i32 / 100;
     
     // Check for critical hit
     let is_crit = attacker_crit_chance > 0 && rng.gen_range(0..100) < attacker_crit_chance;
@@ -49,75 +294,28 @@ pub fn attack_entity(
         base_damage
     };
     
-    // Calculate final damage: final_base_damage - defense, minimum 1
-    let raw_damage = final_base_damage - target_defense;
-    let damage = raw_damage.max(1) as u32;  // Minimum 1 damage
-    
-    // Get target position before mutable borrow
-    let target_y = entities[target_idx].y;
-    
-    // Apply damage to target
-    let target = &mut entities[target_idx];
-    let target_id = target.id.clone();
-    let target_x = target.x;
+    // Calculate final damage: scaled by resistance, then final_base_damage - defense, minimum 1
+    let damage = damage_against(final_base_damage, &attacker_damage_type, &entities[target_idx]);
     
-    if damage >= target.current_health {
-        target.current_health = 0;
-    } else {
-        target.current_health -= damage;
+    // Players attacking a monster provoke it (passive monsters fight back, vision cones stop mattering)
+    if entities[attacker_idx].controller == EntityController::Player {
+        entities[target_idx].provoked = true;
     }
     
-    let health_after = target.current_health;
+    let (target_x, target_y) = (entities[target_idx].x, entities[target_idx].y);
+    let health_after = apply_damage(entities, dungeon, target_idx, damage, object_registry, consumables, rng);
     let target_died = health_after == 0;
-    let was_monster = target.controller == EntityController::AI;
     
-    // If target died and it was a monster, check for potion drop (25% chance)
-    if target_died && was_monster {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        if rng.gen_range(0..100) < 25 {
-            // 25% chance to drop a potion
-            // Find a health potion template
-            let potion_templates: Vec<&crate::game_object::GameObject> = object_registry.get_all_objects()
-                .into_iter()
-                .filter(|obj| obj.object_type == "consumable")
-                .collect();
-            
-            if !potion_templates.is_empty() {
-                // Use first available potion template (or random if multiple)
-                let potion_template = potion_templates[rng.gen_range(0..potion_templates.len())];
-                
-                // Create consumable at the monster's death location
-                use std::sync::atomic::{AtomicU64, Ordering};
-                static CONSUMABLE_COUNTER: AtomicU64 = AtomicU64::new(0);
-                let consumable_id = format!("consumable_{}", CONSUMABLE_COUNTER.fetch_add(1, Ordering::Relaxed));
-                
-                let consumable = Consumable {
-                    id: consumable_id,
-                    x: target_x,
-                    y: target_y,
-                    object_id: potion_template.id.clone(),
-                };
-                
-                consumables.push(consumable);
-            }
-        }
+    if let Some(inflicted) = object_registry.get_object(&entities[attacker_idx].object_id).and_then(|obj| obj.inflicts_status.as_ref()) {
+        try_inflict_status(&mut entities[target_idx], inflicted, rng);
     }
     
-    // Update attacker's facing direction based on relative position
-    if attacker_x < target_x {
-        entities[attacker_idx].facing_right = true;
-    } else if attacker_x > target_x {
-        entities[attacker_idx].facing_right = false;
-    }
+    // Face the target, up and down included
+    entities[attacker_idx].face(target_x as i32 - attacker_x as i32, target_y as i32 - attacker_y as i32);
     
     // Get attacker and target names for better message display
-    let attacker_name = object_registry.get_object(&entities[attacker_idx].object_id)
-        .map(|o| o.name.clone())
-        .unwrap_or_else(|| attacker_id.clone());
-    let target_name = object_registry.get_object(&entities[target_idx].object_id)
-        .map(|o| o.name.clone())
-        .unwrap_or_else(|| target_id.clone());
+    let attacker_name = entities[attacker_idx].display_name(object_registry);
+    let target_name = entities[target_idx].display_name(object_registry);
     
     // Create combat message with crit indicator
     let message = if is_crit {
@@ -140,3 +338,174 @@ pub fn attack_entity(
     Some(message)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_default_config;
+    use crate::dungeon::tests::dungeon_from;
+    use crate::entity::Direction;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    
+    /// A player at (1, 1) facing a sturdy orc at (2, 1)
+    fn duel(attack: i32, spread_percent: u32, defense: i32) -> Vec<Entity> {
+        vec![
+            Entity::new("p1".to_string(), 1, 1, "player".to_string(), attack, 0, spread_percent, 0, 150, 100, EntityController::Player),
+            Entity::new("orc".to_string(), 2, 1, "orc".to_string(), 5, defense, 0, 0, 150, 10_000, EntityController::AI),
+        ]
+    }
+    
+    #[test]
+    fn real_hits_stay_within_the_estimate() {
+        let dungeon = dungeon_from(&["####", "#..#", "####"]);
+        let registry = GameObjectRegistry::load_from_config(&create_default_config());
+        let mut entities = duel(20, 25, 3);
+        let (min, max) = estimate_damage(&entities[0], &entities[1]);
+        assert_eq!((min, max), (12, 22));
+        
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut seen = Vec::new();
+        for _ in 0..200 {
+            let before = entities[1].current_health;
+            attack_entity(&mut entities, &dungeon, 0, 1, &registry, &mut Vec::new(), &mut rng);
+            seen.push(before - entities[1].current_health);
+        }
+        assert!(seen.iter().all(|damage| (min..=max).contains(damage)));
+        assert!(seen.contains(&min) && seen.contains(&max));
+    }
+    
+    #[test]
+    fn the_estimate_never_drops_below_one_damage() {
+        let entities = duel(4, 0, 50);
+        assert_eq!(estimate_damage(&entities[0], &entities[1]), (1, 1));
+    }
+    
+    /// The default config's objects, with players' attacks piercing 4 tiles
+    fn spear_registry() -> GameObjectRegistry {
+        let mut config = create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "player") {
+            obj.line_attack_range = Some(4);
+        }
+        GameObjectRegistry::load_from_config(&config)
+    }
+    
+    /// A player at (1, 1) with orcs at each of `orc_xs` on the same row
+    fn line_up(orc_xs: &[usize]) -> Vec<Entity> {
+        let mut entities = vec![Entity::new("p1".to_string(), 1, 1, "player".to_string(), 10, 0, 0, 0, 150, 100, EntityController::Player)];
+        for (i, &x) in orc_xs.iter().enumerate() {
+            entities.push(Entity::new(format!("orc_{}", i), x, 1, "orc".to_string(), 5, 0, 0, 0, 150, 50, EntityController::AI));
+        }
+        entities
+    }
+    
+    #[test]
+    fn a_line_attack_hits_every_enemy_in_the_line() {
+        let dungeon = dungeon_from(&["#######", "#.....#", "#######"]);
+        let mut entities = line_up(&[2, 3]);
+        
+        let messages = attack_line(&mut entities, &dungeon, 0, (1, 0), false, &spear_registry(), &mut Vec::new(), &mut StdRng::seed_from_u64(1));
+        assert_eq!(messages.len(), 2);
+        assert!(entities[1..].iter().all(|orc| orc.current_health == 40));
+    }
+    
+    #[test]
+    fn a_line_attack_stops_at_a_wall() {
+        let dungeon = dungeon_from(&["#######", "#..#..#", "#######"]);
+        let mut entities = line_up(&[2, 4]);
+        
+        let messages = attack_line(&mut entities, &dungeon, 0, (1, 0), false, &spear_registry(), &mut Vec::new(), &mut StdRng::seed_from_u64(1));
+        assert_eq!(messages.len(), 1);
+        assert_eq!(entities[1].current_health, 40);
+        assert_eq!(entities[2].current_health, 50);
+    }
+    
+    /// Damage a 20-attack fire hit does to an orc with `resistances`
+    fn fire_hit(resistances: &[(&str, i32)]) -> u32 {
+        let dungeon = dungeon_from(&["####", "#..#", "####"]);
+        let registry = GameObjectRegistry::load_from_config(&create_default_config());
+        let mut entities = duel(20, 0, 0);
+        entities[0].damage_type = "fire".to_string();
+        entities[1].resistances = resistances.iter().map(|&(damage_type, percent)| (damage_type.to_string(), percent)).collect();
+        
+        attack_entity(&mut entities, &dungeon, 0, 1, &registry, &mut Vec::new(), &mut StdRng::seed_from_u64(1));
+        entities[1].max_health - entities[1].current_health
+    }
+    
+    #[test]
+    fn resistances_scale_damage_of_their_type() {
+        assert_eq!(fire_hit(&[]), 20);
+        assert_eq!(fire_hit(&[("fire", 50)]), 10);
+        assert_eq!(fire_hit(&[("poison", 50)]), 20);
+    }
+    
+    #[test]
+    fn immunity_blocks_all_damage() {
+        assert_eq!(fire_hit(&[("fire", 100)]), 0);
+    }
+    
+    #[test]
+    fn weakness_adds_damage() {
+        assert_eq!(fire_hit(&[("fire", -50)]), 30);
+    }
+    
+    #[test]
+    fn attackers_turn_toward_their_target_in_all_four_directions() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#...#", "#...#", "#####"]);
+        let registry = GameObjectRegistry::load_from_config(&create_default_config());
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut entities = duel(10, 0, 0);
+        (entities[0].x, entities[0].y) = (2, 2);
+        
+        // The sprite stays mirrored the last horizontal way through vertical attacks
+        for ((x, y), facing, facing_right) in [
+            ((3, 2), Direction::Right, true),
+            ((2, 1), Direction::Up, true),
+            ((1, 2), Direction::Left, false),
+            ((2, 3), Direction::Down, false),
+        ] {
+            (entities[1].x, entities[1].y) = (x, y);
+            attack_entity(&mut entities, &dungeon, 0, 1, &registry, &mut Vec::new(), &mut rng);
+            assert_eq!((entities[0].facing, entities[0].facing_right), (facing, facing_right));
+        }
+    }
+    
+    #[test]
+    fn a_line_attack_only_hits_teammates_with_friendly_fire_on() {
+        let dungeon = dungeon_from(&["#######", "#.....#", "#######"]);
+        for friendly_fire in [false, true] {
+            let mut entities = line_up(&[4]);
+            entities.push(Entity::new("p2".to_string(), 2, 1, "player".to_string(), 10, 0, 0, 0, 150, 100, EntityController::Player));
+            
+            attack_line(&mut entities, &dungeon, 0, (1, 0), friendly_fire, &spear_registry(), &mut Vec::new(), &mut StdRng::seed_from_u64(1));
+            assert!(entities[1].current_health < 50);
+            assert_eq!(entities[2].current_health < 100, friendly_fire);
+        }
+    }
+    
+    #[test]
+    fn a_monster_line_attack_never_hits_monsters() {
+        let dungeon = dungeon_from(&["#######", "#.....#", "#######"]);
+        let mut config = create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.line_attack_range = Some(4);
+        }
+        let registry = GameObjectRegistry::load_from_config(&config);
+        let mut entities = line_up(&[4, 2]);  // orc_0 attacks through orc_1 at the player
+        
+        attack_line(&mut entities, &dungeon, 1, (-1, 0), true, &registry, &mut Vec::new(), &mut StdRng::seed_from_u64(1));
+        assert!(entities[0].current_health < 100);
+        assert_eq!(entities[2].current_health, 50);
+    }
+    
+    #[test]
+    fn drops_with_no_free_tile_left_pile_up_on_the_corpse() {
+        let dungeon = dungeon_from(&["####", "#..#", "####"]);
+        let mut consumables = vec![Consumable { id: "potion_0".to_string(), x: 2, y: 1, object_id: "potion".to_string(), dropped_turns_ago: None }];
+        let drops = ["iron_key", "sword", "potion"].map(String::from).to_vec();
+        
+        drop_items(&dungeon, &mut consumables, 1, 1, "orc", drops);
+        let dropped: Vec<(&str, usize, usize)> = consumables[1..].iter().map(|c| (c.object_id.as_str(), c.x, c.y)).collect();
+        assert_eq!(dropped, vec![("iron_key", 1, 1), ("sword", 1, 1), ("potion", 1, 1)]);
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+
+/// Player actions the server knows how to handle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandKind {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Attack,
+    LineAttack,
+    Taunt,
+    ThrowItem,
+    AutoMoveTo,
+    Examine,
+    Ready,
+}
+
+/// A supported action, served to clients so they can build their UI dynamically
+/// and detect which features the server supports
+#[derive(Serialize, Debug)]
+pub struct CommandSpec {
+    #[serde(skip)]
+    pub kind: CommandKind,
+    pub action: &'static str,  // Canonical action name sent in PlayerCommand.action
+    pub aliases: &'static [&'static str],  // Alternative action names accepted by the server
+    pub params: &'static [&'static str],  // PlayerCommand fields this action reads
+    pub description: &'static str,
+}
+
+/// Central command registry - handle_command dispatches over this list so it stays in sync with behavior
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        kind: CommandKind::MoveUp,
+        action: "move_up",
+        aliases: &["up", "north"],
+        params: &[],
+        description: "Move up one tile (bumping attacks enemies and opens chests)",
+    },
+    CommandSpec {
+        kind: CommandKind::MoveDown,
+        action: "move_down",
+        aliases: &["down", "south"],
+        params: &[],
+        description: "Move down one tile (bumping attacks enemies and opens chests)",
+    },
+    CommandSpec {
+        kind: CommandKind::MoveLeft,
+        action: "move_left",
+        aliases: &["left", "west"],
+        params: &[],
+        description: "Move left one tile (bumping attacks enemies and opens chests)",
+    },
+    CommandSpec {
+        kind: CommandKind::MoveRight,
+        action: "move_right",
+        aliases: &["right", "east"],
+        params: &[],
+        description: "Move right one tile (bumping attacks enemies and opens chests)",
+    },
+    CommandSpec {
+        kind: CommandKind::Attack,
+        action: "attack",
+        aliases: &["hit"],
+        params: &["direction", "target_x", "target_y"],
+        description: "Attack an adjacent tile without moving, given a direction (up/down/left/right) or target coordinates",
+    },
+    CommandSpec {
+        kind: CommandKind::LineAttack,
+        action: "line_attack",
+        aliases: &["pierce"],
+        params: &["direction", "target_x", "target_y"],
+        description: "Piercing attack hitting everything in a line up to the character's line_attack_range, stopped by walls",
+    },
+    CommandSpec {
+        kind: CommandKind::Taunt,
+        action: "taunt",
+        aliases: &["provoke"],
+        params: &[],
+        description: "Force monsters within the character's taunt_radius to chase and attack you for a few turns, whoever else is closer",
+    },
+    CommandSpec {
+        kind: CommandKind::ThrowItem,
+        action: "throw_item",
+        aliases: &["throw"],
+        params: &["item", "target_x", "target_y"],
+        description: "Throw a carried item at a tile in range and sight: healing items heal, damaging items hurt whoever is there",
+    },
+    CommandSpec {
+        kind: CommandKind::AutoMoveTo,
+        action: "auto_move_to",
+        aliases: &["travel"],
+        params: &["target_x", "target_y"],
+        description: "Take one step along the shortest path to a tile; send again without a target to keep going. Stops when a new monster comes into view or the player gets hurt",
+    },
+    CommandSpec {
+        kind: CommandKind::Examine,
+        action: "examine",
+        aliases: &["look", "inspect"],
+        params: &["target_x", "target_y"],
+        description: "Describe a tile - its floor, whoever stands there with full stats, any chest or item, and stairs - without using the turn. Answered only to the sender, as examine_result",
+    },
+    CommandSpec {
+        kind: CommandKind::Ready,
+        action: "ready",
+        aliases: &[],
+        params: &[],
+        description: "Mark yourself ready in the lobby; the game starts once enough players are connected and all of them are ready",
+    },
+];
+
+/// Find the command for an action name or one of its aliases
+pub fn lookup(action: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.action == action || spec.aliases.contains(&action))
+}
@@ -1,16 +1,342 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::collections::HashMap;
 use crate::game_object::GameObject;
+use crate::tile_registry::TileRegistry;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LevelConfig {
     pub level_number: u32,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,  // Human-readable name, e.g. for previewing with /api/map?name=... (unique across levels)
     pub min_rooms: u32,
     pub max_rooms: u32,
     pub min_monsters_per_room: u32,
     pub max_monsters_per_room: u32,
     pub chest_count: u32,
     pub allowed_monsters: Vec<String>,  // IDs of monster characters that can spawn
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_limit: Option<u32>,  // Level fails if not cleared within this many turns (None = no limit)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corridor_width: Option<u32>,  // Corridor width in tiles (None = 1)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corridor_winding_percent: Option<u32>,  // Chance (0-100) for a corridor to take an extra bend
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stairs_up: Option<bool>,  // Place stairs back up to the previous level near the spawn
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stairs_placement: Option<StairsPlacement>,  // Where the stairs down go (None = farthest room)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_clear: Option<bool>,  // The stairs down stay shut until every monster on the level is dead
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub win_condition: Option<WinCondition>,  // What clears the level (None = reaching the stairs)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile_theme: Option<TileTheme>,  // Floor/wall tiles to use on this level (None = all tiles)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub survival_mode: Option<bool>,  // No stairs; monsters keep respawning and the goal is to last
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub survival_spawn_percent: Option<u32>,  // Base per-turn monster spawn chance in survival mode (default 10)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pressure_turns: Option<u32>,  // After this many turns without combat a distant monster heads for the players (None = off)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leash_to_room: Option<bool>,  // Idle monsters stay in their spawn room (they still leave it to chase players)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_walkable_percent: Option<u32>,  // Aim for this share of floor tiles (higher = more open), by varying room count
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_retries: Option<u32>,  // Regenerate a layout that fit fewer than min_rooms up to this many times (default 3)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawn_safe_radius: Option<u32>,  // No random monsters or chests within this many tiles (Chebyshev) of the player spawn
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_chest_spacing: Option<u32>,  // Minimum tiles (Chebyshev) between generated chests (default 0 = any)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_chance_percent: Option<u32>,  // Chance one room (not the spawn or stairs room) is a treasure vault (default 0 = never)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_chests: Option<u32>,  // Chests piled in the vault on top of chest_count (default 3)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_guards: Option<u32>,  // Extra monsters guarding the vault on top of its room's usual ones (default 2)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_guard_percent: Option<u32>,  // Health and attack of the vault's monsters as % of normal (default 150)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_door: Option<String>,  // Door object put on each entrance to the vault, with one key per door left reachable outside if it needs one (None = no doors)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_monster_spacing: Option<u32>,  // Minimum tiles (Chebyshev) between generated monsters (default 0 = any)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub music_track: Option<String>,  // Background music for the client to play on this level (server only forwards it)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ambiance: Option<String>,  // Ambient sound loop for this level (server only forwards it)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_spawns: Option<Vec<FixedSpawn>>,  // Hand-placed monsters, consumables and chests (placed in addition to random ones)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triggers: Option<Vec<TileTrigger>>,  // Regions that fire a scripted action the first time a player steps in
+}
+
+/// An object hand-placed on a level in the editor. Layouts are random, so it goes on the
+/// nearest free floor tile to (x, y) when that tile is a wall or taken.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FixedSpawn {
+    pub object_id: String,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// A rectangle of map tiles that fires its action the first time any player steps into it
+/// (ambushes, scripted reveals). Fires once per visit to the level; a restart re-arms it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TileTrigger {
+    pub x: usize,  // Top-left corner
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub action: TriggerAction,
+}
+
+impl TileTrigger {
+    /// Whether a position lies within the region
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// What a tile trigger does. Written as `action = { type = "message", text = "..." }`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerAction {
+    SpawnMonsters { object_id: String, count: u32 },  // Monsters appear around the middle of the region
+    OpenDoors { object_id: String },  // Open every closed interactable of this object ID on the level
+    CloseDoors { object_id: String },  // Shut every open one (unless something stands in the doorway)
+    Message { text: String },  // Show a level event to everyone
+}
+
+/// Where a level's stairs down go. Written as `stairs_placement = "nearest"`, or
+/// `stairs_placement = { at = { x = 40, y = 12 } }` for a fixed spot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StairsPlacement {
+    #[default]
+    Farthest,  // Room farthest from the player spawn
+    Nearest,  // Closest room other than the spawn room
+    RandomRoom,  // Any room other than the spawn room
+    At { x: usize, y: usize },  // Nearest walkable tile to (x, y) - layouts are random, so it may be a wall
+}
+
+/// How a level is won. Written as `win_condition = "kill_boss"`, or
+/// `win_condition = { survive_turns = 50 }` / `{ collect_items = 5 }`. Anything but the stairs
+/// takes the party down as soon as it's met, and the stairs stay shut meanwhile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WinCondition {
+    #[default]
+    ReachStairs,  // Every alive player confirms on the stairs down
+    KillBoss,  // Kill every boss monster (`boss = true`) on the level
+    SurviveTurns(u32),  // Last this many turns
+    CollectItems(u32),  // Pick up this many items between the party
+}
+
+/// Weighted subset of tiles used for a level's floors and walls, e.g. dirt for early
+/// levels and stone deeper down. Weights are relative (a tile with 3 shows up 3x as often as 1).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TileTheme {
+    #[serde(default)]
+    pub floor_tiles: HashMap<String, u32>,  // Walkable tile id -> weight
+    #[serde(default)]
+    pub wall_tiles: HashMap<String, u32>,  // Wall tile id -> weight
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corridor_tile_id: Option<String>,  // Walkable tile corridors are carved with (None = same tiles as room floors)
+}
+
+impl TileTheme {
+    /// Problems with the theme: ids that aren't tiles, or tiles used in the wrong role
+    pub fn validate(&self, tile_registry: &TileRegistry) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (ids, walkable, role) in [(&self.floor_tiles, true, "floor"), (&self.wall_tiles, false, "wall")] {
+            for id in ids.keys() {
+                match tile_registry.get_tile(id) {
+                    None => errors.push(format!("{} tile '{}' does not exist", role, id)),
+                    Some(tile) if tile.walkable != walkable => errors.push(format!("'{}' cannot be used as a {} tile", id, role)),
+                    Some(_) => {}
+                }
+            }
+        }
+        if let Some(id) = &self.corridor_tile_id {
+            match tile_registry.get_tile(id) {
+                None => errors.push(format!("corridor tile '{}' does not exist", id)),
+                Some(tile) if !tile.walkable => errors.push(format!("'{}' cannot be used as a corridor tile", id)),
+                Some(_) => {}
+            }
+        }
+        errors
+    }
+}
+
+/// Scales monster stats and counts with the number of connected players.
+/// Off by default so solo and co-op play the same unless configured.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DifficultyScaling {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub health_percent_per_player: u32,  // Extra monster health per additional player (e.g., 25 = +25%)
+    #[serde(default)]
+    pub attack_percent_per_player: u32,  // Extra monster attack per additional player
+    #[serde(default)]
+    pub monster_count_percent_per_player: u32,  // Extra monsters per room per additional player
+    #[serde(default)]
+    pub depth: DepthScaling,  // Stat scaling by dungeon level (independent of `enabled`)
+}
+
+/// Makes monsters stronger the deeper they spawn below their tier (the level they're
+/// balanced for), so one config entry serves every floor. Off by default.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DepthScaling {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub health_percent_per_level: u32,  // Extra health per level below the monster's tier (e.g., 15 = +15%)
+    #[serde(default)]
+    pub attack_percent_per_level: u32,  // Extra attack per level below the monster's tier
+}
+
+impl DepthScaling {
+    /// Percentage multiplier (100 = unchanged) for a monster of `tier` spawned on level `depth`
+    fn multiplier_percent(&self, percent_per_level: u32, tier: u32, depth: u32) -> u32 {
+        if !self.enabled {
+            return 100;
+        }
+        100 + percent_per_level * depth.saturating_sub(tier)
+    }
+    
+    pub fn scale_health(&self, health: u32, tier: u32, depth: u32) -> u32 {
+        health * self.multiplier_percent(self.health_percent_per_level, tier, depth) / 100
+    }
+    
+    pub fn scale_attack(&self, attack: i32, tier: u32, depth: u32) -> i32 {
+        attack * self.multiplier_percent(self.attack_percent_per_level, tier, depth) as i32 / 100
+    }
+}
+
+impl DifficultyScaling {
+    /// Percentage multiplier (100 = unchanged) for the given bonus, counting players beyond the first
+    fn multiplier_percent(&self, percent_per_player: u32, player_count: usize) -> u32 {
+        if !self.enabled {
+            return 100;
+        }
+        let extra_players = player_count.saturating_sub(1) as u32;
+        100 + percent_per_player * extra_players
+    }
+
+    pub fn scale_health(&self, health: u32, player_count: usize) -> u32 {
+        health * self.multiplier_percent(self.health_percent_per_player, player_count) / 100
+    }
+
+    pub fn scale_attack(&self, attack: i32, player_count: usize) -> i32 {
+        attack * self.multiplier_percent(self.attack_percent_per_player, player_count) as i32 / 100
+    }
+
+    /// Scale a per-room monster count, rounding up so any bonus adds at least one monster
+    pub fn scale_monster_count(&self, count: usize, player_count: usize) -> usize {
+        let multiplier = self.multiplier_percent(self.monster_count_percent_per_player, player_count) as usize;
+        (count * multiplier).div_ceil(100)
+    }
+}
+
+/// Pixel size of one sprite on a sprite sheet (SpriteCoord x/y are in these units)
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct SpriteSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SpriteSize {
+    /// Size of the sprites on the bundled sheets
+    pub const DEFAULT: SpriteSize = SpriteSize { width: 32, height: 32 };
+}
+
+/// Baseline stats for objects that don't set their own, keyed by object_type in
+/// `GameConfig::defaults` (e.g. `[defaults.character]`)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct StatDefaults {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<u32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attack: Option<i32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defense: Option<i32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attack_spread_percent: Option<u32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crit_chance_percent: Option<u32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crit_damage_percent: Option<u32>,
+}
+
+impl StatDefaults {
+    /// Fill in the stats `obj` leaves unset. Stats given in its legacy `properties` map count as set.
+    pub fn apply_to(&self, obj: &mut GameObject) {
+        let has_property = |obj: &GameObject, key: &str| obj.properties.contains_key(key);
+        obj.health = obj.health.or(self.health);
+        if !has_property(obj, "attack") {
+            obj.attack = obj.attack.or(self.attack);
+        }
+        if !has_property(obj, "defense") {
+            obj.defense = obj.defense.or(self.defense);
+        }
+        if !has_property(obj, "attack_spread_percent") {
+            obj.attack_spread_percent = obj.attack_spread_percent.or(self.attack_spread_percent);
+        }
+        if !has_property(obj, "crit_chance_percent") {
+            obj.crit_chance_percent = obj.crit_chance_percent.or(self.crit_chance_percent);
+        }
+        if !has_property(obj, "crit_damage_percent") {
+            obj.crit_damage_percent = obj.crit_damage_percent.or(self.crit_damage_percent);
+        }
+    }
+}
+
+/// A sprite sheet in `GameConfig::sprite_sheets`, which objects pick by name (`sprite_sheet`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpriteSheet {
+    pub name: String,  // What objects' sprite_sheet refers to (e.g., "tiles.png")
+    pub path: String,  // Image file, relative to the assets directory
+    pub sprite_size: SpriteSize,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<u32>,  // Sprites across the sheet (None = not declared)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u32>,  // Sprites down the sheet (None = not declared)
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -18,19 +344,429 @@ pub struct GameConfig {
     pub game_objects: Vec<GameObject>,
     #[serde(default)]
     pub levels: Vec<LevelConfig>,
+    #[serde(default)]
+    pub difficulty_scaling: DifficultyScaling,
+    #[serde(default)]
+    pub permadeath: bool,  // A party wipe ends the run (game over) instead of restarting the level
+    #[serde(default)]
+    pub shared_party_vision: bool,  // Every player sees the rooms any alive teammate has explored
+    #[serde(default)]
+    pub wall_attacks_allowed: bool,  // Attacking a wall swings at it harmlessly and uses the turn (false = refused, turn kept)
+    #[serde(default)]
+    pub friendly_fire: bool,  // Players' line attacks hit teammates standing in the line (monsters' never hit monsters)
+    #[serde(default)]
+    pub broadcast_coalesce_ms: u64,  // Batch updates arriving within this many ms into one per connection (0 = send each at once)
+    #[serde(default)]
+    pub min_players_to_start: u32,  // Wait in a lobby until this many players are connected and ready (0 or 1 = start right away)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_pathfinding_budget: Option<u32>,  // Tiles monster pathfinding may explore per turn, closest monsters first; the rest step greedily (None = unlimited)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_entities: Option<u32>,  // Most live monsters on a level across every spawn path - extra spawns are skipped (None = 60)
+    #[serde(default)]
+    pub rest_heal_percent: u32,  // Players heal this % of their max health when descending to the next level (0 = no rest, 100 = full)
+    #[serde(default)]
+    pub monsters_avoid_stairs: bool,  // Monsters path around the stairs instead of standing on them
+    #[serde(default)]
+    pub monsters_avoid_chests: bool,  // Monsters path around opened chests (and the loot on them) too - closed ones always block
+    #[serde(default)]
+    pub admin_commands: bool,  // Accept admin commands: a new_game command mid-run and the /api/admin/... endpoints
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub defaults: HashMap<String, StatDefaults>,  // object_type -> stats its objects get when they don't set them (players and monsters are "character")
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sprite_sheets: Vec<SpriteSheet>,  // Every sheet objects draw from - each object's sprite_sheet must name one
+}
+
+/// Why `GameConfig::load` failed
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),  // The file couldn't be read (missing, no permission, ...)
+    Parse { message: String, line: Option<usize> },  // Not valid TOML or not shaped like a config (line is 1-based)
+    Validation(Vec<String>),  // Parsed fine but has invalid values
+}
+
+impl ConfigError {
+    /// The file doesn't exist (safe to create a default one in its place)
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ConfigError::Io(e) if e.kind() == std::io::ErrorKind::NotFound)
+    }
+    
+    /// A parse error in `content`, placed on the line where `span` starts
+    fn parse(content: &str, message: &str, span: Option<std::ops::Range<usize>>) -> Self {
+        ConfigError::Parse {
+            message: message.to_string(),
+            line: span.map(|span| content[..span.start].matches('\n').count() + 1),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse { message, line: Some(line) } => write!(f, "parse error on line {}: {}", line, message),
+            ConfigError::Parse { message, line: None } => write!(f, "parse error: {}", message),
+            ConfigError::Validation(errors) => write!(f, "invalid config: {}", errors.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
 }
 
 impl GameConfig {
-    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
         let content = fs::read_to_string(path)?;
-        let config: GameConfig = toml::from_str(&content)?;
+        let config: GameConfig = toml::from_str(&content)
+            .map_err(|e| ConfigError::parse(&content, e.message(), e.span()))?;
+        let mut errors = config.validate_sprite_sheets();
+        errors.extend(config.validate_rarities());
+        errors.extend(config.validate_win_conditions());
+        errors.extend(config.validate_level_names());
+        if !errors.is_empty() {
+            return Err(ConfigError::Validation(errors));
+        }
         Ok(config)
     }
+    
+    /// Most live monsters a level may hold, from `max_entities` (MAX_MONSTERS when unset)
+    pub fn monster_cap(&self) -> usize {
+        self.max_entities.map_or(crate::entity::MAX_MONSTERS, |max| max as usize)
+    }
+    
+    /// The sheet in `sprite_sheets` called `name`
+    pub fn sprite_sheet(&self, name: &str) -> Option<&SpriteSheet> {
+        self.sprite_sheets.iter().find(|sheet| sheet.name == name)
+    }
+    
+    /// Object IDs referenced by objects or levels that no game object defines, each described
+    /// with where it's used (e.g. "level 2 allowed_monsters: 'orcc'")
+    pub fn find_dangling_references(&self) -> Vec<String> {
+        let defined: std::collections::HashSet<&str> = self.game_objects.iter().map(|obj| obj.id.as_str()).collect();
+        let mut references: Vec<(String, &str)> = Vec::new();
+        
+        for obj in &self.game_objects {
+            if let Some(spawn) = &obj.on_death_spawn {
+                references.push((format!("object '{}' on_death_spawn", obj.id), &spawn.object_id));
+            }
+            if let Some(drop) = &obj.drops_on_death {
+                references.push((format!("object '{}' drops_on_death", obj.id), drop));
+            }
+            for item in obj.equipment.iter().flatten() {
+                references.push((format!("object '{}' equipment", obj.id), item));
+            }
+            if let Some(key) = &obj.requires_key {
+                references.push((format!("object '{}' requires_key", obj.id), key));
+            }
+        }
+        for level in &self.levels {
+            for monster in &level.allowed_monsters {
+                references.push((format!("level {} allowed_monsters", level.level_number), monster));
+            }
+            for spawn in level.fixed_spawns.iter().flatten() {
+                references.push((format!("level {} fixed_spawns", level.level_number), &spawn.object_id));
+            }
+            if let Some(door) = &level.vault_door {
+                references.push((format!("level {} vault_door", level.level_number), door));
+            }
+            for trigger in level.triggers.iter().flatten() {
+                match &trigger.action {
+                    TriggerAction::SpawnMonsters { object_id, .. }
+                    | TriggerAction::OpenDoors { object_id }
+                    | TriggerAction::CloseDoors { object_id } => {
+                        references.push((format!("level {} triggers", level.level_number), object_id));
+                    }
+                    TriggerAction::Message { .. } => {}
+                }
+            }
+            if let Some(theme) = &level.tile_theme {
+                for tile in theme.floor_tiles.keys().chain(theme.wall_tiles.keys()).chain(theme.corridor_tile_id.iter()) {
+                    references.push((format!("level {} tile_theme", level.level_number), tile));
+                }
+            }
+        }
+        
+        references.into_iter()
+            .filter(|(_, id)| !defined.contains(id))
+            .map(|(location, id)| format!("{}: '{}'", location, id))
+            .collect()
+    }
+    
+    fn validate_rarities(&self) -> Vec<String> {
+        self.game_objects.iter()
+            .filter_map(|obj| obj.rarity.as_ref().map(|rarity| (obj, rarity)))
+            .filter(|(_, rarity)| !crate::game_object::RARITIES.contains(&rarity.as_str()))
+            .map(|(obj, rarity)| format!("object '{}' has unknown rarity '{}' (expected one of {})",
+                obj.id, rarity, crate::game_object::RARITIES.join(", ")))
+            .collect()
+    }
+    
+    /// kill_boss levels need a boss placed by fixed_spawns, or they'd be won on the first turn
+    fn validate_win_conditions(&self) -> Vec<String> {
+        let is_boss = |id: &str| self.game_objects.iter().any(|obj| obj.id == id && obj.boss.unwrap_or(false));
+        self.levels.iter()
+            .filter(|level| level.win_condition == Some(WinCondition::KillBoss))
+            .filter(|level| !level.fixed_spawns.iter().flatten().any(|spawn| is_boss(&spawn.object_id)))
+            .map(|level| format!("level {} has a kill_boss win condition but no boss in its fixed_spawns", level.level_number))
+            .collect()
+    }
+    
+    /// Level names must be unique so they can be looked up by name
+    fn validate_level_names(&self) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        self.levels.iter()
+            .filter_map(|level| level.name.as_deref().map(|name| (level.level_number, name)))
+            .filter(|&(_, name)| !seen.insert(name))
+            .map(|(number, name)| format!("level {} reuses the level name '{}'", number, name))
+            .collect()
+    }
+    
+    fn validate_sprite_sheets(&self) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        let mut errors: Vec<String> = self.sprite_sheets.iter()
+            .filter(|sheet| !seen.insert(sheet.name.as_str()))
+            .map(|sheet| format!("sprite sheet '{}' is defined twice", sheet.name))
+            .collect();
+        errors.extend(self.sprite_sheets.iter()
+            .filter(|sheet| sheet.sprite_size.width == 0 || sheet.sprite_size.height == 0)
+            .map(|sheet| format!("sprite sheet '{}' has an empty sprite_size ({}x{})",
+                sheet.name, sheet.sprite_size.width, sheet.sprite_size.height)));
+        
+        // Every sheet objects use has to be defined, and their sprites must lie on it when its grid is declared
+        for obj in &self.game_objects {
+            let Some(name) = &obj.sprite_sheet else {
+                continue;
+            };
+            let Some(sheet) = self.sprite_sheet(name) else {
+                errors.push(format!("object '{}' uses sprite sheet '{}', which sprite_sheets doesn't define", obj.id, name));
+                continue;
+            };
+            let off_sheet = obj.sprites.iter().any(|sprite| {
+                sheet.columns.is_some_and(|columns| sprite.x >= columns)
+                    || sheet.rows.is_some_and(|rows| sprite.y >= rows)
+            });
+            if off_sheet {
+                errors.push(format!("object '{}' has a sprite outside its sheet '{}'", obj.id, name));
+            }
+        }
+        errors
+    }
 
     pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
         let content = toml::to_string(self)?;
         fs::write(path, content)?;
         Ok(())
     }
+    
+    /// Add a fixed spawn to a level in the config file at `path`, or take it out with `remove`.
+    /// Only that entry is touched, so the file keeps its comments and layout (unlike `save`).
+    /// Adding a spawn that's already there, or removing one that isn't, leaves the file as is.
+    pub fn edit_fixed_spawn(path: &str, level_number: u32, spawn: &FixedSpawn, remove: bool) -> Result<(), ConfigError> {
+        let content = fs::read_to_string(path)?;
+        let mut doc: toml_edit::DocumentMut = content.parse()
+            .map_err(|e: toml_edit::TomlError| ConfigError::parse(&content, e.message(), e.span()))?;
+        let level = doc.get_mut("levels")
+            .and_then(|levels| levels.as_array_of_tables_mut())
+            .and_then(|levels| levels.iter_mut().find(|level| {
+                level.get("level_number").and_then(|number| number.as_integer()) == Some(level_number as i64)
+            }))
+            .ok_or_else(|| ConfigError::Validation(vec![format!("level {} is not in {}", level_number, path)]))?;
+        
+        let is_spawn = |entry: &dyn toml_edit::TableLike| {
+            entry.get("object_id").and_then(|id| id.as_str()) == Some(spawn.object_id.as_str())
+                && entry.get("x").and_then(|x| x.as_integer()) == Some(spawn.x as i64)
+                && entry.get("y").and_then(|y| y.as_integer()) == Some(spawn.y as i64)
+        };
+        let mut entry = toml_edit::Table::new();
+        entry.insert("object_id", toml_edit::value(spawn.object_id.as_str()));
+        entry.insert("x", toml_edit::value(spawn.x as i64));
+        entry.insert("y", toml_edit::value(spawn.y as i64));
+        
+        // Written either as [[levels.fixed_spawns]] tables or as an inline array
+        let now_empty = match level.get_mut("fixed_spawns") {
+            Some(toml_edit::Item::ArrayOfTables(spawns)) => {
+                if remove {
+                    spawns.retain(|existing| !is_spawn(existing));
+                } else if !spawns.iter().any(|existing| is_spawn(existing)) {
+                    spawns.push(entry);
+                }
+                spawns.is_empty()
+            }
+            Some(item) => {
+                let spawns = item.as_array_mut()
+                    .ok_or_else(|| ConfigError::Validation(vec![format!("level {} fixed_spawns is not a list", level_number)]))?;
+                let is_spawn_value = |value: &toml_edit::Value| value.as_inline_table().is_some_and(|existing| is_spawn(existing));
+                if remove {
+                    spawns.retain(|existing| !is_spawn_value(existing));
+                } else if !spawns.iter().any(is_spawn_value) {
+                    spawns.push(entry.into_inline_table());
+                }
+                spawns.is_empty()
+            }
+            None if !remove => {
+                let mut spawns = toml_edit::ArrayOfTables::new();
+                spawns.push(entry);
+                level.insert("fixed_spawns", toml_edit::Item::ArrayOfTables(spawns));
+                false
+            }
+            None => false,
+        };
+        if now_empty {
+            level.remove("fixed_spawns");
+        }
+        
+        fs::write(path, doc.to_string())?;
+        Ok(())
+    }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const LEVELS: &str = r#"# Hand-made levels
+[[levels]]
+level_number = 1  # Where everyone starts
+name = "start"
+
+[[levels]]
+level_number = 2
+fixed_spawns = [{ object_id = "orc", x = 3, y = 4 }]
+"#;
+    
+    /// Fixed spawns of each level in the file, as (level number, object ID, x, y)
+    fn fixed_spawns(path: &str) -> Vec<(i64, String, i64, i64)> {
+        let doc: toml::Table = toml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let mut spawns = Vec::new();
+        for level in doc["levels"].as_array().unwrap() {
+            for spawn in level.get("fixed_spawns").and_then(|s| s.as_array()).into_iter().flatten() {
+                spawns.push((
+                    level["level_number"].as_integer().unwrap(),
+                    spawn["object_id"].as_str().unwrap().to_string(),
+                    spawn["x"].as_integer().unwrap(),
+                    spawn["y"].as_integer().unwrap(),
+                ));
+            }
+        }
+        spawns
+    }
+    
+    #[test]
+    fn editing_fixed_spawns_keeps_the_rest_of_the_file() {
+        let path = std::env::temp_dir().join(format!("fixed_spawns_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, LEVELS).unwrap();
+        let chest = FixedSpawn { object_id: "chest".to_string(), x: 5, y: 6 };
+        let orc = FixedSpawn { object_id: "orc".to_string(), x: 3, y: 4 };
+        
+        GameConfig::edit_fixed_spawn(path, 1, &chest, false).unwrap();
+        GameConfig::edit_fixed_spawn(path, 1, &chest, false).unwrap();
+        GameConfig::edit_fixed_spawn(path, 2, &chest, false).unwrap();
+        assert_eq!(fixed_spawns(path), vec![
+            (1, "chest".to_string(), 5, 6),
+            (2, "orc".to_string(), 3, 4),
+            (2, "chest".to_string(), 5, 6),
+        ]);
+        let content = fs::read_to_string(path).unwrap();
+        assert!(content.starts_with("# Hand-made levels\n"));
+        assert!(content.contains("level_number = 1  # Where everyone starts"));
+        
+        GameConfig::edit_fixed_spawn(path, 1, &chest, true).unwrap();
+        GameConfig::edit_fixed_spawn(path, 2, &orc, true).unwrap();
+        GameConfig::edit_fixed_spawn(path, 2, &chest, true).unwrap();
+        assert!(fixed_spawns(path).is_empty());
+        assert!(!fs::read_to_string(path).unwrap().contains("fixed_spawns"));
+        
+        assert!(matches!(GameConfig::edit_fixed_spawn(path, 3, &chest, false), Err(ConfigError::Validation(_))));
+        fs::remove_file(path).unwrap();
+    }
+    
+    #[test]
+    fn dangling_object_references_are_reported() {
+        let mut config = crate::api::create_default_config();
+        let before = config.find_dangling_references();
+        
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.drops_on_death = Some("golden_key".to_string());
+        }
+        let mut level = crate::game_state::tests::level("allowed_monsters = [\"orc\", \"dragon\"]");
+        level.fixed_spawns = Some(vec![FixedSpawn { object_id: "ghost".to_string(), x: 3, y: 4 }]);
+        config.levels.push(level);
+        
+        let added: Vec<String> = config.find_dangling_references().into_iter()
+            .filter(|reference| !before.contains(reference))
+            .collect();
+        assert_eq!(added, vec![
+            "object 'orc' drops_on_death: 'golden_key'".to_string(),
+            "level 1 allowed_monsters: 'dragon'".to_string(),
+            "level 1 fixed_spawns: 'ghost'".to_string(),
+        ]);
+    }
+    
+    /// Load `content` as a config file
+    fn load(content: &str) -> Result<GameConfig, ConfigError> {
+        let path = std::env::temp_dir().join(format!("load_{}_{}.toml", std::process::id(), content.len()));
+        fs::write(&path, content).unwrap();
+        let result = GameConfig::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        result
+    }
+    
+    #[test]
+    fn a_missing_config_file_is_an_io_error() {
+        let path = std::env::temp_dir().join(format!("missing_{}.toml", std::process::id()));
+        let error = GameConfig::load(path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, ConfigError::Io(_)));
+        assert!(error.is_not_found());
+    }
+    
+    #[test]
+    fn a_config_that_isnt_toml_is_a_parse_error_with_its_line() {
+        let error = load("game_objects = []\nlevels = []\npermadeath = maybe\n").unwrap_err();
+        assert!(matches!(error, ConfigError::Parse { line: Some(3), .. }), "{:?}", error);
+        assert!(!error.is_not_found());
+    }
+    
+    #[test]
+    fn a_config_with_invalid_values_is_a_validation_error() {
+        let mut config = crate::api::create_default_config();
+        config.sprite_sheets.push(SpriteSheet { name: "broken".to_string(), path: "broken.png".to_string(), sprite_size: SpriteSize { width: 0, height: 32 }, columns: None, rows: None });
+        let error = load(&toml::to_string(&config).unwrap()).unwrap_err();
+        let ConfigError::Validation(errors) = error else {
+            panic!("expected a validation error, got {:?}", error);
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("sprite sheet 'broken' has an empty sprite_size"), "{}", errors[0]);
+    }
+    
+    #[test]
+    fn objects_on_an_undefined_sprite_sheet_fail_validation() {
+        let mut config = crate::api::create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.sprite_sheet = Some("monsters.png".to_string());
+        }
+        let error = load(&toml::to_string(&config).unwrap()).unwrap_err();
+        let ConfigError::Validation(errors) = error else {
+            panic!("expected a validation error, got {:?}", error);
+        };
+        assert_eq!(errors, vec!["object 'orc' uses sprite sheet 'monsters.png', which sprite_sheets doesn't define".to_string()]);
+        
+        config.sprite_sheets.push(SpriteSheet { name: "monsters.png".to_string(), path: "monsters.png".to_string(), sprite_size: SpriteSize::DEFAULT, columns: None, rows: None });
+        assert!(load(&toml::to_string(&config).unwrap()).is_ok());
+    }
 }
@@ -6,5 +6,7 @@ pub struct Consumable {
     pub x: usize,
     pub y: usize,
     pub object_id: String,  // Reference to GameObject
+    #[serde(default)]
+    pub dropped_turns_ago: Option<u32>,  // Turns since a monster or chest dropped it (None = placed with the level, never despawns)
 }
 
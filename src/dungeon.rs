@@ -1,8 +1,36 @@
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use crate::tile::Tile;
 use crate::tile_registry::TileRegistry;
+use crate::entity::Entity;
+use crate::config::TileTheme;
 
-#[derive(Clone)]
+/// Tiles on a straight line between two points (Bresenham), both ends included
+pub fn bresenham_line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = (to.0 - x).signum();
+    let step_y = (to.1 - y).signum();
+    let mut error = dx + dy;
+    
+    let mut line = vec![(x, y)];
+    while (x, y) != to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        line.push((x, y));
+    }
+    line
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Room {
     pub x: usize,
     pub y: usize,
@@ -10,6 +38,52 @@ pub struct Room {
     pub height: usize,
 }
 
+impl Room {
+    /// Whether a position lies within the room's bounding box
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+    
+    /// Center tile of the room
+    pub fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+    
+    /// Flavor description of the room based on its size
+    pub fn describe(&self) -> &'static str {
+        let area = self.width * self.height;
+        if area >= 100 {
+            "a large cavern"
+        } else if area >= 50 {
+            "a chamber"
+        } else {
+            "a small room"
+        }
+    }
+}
+
+/// Level-specific knobs for dungeon generation
+#[derive(Clone, Debug)]
+pub struct DungeonOptions {
+    pub min_rooms: u32,
+    pub max_rooms: u32,
+    pub corridor_width: usize,  // Width of carved corridors in tiles (1 = classic single-tile corridors)
+    pub corridor_winding_percent: u32,  // Chance for a corridor to take an extra bend instead of a plain L-shape
+    pub tile_theme: Option<TileTheme>,  // Restricts and weights floor/wall tiles (None = all tiles)
+}
+
+impl Default for DungeonOptions {
+    fn default() -> Self {
+        Self {
+            min_rooms: 8,
+            max_rooms: 12,
+            corridor_width: 1,
+            corridor_winding_percent: 0,
+            tile_theme: None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Dungeon {
     pub width: usize,
@@ -20,28 +94,48 @@ pub struct Dungeon {
 
 impl Dungeon {
     pub fn new_with_registry(width: usize, height: usize, registry: &TileRegistry) -> Self {
-        Self::new_with_room_count(width, height, registry, 8, 12)
+        Self::new_with_options(width, height, registry, &DungeonOptions::default(), &mut rand::thread_rng())
     }
     
-    pub fn new_with_room_count(width: usize, height: usize, registry: &TileRegistry, min_rooms: u32, max_rooms: u32) -> Self {
+    /// Generate a dungeon, drawing every random choice from `rng` so a seed reproduces it
+    pub fn new_with_options(width: usize, height: usize, registry: &TileRegistry, options: &DungeonOptions, rng: &mut impl Rng) -> Self {
         // Get all wall tiles from registry, default to wall_dirt_top if none found
         let wall_tiles = registry.get_wall_tiles();
-        let default_wall = if wall_tiles.is_empty() {
+        let mut default_wall = if wall_tiles.is_empty() {
             registry.get_wall_dirt_top()
         } else {
             // Use first wall tile as default
             wall_tiles[0].clone()
         };
+        default_wall.randomize_sprite(rng);
         
         let mut tiles = vec![vec![default_wall; width]; height];
-        let rooms = Self::generate_rooms(&mut tiles, width, height, registry, min_rooms, max_rooms);
+        
+        // Themed levels mix their wall tiles instead of using a single one
+        let themed_walls = options.tile_theme.as_ref()
+            .map(|theme| registry.get_themed_tiles(&theme.wall_tiles, false))
+            .unwrap_or_default();
+        if !themed_walls.is_empty() {
+            for cell in tiles.iter_mut().flatten() {
+                let mut tile = themed_walls[rng.gen_range(0..themed_walls.len())].clone();
+                tile.randomize_sprite(rng);
+                *cell = tile;
+            }
+        }
+        
+        let rooms = Self::generate_rooms(&mut tiles, width, height, registry, options, rng);
         Self { width, height, tiles, rooms }
     }
 
-    fn generate_rooms(tiles: &mut Vec<Vec<Tile>>, width: usize, height: usize, registry: &TileRegistry, min_rooms: u32, max_rooms: u32) -> Vec<Room> {
-        let mut rng = rand::thread_rng();
+    /// Share of the map's tiles that are walkable, in percent
+    pub fn walkable_percent(&self) -> f32 {
+        let walkable = self.tiles.iter().flatten().filter(|tile| tile.walkable).count();
+        walkable as f32 * 100.0 / (self.width * self.height) as f32
+    }
+
+    fn generate_rooms(tiles: &mut [Vec<Tile>], width: usize, height: usize, registry: &TileRegistry, options: &DungeonOptions, rng: &mut impl Rng) -> Vec<Room> {
         // Generate rooms based on level config
-        let num_rooms = rng.gen_range(min_rooms..=max_rooms) as usize;
+        let num_rooms = rng.gen_range(options.min_rooms..=options.max_rooms) as usize;
         let mut rooms: Vec<Room> = Vec::new();
         const MAX_ATTEMPTS: usize = 200; // Limit attempts to avoid infinite loops
 
@@ -111,8 +205,8 @@ impl Dungeon {
             }
 
             if !overlaps {
-                // Carve out oval/elliptical room using all walkable tiles from registry
-                let floor_tiles = registry.get_walkable_tiles();
+                // Carve out oval/elliptical room using the level's floor tiles
+                let floor_tiles = Self::floor_tiles(registry, options);
                 
                 // Calculate ellipse center and radii
                 let center_x = x as f32 + room_width as f32 / 2.0;
@@ -137,7 +231,7 @@ impl Dungeon {
                                 let floor_idx = rng.gen_range(0..floor_tiles.len());
                                 let mut tile = floor_tiles[floor_idx].clone();
                                 // Randomize sprite if tile has multiple sprites
-                                tile.randomize_sprite();
+                                tile.randomize_sprite(rng);
                                 tiles[y + dy][x + dx] = tile;
                             }
                         }
@@ -145,7 +239,7 @@ impl Dungeon {
                 } else {
                     // Fallback: use default floor if no walkable tiles found
                     let mut default_floor = registry.get_floor_dark();
-                    default_floor.randomize_sprite();
+                    default_floor.randomize_sprite(rng);
                     for dy in 0..room_height {
                         for dx in 0..room_width {
                             // Check if point is inside ellipse
@@ -157,7 +251,7 @@ impl Dungeon {
                             
                             if dist_sq <= 1.0 {
                                 tiles[y + dy][x + dx] = default_floor.clone();
-                                tiles[y + dy][x + dx].randomize_sprite();
+                                tiles[y + dy][x + dx].randomize_sprite(rng);
                             }
                         }
                     }
@@ -169,12 +263,21 @@ impl Dungeon {
         // Connect rooms with corridors using minimum spanning tree (MST) for shorter paths
         // This ensures all rooms are connected with minimal total path length
         if rooms.len() > 1 {
-            let floor_tiles = registry.get_walkable_tiles();
-            let default_floor = if floor_tiles.is_empty() {
-                registry.get_floor_dark()
+            // A theme's corridor tile (if it's a valid floor) sets corridors apart from rooms
+            let corridor_tile = options.tile_theme.as_ref()
+                .and_then(|theme| theme.corridor_tile_id.as_ref())
+                .and_then(|id| registry.get_tile(id))
+                .filter(|tile| tile.walkable);
+            let floor_tiles = match &corridor_tile {
+                Some(tile) => vec![tile.clone()],
+                None => Self::floor_tiles(registry, options),
+            };
+            let floor_tiles = if floor_tiles.is_empty() {
+                vec![registry.get_floor_dark()]
             } else {
-                floor_tiles[0].clone()
+                floor_tiles
             };
+            let keep_room_floors = corridor_tile.is_some();
             
             // Calculate distances between all room pairs
             let mut distances: Vec<(usize, usize, usize)> = Vec::new();
@@ -225,74 +328,17 @@ impl Dungeon {
                     let room1 = &rooms[i];
                     let room2 = &rooms[j];
                     
-                    let center1_x = room1.x + room1.width / 2;
-                    let center1_y = room1.y + room1.height / 2;
-                    let center2_x = room2.x + room2.width / 2;
-                    let center2_y = room2.y + room2.height / 2;
-                    
-                    // L-shaped corridor (choose direction that minimizes path)
-                    let dx = if center2_x > center1_x { center2_x - center1_x } else { center1_x - center2_x };
-                    let dy = if center2_y > center1_y { center2_y - center1_y } else { center1_y - center2_y };
+                    let center1 = (room1.x + room1.width / 2, room1.y + room1.height / 2);
+                    let center2 = (room2.x + room2.width / 2, room2.y + room2.height / 2);
                     
-                    // Choose direction that creates shorter path
-                    if dx < dy {
-                        // Horizontal then vertical
-                        let start_x = center1_x.min(center2_x);
-                        let end_x = center1_x.max(center2_x);
-                        for x in start_x..=end_x {
-                            if center1_y < tiles.len() && x < tiles[0].len() {
-                                let mut tile = if !floor_tiles.is_empty() {
-                                    let floor_idx = rng.gen_range(0..floor_tiles.len());
-                                    floor_tiles[floor_idx].clone()
-                                } else {
-                                    default_floor.clone()
-                                };
-                                tile.randomize_sprite();
-                                tiles[center1_y][x] = tile;
-                            }
-                        }
-                        let start_y = center1_y.min(center2_y);
-                        let end_y = center1_y.max(center2_y);
-                        for y in start_y..=end_y {
-                            if y < tiles.len() && center2_x < tiles[0].len() {
-                                let mut tile = if !floor_tiles.is_empty() {
-                                    let floor_idx = rng.gen_range(0..floor_tiles.len());
-                                    floor_tiles[floor_idx].clone()
-                                } else {
-                                    default_floor.clone()
-                                };
-                                tile.randomize_sprite();
-                                tiles[y][center2_x] = tile;
-                            }
-                        }
-                    } else {
-                        // Vertical then horizontal
-                        let start_y = center1_y.min(center2_y);
-                        let end_y = center1_y.max(center2_y);
-                        for y in start_y..=end_y {
-                            if y < tiles.len() && center1_x < tiles[0].len() {
-                                let mut tile = if !floor_tiles.is_empty() {
-                                    let floor_idx = rng.gen_range(0..floor_tiles.len());
-                                    floor_tiles[floor_idx].clone()
-                                } else {
-                                    default_floor.clone()
-                                };
-                                tile.randomize_sprite();
-                                tiles[y][center1_x] = tile;
-                            }
-                        }
-                        let start_x = center1_x.min(center2_x);
-                        let end_x = center1_x.max(center2_x);
-                        for x in start_x..=end_x {
-                            if center2_y < tiles.len() && x < tiles[0].len() {
-                                let mut tile = if !floor_tiles.is_empty() {
-                                    let floor_idx = rng.gen_range(0..floor_tiles.len());
-                                    floor_tiles[floor_idx].clone()
-                                } else {
-                                    default_floor.clone()
-                                };
-                                tile.randomize_sprite();
-                                tiles[center2_y][x] = tile;
+                    let waypoints = Self::corridor_waypoints(center1, center2, options.corridor_winding_percent, rng);
+                    for segment in waypoints.windows(2) {
+                        let (from_x, from_y) = segment[0];
+                        let (to_x, to_y) = segment[1];
+                        // Segments are axis-aligned, so one of these ranges is a single tile
+                        for y in from_y.min(to_y)..=from_y.max(to_y) {
+                            for x in from_x.min(to_x)..=from_x.max(to_x) {
+                                Self::carve_corridor_tile(tiles, x, y, options.corridor_width, &floor_tiles, keep_room_floors, rng);
                             }
                         }
                     }
@@ -303,11 +349,184 @@ impl Dungeon {
         rooms
     }
 
+    /// Floor tiles to pick from: the level theme's (weighted) if it has any valid ones,
+    /// otherwise all walkable tiles in the registry
+    fn floor_tiles(registry: &TileRegistry, options: &DungeonOptions) -> Vec<Tile> {
+        let themed = options.tile_theme.as_ref()
+            .map(|theme| registry.get_themed_tiles(&theme.floor_tiles, true))
+            .unwrap_or_default();
+        if themed.is_empty() {
+            registry.get_walkable_tiles()
+        } else {
+            themed
+        }
+    }
+    
+    /// Corner points of a corridor between two room centers.
+    /// Normally an L-shape (choosing the direction that minimizes path); winding corridors
+    /// take an extra bend at a random point along the way, which keeps them connected.
+    fn corridor_waypoints(start: (usize, usize), end: (usize, usize), winding_percent: u32, rng: &mut impl Rng) -> Vec<(usize, usize)> {
+        let (x1, y1) = start;
+        let (x2, y2) = end;
+        let horizontal_first = x1.abs_diff(x2) < y1.abs_diff(y2);
+        let winding = winding_percent > 0 && rng.gen_range(0..100) < winding_percent;
+        
+        match (horizontal_first, winding) {
+            (true, false) => vec![start, (x2, y1), end],
+            (false, false) => vec![start, (x1, y2), end],
+            (true, true) => {
+                let mid_y = rng.gen_range(y1.min(y2)..=y1.max(y2));
+                vec![start, (x1, mid_y), (x2, mid_y), end]
+            }
+            (false, true) => {
+                let mid_x = rng.gen_range(x1.min(x2)..=x1.max(x2));
+                vec![start, (mid_x, y1), (mid_x, y2), end]
+            }
+        }
+    }
+    
+    /// Carve a band of floor tiles centered on a corridor tile, keeping the map border solid.
+    /// With `keep_walkable`, tiles that are already floor (rooms, earlier corridors) are left as they are.
+    fn carve_corridor_tile(tiles: &mut [Vec<Tile>], x: usize, y: usize, corridor_width: usize, floor_tiles: &[Tile], keep_walkable: bool, rng: &mut impl Rng) {
+        let height = tiles.len();
+        let width = if height > 0 { tiles[0].len() } else { 0 };
+        if width < 3 || height < 3 {
+            return;
+        }
+        
+        let corridor_width = corridor_width.max(1);
+        let before = (corridor_width - 1) / 2;
+        let after = corridor_width / 2;
+        let rows = y.saturating_sub(before).max(1)..=(y + after).min(height - 2);
+        let columns = x.saturating_sub(before).max(1)..=(x + after).min(width - 2);
+        for row in &mut tiles[rows] {
+            for cell in &mut row[columns.clone()] {
+                if keep_walkable && cell.walkable {
+                    continue;
+                }
+                let mut tile = floor_tiles[rng.gen_range(0..floor_tiles.len())].clone();
+                tile.randomize_sprite(rng);
+                *cell = tile;
+            }
+        }
+    }
+
     pub fn is_walkable(&self, x: usize, y: usize) -> bool {
         if y >= self.height || x >= self.width {
             return false;
         }
         self.tiles[y][x].walkable
     }
+    
+    /// Whether a tile stops projectiles and line of sight (out of bounds counts as blocking)
+    pub fn blocks_projectiles(&self, x: usize, y: usize) -> bool {
+        if y >= self.height || x >= self.width {
+            return true;
+        }
+        self.tiles[y][x].blocks_projectiles
+    }
+    
+    /// Check that a position-changing step is legal before anything is mutated:
+    /// exactly one tile up, down, left or right, inside the map, and onto a walkable tile.
+    /// Entity occupancy is not checked here (bumping an entity attacks it).
+    pub fn validate_move(&self, entity: &Entity, dx: i32, dy: i32) -> bool {
+        if dx.abs() + dy.abs() != 1 {
+            return false;
+        }
+        let new_x = entity.x as i32 + dx;
+        let new_y = entity.y as i32 + dy;
+        if new_x < 0 || new_y < 0 {
+            return false;
+        }
+        self.is_walkable(new_x as usize, new_y as usize)
+    }
 }
 
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::entity::EntityController;
+    use crate::map_generator::{MAP_HEIGHT, MAP_WIDTH};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    
+    /// A hand-drawn map without rooms: `#` is wall, anything else is floor
+    pub(crate) fn dungeon_from(rows: &[&str]) -> Dungeon {
+        let tiles: Vec<Vec<Tile>> = rows.iter()
+            .map(|row| row.chars().map(|c| if c == '#' { Tile::wall_dirt_top() } else { Tile::floor_dark() }).collect())
+            .collect();
+        Dungeon {
+            width: tiles[0].len(),
+            height: tiles.len(),
+            tiles,
+            rooms: Vec::new(),
+        }
+    }
+    
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let dungeon = dungeon_from(&["#####", "#...#", "#...#", "#####"]);
+        let entity = Entity::new("p1".to_string(), 1, 1, "player".to_string(), 10, 0, 0, 0, 100, 100, EntityController::Player);
+        assert!(dungeon.validate_move(&entity, 1, 0));
+        
+        // Too far, diagonal, no move at all, into a wall, and off the map
+        assert!(!dungeon.validate_move(&entity, 2, 0));
+        assert!(!dungeon.validate_move(&entity, 1, 1));
+        assert!(!dungeon.validate_move(&entity, 0, 0));
+        assert!(!dungeon.validate_move(&entity, -1, 0));
+        assert!(!dungeon.validate_move(&entity, 0, -1));
+        let at_edge = Entity::new("p2".to_string(), 0, 0, "player".to_string(), 10, 0, 0, 0, 100, 100, EntityController::Player);
+        assert!(!dungeon.validate_move(&at_edge, -1, 0));
+    }
+    
+    /// Top-left corners from which a 2x2 creature can reach, starting at `start`
+    fn reachable_by_2x2(dungeon: &Dungeon, start: (usize, usize)) -> std::collections::HashSet<(usize, usize)> {
+        let fits = |x: usize, y: usize| (0..2).all(|dy| (0..2).all(|dx| dungeon.is_walkable(x + dx, y + dy)));
+        let mut reached = std::collections::HashSet::from([start]);
+        let mut queue = vec![start];
+        while let Some((x, y)) = queue.pop() {
+            for (nx, ny) in [(x + 1, y), (x, y + 1), (x.wrapping_sub(1), y), (x, y.wrapping_sub(1))] {
+                if nx < dungeon.width && ny < dungeon.height && fits(nx, ny) && reached.insert((nx, ny)) {
+                    queue.push((nx, ny));
+                }
+            }
+        }
+        reached
+    }
+    
+    #[test]
+    fn wide_winding_corridors_connect_every_room_two_tiles_wide() {
+        let registry = TileRegistry::load_from_config(&crate::api::create_default_config());
+        let options = DungeonOptions { corridor_width: 2, corridor_winding_percent: 50, ..Default::default() };
+        for seed in 0..10 {
+            let dungeon = Dungeon::new_with_options(MAP_WIDTH, MAP_HEIGHT, &registry, &options, &mut StdRng::seed_from_u64(seed));
+            let reached = reachable_by_2x2(&dungeon, dungeon.rooms[0].center());
+            for room in &dungeon.rooms {
+                assert!(reached.contains(&room.center()), "seed {}: no 2-wide way to the room at ({}, {})", seed, room.x, room.y);
+            }
+        }
+    }
+    
+    #[test]
+    fn movement_and_projectiles_are_blocked_independently() {
+        let mut dungeon = dungeon_from(&["#####", "#...#", "#...#", "#...#", "#####"]);
+        let mut set = |x: usize, y: usize, walkable: bool, blocks_projectiles: bool| {
+            dungeon.tiles[y][x].walkable = walkable;
+            dungeon.tiles[y][x].blocks_projectiles = blocks_projectiles;
+        };
+        set(1, 2, false, false);  // Window
+        set(2, 1, true, true);  // Smoke
+        set(2, 3, false, true);  // Wall
+        let entity = Entity::new("p1".to_string(), 2, 2, "player".to_string(), 10, 0, 0, 0, 100, 100, EntityController::Player);
+        
+        let checks = |dx: i32, dy: i32| {
+            let (x, y) = ((2 + dx) as usize, (2 + dy) as usize);
+            (dungeon.validate_move(&entity, dx, dy), dungeon.blocks_projectiles(x, y))
+        };
+        assert_eq!(checks(1, 0), (true, false));  // Floor
+        assert_eq!(checks(-1, 0), (false, false));  // Window
+        assert_eq!(checks(0, -1), (true, true));  // Smoke
+        assert_eq!(checks(0, 1), (false, true));  // Wall
+    }
+}
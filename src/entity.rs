@@ -1,4 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::game_object::{GameObject, GameObjectRegistry, Tint};
+use crate::dungeon::Room;
+
+/// Default upper bound on live monsters (`max_entities` in the config), so spawning
+/// (survival respawns, splitting, summons) can't run away
+pub const MAX_MONSTERS: usize = 60;
+
+/// Damage type of attacks and items that don't specify one
+pub const DEFAULT_DAMAGE_TYPE: &str = "physical";
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum EntityController {
@@ -6,6 +16,48 @@ pub enum EntityController {
     AI,
 }
 
+/// Way an entity is facing, for pointing attack animations
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Direction of an offset along its longer axis (horizontal on diagonals), None for no offset
+    pub fn toward(dx: i32, dy: i32) -> Option<Direction> {
+        if dx == 0 && dy == 0 {
+            None
+        } else if dx.abs() >= dy.abs() {
+            Some(if dx > 0 { Direction::Right } else { Direction::Left })
+        } else {
+            Some(if dy > 0 { Direction::Down } else { Direction::Up })
+        }
+    }
+}
+
+/// Kinds of temporary status effects
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusEffectKind {
+    Confused,  // Moves go in a random direction
+    Strengthened,  // Attack raised by the effect's amount
+    Fortified,  // Defense raised by the effect's amount
+    Hasted,  // Moves carry on a second step in the same direction
+}
+
+/// A temporary condition on an entity, counting down once per round
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub turns_remaining: u32,
+    #[serde(default)]
+    pub amount: i32,  // Stat bonus while it lasts (strengthened/fortified; 0 for the others)
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Entity {
     pub id: String,  // Unique entity ID
@@ -14,6 +66,8 @@ pub struct Entity {
     pub object_id: String,  // Reference to GameObject
     pub attack: i32,
     pub defense: i32,
+    pub equipment_attack: i32,  // Added by the player's equipped weapon (0 = unarmed, always 0 for monsters)
+    pub equipment_defense: i32,  // Added by the player's equipped armor
     pub attack_spread_percent: u32,  // Attack damage variance as percentage (0 = no variance)
     pub crit_chance_percent: u32,  // Critical hit chance as percentage (0 = no crits)
     pub crit_damage_percent: u32,  // Critical hit damage multiplier as percentage (100 = normal damage, 150 = 1.5x)
@@ -21,6 +75,26 @@ pub struct Entity {
     pub current_health: u32,
     pub controller: EntityController,
     pub facing_right: bool,  // true = facing right, false = facing left
+    pub facing: Direction,  // Full facing including up/down (facing_right keeps the last horizontal one for sprite mirroring)
+    pub patrol_route: Vec<(usize, usize)>,  // Waypoints to cycle through when idle (empty = wander randomly)
+    pub patrol_index: usize,  // Index of the waypoint currently being walked to
+    pub channel_turns_remaining: u32,  // Turns until a channeled attack is released (0 = not channeling)
+    pub channel_target: Option<String>,  // Entity ID the channeled attack is aimed at
+    pub dying: bool,  // Killed this tick - still sent to clients once so they can animate the death
+    pub last_seen_player: Option<(usize, usize)>,  // Where a player was last spotted (idle monsters drift there)
+    pub aggro_turns_remaining: Option<u32>,  // Turns left hunting last_seen_player after losing sight (None = not on a timer)
+    pub forced_target: Option<String>,  // Player ID that taunted it - chased and attacked above anyone else while taunt_turns_remaining lasts
+    pub taunt_turns_remaining: u32,  // Own turns left under the taunt (0 = not taunted)
+    pub last_wander_dir: Option<(i32, i32)>,  // Step taken on the last idle wander turn (None = didn't wander or got stuck)
+    pub boxed_in: bool,  // Had no legal move and no player to hit on its last turn
+    pub noclip: bool,  // Can move through entities on its own side (not walls or enemies)
+    pub damage_type: String,  // Type of damage this entity's attacks deal (e.g., "physical", "fire")
+    pub resistances: HashMap<String, i32>,  // Damage type -> percent reduction (100 = immune, negative = weakness)
+    pub home_room: Option<Room>,  // Room an idle monster is leashed to (None = wanders anywhere)
+    pub provoked: bool,  // Hit by a player - passive monsters (hostile_on_sight = false) chase from then on
+    pub status_effects: Vec<StatusEffect>,  // Active temporary effects (at most one per kind)
+    pub unique_name: Option<String>,  // Flavor name from the object's name pool ("Orc: Grolok"), None = object name
+    pub tint: Option<Tint>,  // Color variant picked from the object's tint_variants (None = untinted)
 }
 
 impl Entity {
@@ -44,6 +118,8 @@ impl Entity {
             object_id,
             attack,
             defense,
+            equipment_attack: 0,
+            equipment_defense: 0,
             attack_spread_percent,
             crit_chance_percent,
             crit_damage_percent,
@@ -51,6 +127,26 @@ impl Entity {
             current_health: max_health,
             controller,
             facing_right: true,  // Default: facing right
+            facing: Direction::Right,
+            patrol_route: Vec::new(),
+            patrol_index: 0,
+            channel_turns_remaining: 0,
+            channel_target: None,
+            dying: false,
+            last_seen_player: None,
+            aggro_turns_remaining: None,
+            forced_target: None,
+            taunt_turns_remaining: 0,
+            last_wander_dir: None,
+            boxed_in: false,
+            noclip: false,
+            damage_type: DEFAULT_DAMAGE_TYPE.to_string(),
+            resistances: HashMap::new(),
+            home_room: None,
+            provoked: false,
+            status_effects: Vec::new(),
+            unique_name: None,
+            tint: None,
         }
     }
     
@@ -58,8 +154,96 @@ impl Entity {
         self.current_health > 0
     }
     
+    /// Turn toward an offset (a move or a target). Sprites only mirror horizontally, so
+    /// facing_right is left as it was for purely vertical turns.
+    pub fn face(&mut self, dx: i32, dy: i32) {
+        if let Some(direction) = Direction::toward(dx, dy) {
+            self.facing = direction;
+        }
+        if dx != 0 {
+            self.facing_right = dx > 0;
+        }
+    }
+    
+    /// Take the attack damage type and resistances from the entity's template
+    pub fn set_damage_traits(&mut self, template: &GameObject) {
+        self.damage_type = template.damage_type.clone().unwrap_or_else(|| DEFAULT_DAMAGE_TYPE.to_string());
+        self.resistances = template.resistances.clone().unwrap_or_default();
+    }
+    
+    /// Percent resistance to a damage type (0 if none)
+    pub fn resistance_to(&self, damage_type: &str) -> i32 {
+        self.resistances.get(damage_type).copied().unwrap_or(0)
+    }
+    
     pub fn heal(&mut self, amount: u32) {
         self.current_health = (self.current_health + amount).min(self.max_health);
     }
+    
+    /// Name for messages: the unique flavor name if it has one, otherwise its object's name (or its ID)
+    pub fn display_name(&self, object_registry: &GameObjectRegistry) -> String {
+        self.unique_name.clone()
+            .or_else(|| object_registry.get_object(&self.object_id).map(|obj| obj.name.clone()))
+            .unwrap_or_else(|| self.id.clone())
+    }
+    
+    pub fn has_status(&self, kind: StatusEffectKind) -> bool {
+        self.status_effects.iter().any(|effect| effect.kind == kind)
+    }
+    
+    /// Apply a status effect. Reapplying one that's active extends it to the longer duration.
+    pub fn add_status(&mut self, kind: StatusEffectKind, turns: u32) {
+        self.add_buff(kind, 0, turns);
+    }
+    
+    /// Apply a status effect with a stat bonus. Reapplying one that's active keeps the longer
+    /// duration and the bigger bonus.
+    pub fn add_buff(&mut self, kind: StatusEffectKind, amount: i32, turns: u32) {
+        match self.status_effects.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => {
+                effect.turns_remaining = effect.turns_remaining.max(turns);
+                effect.amount = effect.amount.max(amount);
+            }
+            None => self.status_effects.push(StatusEffect { kind, turns_remaining: turns, amount }),
+        }
+    }
+    
+    /// Total bonus from active effects of a kind (0 if none)
+    fn status_bonus(&self, kind: StatusEffectKind) -> i32 {
+        self.status_effects.iter()
+            .filter(|effect| effect.kind == kind)
+            .map(|effect| effect.amount)
+            .sum()
+    }
+    
+    /// Attack including equipment and buffs - what combat uses
+    pub fn effective_attack(&self) -> i32 {
+        self.attack + self.equipment_attack + self.status_bonus(StatusEffectKind::Strengthened)
+    }
+    
+    /// Defense including equipment and buffs - what combat uses
+    pub fn effective_defense(&self) -> i32 {
+        self.defense + self.equipment_defense + self.status_bonus(StatusEffectKind::Fortified)
+    }
+    
+    /// Count status effects down by one round, dropping the ones that ran out
+    pub fn tick_status_effects(&mut self) {
+        for effect in &mut self.status_effects {
+            effect.turns_remaining = effect.turns_remaining.saturating_sub(1);
+        }
+        self.status_effects.retain(|effect| effect.turns_remaining > 0);
+    }
+    
+    /// Current patrol waypoint, advancing to the next one (looping) if already reached
+    pub fn next_patrol_waypoint(&mut self) -> Option<(usize, usize)> {
+        if self.patrol_route.is_empty() {
+            return None;
+        }
+        self.patrol_index %= self.patrol_route.len();
+        if self.patrol_route[self.patrol_index] == (self.x, self.y) {
+            self.patrol_index = (self.patrol_index + 1) % self.patrol_route.len();
+        }
+        Some(self.patrol_route[self.patrol_index])
+    }
 }
 
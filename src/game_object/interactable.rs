@@ -1,11 +1,27 @@
 use serde::{Deserialize, Serialize};
+use crate::entity::StatusEffectKind;
 
-/// Marker for interactable game objects (chests, doors, etc.)
+/// Marker for interactable game objects (chests, doors, levers, etc.)
 /// For interactable objects, sprites[0] = before state (closed), sprites[1] = after state (open)
 /// Before state is always non-walkable, after state is always walkable
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct InteractableData {
-    // Empty struct - just a marker
     // The actual sprites come from GameObject.sprites[0] and sprites[1]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect: Option<InteractionEffect>,  // What bumping it does (None = chests drop loot, anything else just opens)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toggle: Option<bool>,  // Stays shut and flips the object's is_active on every bump instead of opening once (levers)
 }
 
+/// Effect triggered when a player bumps a closed interactable, which then switches to its open state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InteractionEffect {
+    Loot,  // Drop a random consumable (chests)
+    Heal { amount: u32 },  // Heal the player who used it (fountains, altars)
+    OpenDoors { object_id: String },  // Open every closed interactable of this object ID on the level (levers)
+    SpawnMonsters { object_id: String, count: u32 },  // Monsters appear around it (cursed altars)
+    Buff { kind: StatusEffectKind, amount: i32, turns: u32 },  // Give the player who used it a timed buff (shrines, made walkable so they're stepped onto)
+}
@@ -5,8 +5,8 @@ pub mod registry;
 pub mod schema;
 
 // Re-export commonly used types
-pub use object::{GameObject, SpriteCoord};
-pub use interactable::InteractableData;
+pub use object::{GameObject, SpriteCoord, Tint, RARITIES};
+pub use interactable::{InteractableData, InteractionEffect};
 pub use registry::GameObjectRegistry;
 pub use schema::{GameObjectSchema, FieldSchema};
 
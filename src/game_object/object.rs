@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::entity::StatusEffectKind;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SpriteCoord {
@@ -6,6 +8,31 @@ pub struct SpriteCoord {
     pub y: u32,
 }
 
+/// Children spawned around a monster when it dies
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnDeathSpawn {
+    pub object_id: String,  // Character object to spawn
+    pub count: u32,
+}
+
+/// Status effect applied to whatever this object's attacks (or thrown hits) land on
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InflictedStatus {
+    pub effect: StatusEffectKind,
+    pub turns: u32,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chance_percent: Option<u32>,  // Chance per hit (default 100)
+}
+
+/// Color multipliers a client applies to a sprite, each channel 0.0 - 1.0 (1.0 = unchanged)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tint {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameObject {
     pub id: String,
@@ -33,7 +60,124 @@ pub struct GameObject {
     pub monster: Option<bool>,  // Whether this character is a monster
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub healing_power: Option<u32>,  // Healing power for consumables
+    pub healing_power: Option<u32>,  // Healing power for consumables (or per heal for support monsters)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heal_radius: Option<u32>,  // Heals all allies within this many tiles instead of a single target
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patrols: Option<bool>,  // Whether this monster patrols its spawn room's corners when idle
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_turns: Option<u32>,  // Turns a monster telegraphs before releasing its attack (None/0 = attacks immediately)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_damage_percent: Option<u32>,  // Damage multiplier of a released channeled attack (e.g., 300 = 3x)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attacks_when_trapped: Option<bool>,  // When boxed in with no moves, attack any adjacent entity (allies too)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wander_bias_percent: Option<u32>,  // Chance an idle step heads toward where a player was last seen (default 75)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wander_persist_percent: Option<u32>,  // Chance an idle wander step keeps the last step's direction (default 60, 0 = fully random)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wander_pause_percent: Option<u32>,  // Chance a wandering monster stands still for the turn instead (default 0 = always moves)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggro_persist_turns: Option<u32>,  // Turns it hunts a player's last known position after losing them, then gives up (None = drifts there)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boss: Option<bool>,  // Boss monster - levels with a kill_boss win condition are won once every boss is dead
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noclip: Option<bool>,  // Can move through allied entities (still blocked by walls), e.g. ghosts
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_death_spawn: Option<OnDeathSpawn>,  // Entities spawned where this monster dies (e.g., slime splits)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_attack_range: Option<u32>,  // Reach of the piercing line attack in tiles (None = can't line attack)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taunt_radius: Option<u32>,  // Tiles (Chebyshev) the taunt ability reaches (None = can't taunt)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taunt_turns: Option<u32>,  // Monster turns a taunt holds its targets (default 3)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_projectiles: Option<bool>,  // Tiles: stops throws, line attacks and sight (default = not walkable)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throwable: Option<bool>,  // Consumable is picked up into the inventory to be thrown instead of used on the spot
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throw_range: Option<u32>,  // Max throw distance in tiles (default 5)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_stack: Option<u32>,  // How many of this item share one inventory slot (default 1 = no stacking)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub despawn_turns: Option<u32>,  // Copies dropped by monsters or chests vanish after lying this many turns (None = stay)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub durability: Option<u32>,  // Equipment: uses before it breaks - attacks for weapons, hits taken for armor (None = indestructible)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<String>,  // One of RARITIES - rarer items drop less often and stand out in pickup messages
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub damage: Option<u32>,  // Damage dealt to whatever a thrown consumable hits (e.g., bombs)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub damage_type: Option<String>,  // Type of damage dealt by attacks or thrown items (default "physical")
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resistances: Option<HashMap<String, i32>>,  // Damage type -> percent reduction (100 = immune, negative = weakness)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inflicts_status: Option<InflictedStatus>,  // Status effect put on targets it hits (e.g., confusion)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tier: Option<u32>,  // Dungeon level its stats are balanced for; depth scaling boosts it below that (default 1)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_pool: Option<Vec<String>>,  // Flavor names given to spawned monsters ("Orc: Grolok")
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tint_variants: Option<Vec<Tint>>,  // Color variants spawned monsters pick from at random (None = untinted)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flee_health_percent: Option<u32>,  // Flees from players below this % of max health when no ally is near (None = never)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub morale_radius: Option<u32>,  // Tiles within which an ally keeps it fighting (default 3)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostile_on_sight: Option<bool>,  // Chases players it sees (default true); false = passive until attacked
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_fire: Option<bool>,  // Goes for the weakest player it can see (lowest health, then nearest) instead of the nearest (default false)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vision_cone_degrees: Option<u32>,  // Only spots players within this arc around its facing (None = sees all around)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drops_on_death: Option<String>,  // Object ID of an item this monster always drops when killed (e.g., a key)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equipment: Option<Vec<String>>,  // Object IDs of items this monster carries as gear, dropped when it dies
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equipment_drop_percent: Option<u32>,  // Chance for each piece of equipment to drop on death (None = 100)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires_key: Option<String>,  // Object ID of the key item needed to open this door (used up on opening)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reveals_loot: Option<bool>,  // Treasure sense: players using this object are told where loot lies in unexplored rooms
     #[serde(default)]
     pub sprites: Vec<SpriteCoord>,  // Array of sprite coordinates for randomization (default state, or "before" for interactables)
     #[serde(default)]
@@ -53,6 +197,9 @@ pub struct GameObject {
     pub properties: std::collections::HashMap<String, String>,  // Additional custom properties
 }
 
+/// Valid item rarities, most common first
+pub const RARITIES: [&str; 3] = ["common", "rare", "epic"];
+
 impl GameObject {
     pub fn new(
         id: String,
@@ -75,6 +222,45 @@ impl GameObject {
             crit_damage_percent: None,
             monster: None,
             healing_power: None,
+            heal_radius: None,
+            patrols: None,
+            channel_turns: None,
+            channel_damage_percent: None,
+            attacks_when_trapped: None,
+            wander_bias_percent: None,
+            wander_persist_percent: None,
+            wander_pause_percent: None,
+            aggro_persist_turns: None,
+            boss: None,
+            noclip: None,
+            on_death_spawn: None,
+            line_attack_range: None,
+            taunt_radius: None,
+            taunt_turns: None,
+            blocks_projectiles: None,
+            throwable: None,
+            throw_range: None,
+            max_stack: None,
+            despawn_turns: None,
+            rarity: None,
+            damage: None,
+            damage_type: None,
+            resistances: None,
+            inflicts_status: None,
+            tier: None,
+            name_pool: None,
+            tint_variants: None,
+            flee_health_percent: None,
+            morale_radius: None,
+            hostile_on_sight: None,
+            focus_fire: None,
+            vision_cone_degrees: None,
+            drops_on_death: None,
+            equipment: None,
+            equipment_drop_percent: None,
+            durability: None,
+            requires_key: None,
+            reveals_loot: None,
             sprites: vec![SpriteCoord { x: sprite_x, y: sprite_y }],
             interactable: None,
             sprite_x: Some(sprite_x),
@@ -115,8 +301,7 @@ impl GameObject {
     /// Returns default sprites if not interactable or if sprites array is too short.
     pub fn get_interactable_sprites(&self, is_after: bool) -> Vec<SpriteCoord> {
         // Check if this is an interactable object (chest, door, etc.)
-        // We check both the interactable marker and object_type for safety
-        if self.interactable.is_some() || self.object_type == "chest" {
+        if self.is_interactable() {
             let sprites = self.get_sprites_vec();
             if is_after {
                 // After state: use sprites[1] if available, fallback to sprites[0]
@@ -142,21 +327,43 @@ impl GameObject {
     }
     
     /// Get walkable status for interactable state.
-    /// Before state: always false (non-walkable)
+    /// Before state: false (non-walkable), unless the object is walkable (shrines are stepped onto)
     /// After state: always true (walkable)
     /// Returns base walkable if not interactable.
     pub fn get_interactable_walkable(&self, is_after: bool) -> bool {
         // Check if this is an interactable object (chest, door, etc.)
-        // We check both the interactable marker and object_type for safety
-        if self.interactable.is_some() || self.object_type == "chest" {
-            // Interactable objects: before = false, after = true
-            is_after
+        if self.is_interactable() {
+            // Interactable objects: before = base walkable, after = true
+            is_after || self.walkable
         } else {
             // Not an interactable, return base walkable
             self.walkable
         }
     }
 
+    /// Relative chance of this item being picked for a random drop (rarer = less likely)
+    pub fn drop_weight(&self) -> u32 {
+        match self.rarity.as_deref() {
+            Some("epic") => 1,
+            Some("rare") => 3,
+            _ => 10,
+        }
+    }
+    
+    /// Whether this opens when bumped (closed/open states): the `interactable` marker, or a
+    /// chest or door without one
+    pub fn is_interactable(&self) -> bool {
+        self.interactable.is_some() || matches!(self.object_type.as_str(), "chest" | "door")
+    }
+    
+    /// Whether this is a monster character (`monster` field, or the legacy property)
+    pub fn is_monster(&self) -> bool {
+        self.object_type == "character" && (
+            self.monster.unwrap_or(false) ||
+            self.properties.get("monster").is_some_and(|s| s == "true")
+        )
+    }
+
     #[allow(dead_code)]
     pub fn with_property(mut self, key: String, value: String) -> Self {
         self.properties.insert(key, value);
@@ -1,21 +1,25 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use crate::game_object::object::GameObject;
 
 pub struct GameObjectRegistry {
-    objects: HashMap<String, GameObject>,
+    objects: BTreeMap<String, GameObject>,  // Sorted by id so iteration order is stable
 }
 
 impl GameObjectRegistry {
     pub fn new() -> Self {
         Self {
-            objects: HashMap::new(),
+            objects: BTreeMap::new(),
         }
     }
 
     pub fn load_from_config(config: &crate::config::GameConfig) -> Self {
         let mut registry = Self::new();
         for obj in &config.game_objects {
-            registry.objects.insert(obj.id.clone(), obj.clone());
+            let mut obj = obj.clone();
+            if let Some(defaults) = config.defaults.get(&obj.object_type) {
+                defaults.apply_to(&mut obj);
+            }
+            registry.objects.insert(obj.id.clone(), obj);
         }
         registry
     }
@@ -28,20 +32,47 @@ impl GameObjectRegistry {
         self.objects.values().collect()
     }
     
+    /// A random consumable for a drop, weighted by rarity (None if there are no consumables)
+    pub fn random_loot(&self, rng: &mut impl rand::Rng) -> Option<&GameObject> {
+        use rand::seq::SliceRandom;
+        let loot: Vec<&GameObject> = self.objects.values()
+            .filter(|obj| obj.object_type == "consumable")
+            .collect();
+        loot.choose_weighted(rng, |obj| obj.drop_weight()).ok().copied()
+    }
+    
     /// Get all monster characters (characters with monster=true)
     pub fn get_monster_characters(&self) -> Vec<&GameObject> {
         self.objects
             .values()
-            .filter(|obj| {
-                obj.object_type == "character" && {
-                    // Check if monster is true (top-level field or in properties map)
-                    obj.monster.unwrap_or(false) ||
-                    obj.properties.get("monster")
-                        .map(|s| s == "true")
-                        .unwrap_or(false)
-                }
-            })
+            .filter(|obj| obj.is_monster())
             .collect()
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_default_config;
+    
+    fn ids(objects: Vec<&GameObject>) -> Vec<String> {
+        objects.into_iter().map(|obj| obj.id.clone()).collect()
+    }
+    
+    #[test]
+    fn objects_come_back_sorted_by_id_whatever_the_config_order() {
+        let config = create_default_config();
+        let mut reversed = create_default_config();
+        reversed.game_objects.reverse();
+        let registry = GameObjectRegistry::load_from_config(&config);
+        let reversed_registry = GameObjectRegistry::load_from_config(&reversed);
+        
+        let all = ids(registry.get_all_objects());
+        let mut sorted = all.clone();
+        sorted.sort();
+        assert_eq!(all, sorted);
+        assert_eq!(ids(reversed_registry.get_all_objects()), all);
+        assert_eq!(ids(reversed_registry.get_monster_characters()), ids(registry.get_monster_characters()));
+    }
+}
@@ -112,9 +112,321 @@ impl GameObjectSchema {
                 field_type: "Option<u32>".to_string(),
                 optional: true,
                 default: None,
-                show_for_types: vec!["consumable".to_string()],
+                show_for_types: vec!["consumable".to_string(), "character".to_string()],
                 label: Some("Healing Power".to_string()),
             },
+            FieldSchema {
+                name: "heal_radius".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["consumable".to_string(), "character".to_string()],
+                label: Some("Heal Radius".to_string()),
+            },
+            FieldSchema {
+                name: "patrols".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Patrols".to_string()),
+            },
+            FieldSchema {
+                name: "channel_turns".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("0".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Channel Turns".to_string()),
+            },
+            FieldSchema {
+                name: "channel_damage_percent".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("300".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Channel Damage %".to_string()),
+            },
+            FieldSchema {
+                name: "attacks_when_trapped".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Attacks When Trapped".to_string()),
+            },
+            FieldSchema {
+                name: "wander_bias_percent".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("75".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Wander Bias %".to_string()),
+            },
+            FieldSchema {
+                name: "wander_persist_percent".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("60".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Wander Persistence %".to_string()),
+            },
+            FieldSchema {
+                name: "wander_pause_percent".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("0".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Wander Pause %".to_string()),
+            },
+            FieldSchema {
+                name: "aggro_persist_turns".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Aggro Persist Turns".to_string()),
+            },
+            FieldSchema {
+                name: "boss".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Boss".to_string()),
+            },
+            FieldSchema {
+                name: "noclip".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Passes Through Entities".to_string()),
+            },
+            FieldSchema {
+                name: "on_death_spawn".to_string(),
+                field_type: "Option<OnDeathSpawn>".to_string(),
+                optional: true,
+                default: Some("None".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("On Death Spawn (object_id, count)".to_string()),
+            },
+            FieldSchema {
+                name: "line_attack_range".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Line Attack Range".to_string()),
+            },
+            FieldSchema {
+                name: "taunt_radius".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Taunt Radius".to_string()),
+            },
+            FieldSchema {
+                name: "taunt_turns".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("3".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Taunt Turns".to_string()),
+            },
+            FieldSchema {
+                name: "blocks_projectiles".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["tile".to_string()],
+                label: Some("Blocks Projectiles/Sight (default: if not walkable)".to_string()),
+            },
+            FieldSchema {
+                name: "throwable".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["consumable".to_string()],
+                label: Some("Throwable".to_string()),
+            },
+            FieldSchema {
+                name: "throw_range".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("5".to_string()),
+                show_for_types: vec!["consumable".to_string()],
+                label: Some("Throw Range".to_string()),
+            },
+            FieldSchema {
+                name: "max_stack".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("1".to_string()),
+                show_for_types: vec!["consumable".to_string()],
+                label: Some("Max Stack".to_string()),
+            },
+            FieldSchema {
+                name: "despawn_turns".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["consumable".to_string()],
+                label: Some("Despawn After Turns (dropped)".to_string()),
+            },
+            FieldSchema {
+                name: "durability".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["equipment".to_string()],
+                label: Some("Durability".to_string()),
+            },
+            FieldSchema {
+                name: "rarity".to_string(),
+                field_type: "Option<String>".to_string(),
+                optional: true,
+                default: Some("common".to_string()),
+                show_for_types: vec!["consumable".to_string()],
+                label: Some("Rarity (common/rare/epic)".to_string()),
+            },
+            FieldSchema {
+                name: "damage".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["consumable".to_string()],
+                label: Some("Damage".to_string()),
+            },
+            FieldSchema {
+                name: "damage_type".to_string(),
+                field_type: "Option<String>".to_string(),
+                optional: true,
+                default: Some("physical".to_string()),
+                show_for_types: vec!["character".to_string(), "consumable".to_string()],
+                label: Some("Damage Type".to_string()),
+            },
+            FieldSchema {
+                name: "resistances".to_string(),
+                field_type: "Option<HashMap<String, i32>>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Resistances % (damage type -> %, negative = weakness)".to_string()),
+            },
+            FieldSchema {
+                name: "inflicts_status".to_string(),
+                field_type: "Option<InflictedStatus>".to_string(),
+                optional: true,
+                default: Some("None".to_string()),
+                show_for_types: vec!["character".to_string(), "consumable".to_string()],
+                label: Some("Inflicts Status (effect, turns, chance_percent)".to_string()),
+            },
+            FieldSchema {
+                name: "tier".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("1".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Tier (native dungeon level)".to_string()),
+            },
+            FieldSchema {
+                name: "name_pool".to_string(),
+                field_type: "Option<Vec<String>>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Name Pool (unique monster names)".to_string()),
+            },
+            FieldSchema {
+                name: "tint_variants".to_string(),
+                field_type: "Option<Vec<Tint>>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Tint Variants (color multipliers)".to_string()),
+            },
+            FieldSchema {
+                name: "flee_health_percent".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Flee Below Health % (when alone)".to_string()),
+            },
+            FieldSchema {
+                name: "morale_radius".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("3".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Morale Radius".to_string()),
+            },
+            FieldSchema {
+                name: "hostile_on_sight".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("true".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Hostile On Sight".to_string()),
+            },
+            FieldSchema {
+                name: "focus_fire".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Focus Fire".to_string()),
+            },
+            FieldSchema {
+                name: "vision_cone_degrees".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Vision Cone (degrees)".to_string()),
+            },
+            FieldSchema {
+                name: "drops_on_death".to_string(),
+                field_type: "Option<String>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Drops On Death (object ID)".to_string()),
+            },
+            FieldSchema {
+                name: "equipment".to_string(),
+                field_type: "Option<Vec<String>>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["character".to_string()],
+                label: Some("Equipment (object IDs)".to_string()),
+            },
+            FieldSchema {
+                name: "equipment_drop_percent".to_string(),
+                field_type: "Option<u32>".to_string(),
+                optional: true,
+                default: Some("100".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Equipment Drop Chance (%)".to_string()),
+            },
+            FieldSchema {
+                name: "requires_key".to_string(),
+                field_type: "Option<String>".to_string(),
+                optional: true,
+                default: None,
+                show_for_types: vec!["chest".to_string(), "door".to_string(), "lever".to_string()],
+                label: Some("Requires Key (object ID)".to_string()),
+            },
+            FieldSchema {
+                name: "reveals_loot".to_string(),
+                field_type: "Option<bool>".to_string(),
+                optional: true,
+                default: Some("false".to_string()),
+                show_for_types: vec!["character".to_string()],
+                label: Some("Treasure Sense (player)".to_string()),
+            },
             FieldSchema {
                 name: "sprites".to_string(),
                 field_type: "Vec<SpriteCoord>".to_string(),
@@ -128,8 +440,8 @@ impl GameObjectSchema {
                 field_type: "Option<InteractableData>".to_string(),
                 optional: true,
                 default: Some("None".to_string()),
-                show_for_types: vec!["chest".to_string()],
-                label: Some("Interactable (before/after states)".to_string()),
+                show_for_types: vec!["chest".to_string(), "door".to_string(), "lever".to_string()],
+                label: Some("Interactable (before/after states, optional effect and toggle)".to_string()),
             },
             FieldSchema {
                 name: "sprite_sheet".to_string(),
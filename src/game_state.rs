@@ -1,13 +1,86 @@
-use crate::dungeon::Dungeon;
+use crate::dungeon::{Dungeon, Room};
 use crate::tile_registry::TileRegistry;
-use crate::game_object::GameObjectRegistry;
-use crate::entity::{Entity, EntityController};
+use crate::game_object::{GameObjectRegistry, InteractionEffect};
+use crate::entity::{Entity, EntityController, StatusEffectKind, DEFAULT_DAMAGE_TYPE, MAX_MONSTERS};
 use crate::consumable::Consumable;
 use crate::chest::Chest;
-use crate::message::{GameMessage, PlayerCommand};
-use crate::map_generator::MapGenerator;
-use crate::combat::attack_entity;
-use crate::ai::process_ai_turns;
+use crate::message::{GameMessage, MessageType, PlayerCommand};
+use crate::map_generator::{GeneratedMap, MapGenerator};
+use crate::combat::{apply_damage, attack_entity, attack_line, damage_against, heal_allies, try_inflict_status};
+use crate::dungeon::bresenham_line;
+use crate::ai::{adjacent_entities, find_path_step, has_legal_move, process_ai_turns, random_legal_direction};
+use crate::command::{self, CommandKind};
+use crate::config::{DifficultyScaling, GameConfig, LevelConfig, TileTrigger, TriggerAction, WinCondition};
+use crate::replay::{Replay, ReplayAction};
+use crate::logging;
+use crate::player::{AutoMove, PlayerState};
+use serde::{Deserialize, Serialize};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand::seq::SliceRandom;
+
+/// Tiles (Chebyshev) from every player beyond which a monster is out of sight.
+/// The client viewport is 10 tiles, 5 each side of the player.
+const OFF_SCREEN_DISTANCE: usize = 6;
+
+/// A level the party left through the stairs, restored as-is when they come back
+pub struct CachedLevel {
+    pub dungeon: Dungeon,
+    pub monsters: Vec<Entity>,
+    pub consumables: Vec<Consumable>,
+    pub chests: Vec<Chest>,
+    pub stairs_position: Option<(usize, usize)>,
+    pub stairs_up_position: Option<(usize, usize)>,
+    pub level_config: Option<LevelConfig>,
+    pub discovered_rooms: std::collections::HashMap<String, std::collections::HashSet<usize>>,  // Rooms each player had explored here
+    pub stairs_discovered: bool,  // The stairs had already been announced
+    pub fired_triggers: std::collections::HashSet<usize>,  // Indices of the level's triggers that already fired
+    pub vault_room: Option<usize>,
+    pub vault_discovered: bool,  // The vault had already been announced
+    pub has_boss: bool,  // A boss was put on the level
+    pub won: bool,  // The level's win condition was already met
+}
+
+/// Whether the run is still going. Permadeath runs end in GameOver instead of restarting the level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    Lobby,     // Waiting for enough players to ready up - no monsters, no turns
+    Playing,   // Normal play (a party wipe restarts the level unless permadeath applies)
+    GameOver,  // The run is over - only a new-game command leaves this state
+}
+
+/// Lobby headcount, sent to clients while waiting for players
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LobbyStatus {
+    pub connected: usize,  // Players connected so far
+    pub ready: usize,  // How many of them are ready
+    pub min_players: u32,  // Ready players needed before the game starts (everyone connected must be ready too)
+}
+
+/// A finished run, recorded on the scoreboard when the game is over
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunRecord {
+    pub players: Vec<String>,  // Player IDs that took part
+    pub level: u32,  // Level the party died on
+    pub turns: u32,  // Turns survived on that level
+}
+
+/// How far the party is from meeting the level's win condition
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WinProgress {
+    pub condition: WinCondition,
+    pub remaining: u32,  // Bosses left alive, turns left to survive, or items left to collect (0 = won)
+}
+
+/// What currently lies inside one room of the level, for checking how encounters are balanced
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoomContents {
+    pub room: Room,
+    pub is_vault: bool,
+    pub entities: Vec<String>,  // IDs of the entities standing in it (alive ones, players too)
+    pub chests: Vec<String>,  // IDs of its chests, doors and levers
+    pub consumables: Vec<String>,  // IDs of the items lying in it
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TurnPhase {
@@ -23,46 +96,188 @@ pub struct GameState {
     pub tile_registry: TileRegistry,
     pub object_registry: GameObjectRegistry,
     pub stairs_position: Option<(usize, usize)>,  // Position of stairs (goal tile)
-    pub player_confirmations: std::collections::HashSet<String>,  // Players who confirmed they want to end level
-    pub restart_confirmations: std::collections::HashSet<String>,  // Players who confirmed they want to restart after death
+    pub players: std::collections::HashMap<String, PlayerState>,  // Player-only state (inventory, confirmations, ...) by player ID
     pub turn_phase: TurnPhase,  // Current phase of the turn
     pub players_acted_this_turn: std::collections::HashSet<String>,  // Players who have taken their turn this round
     pub current_turn: u32,  // Current turn number
+    pub difficulty_scaling: DifficultyScaling,  // Monster scaling by player count (applied on restart)
+    pub level_config: Option<LevelConfig>,  // Config of the level being played (None = defaults)
+    pub stairs_discovered: bool,  // Whether the stairs have been announced this level
+    pub vault_room: Option<usize>,  // Index of this level's treasure vault in dungeon.rooms (None = no vault)
+    pub vault_discovered: bool,  // Whether the vault has been announced this level
+    pub stairs_up_position: Option<(usize, usize)>,  // Position of stairs back to the previous level
+    pub spawn_position: (usize, usize),  // Where players (re)join on this level
+    pub current_level: u32,  // Level number being played
+    pub level_configs: Vec<LevelConfig>,  // All configured levels, used when changing level
+    pub level_cache: std::collections::HashMap<u32, CachedLevel>,  // Levels the party has left, by level number
+    pub permadeath: bool,  // A party wipe ends the run instead of restarting the level, from the config
+    pub status: GameStatus,  // Whether the run is still going
+    pub scoreboard: Vec<RunRecord>,  // Finished runs, oldest first
+    pub shared_party_vision: bool,  // Explored rooms are pooled across alive players
+    pub wall_attacks_allowed: bool,  // Attacking a wall is a harmless swing that uses the turn (false = refused)
+    pub friendly_fire: bool,  // Players' line attacks also hit teammates in the way
+    pub ai_pathfinding_budget: Option<u32>,  // Tiles monster pathfinding may explore per turn (None = unlimited), from the config
+    pub max_entities: usize,  // Most live monsters at once, enforced by try_spawn_entity, from the config
+    pub rest_heal_percent: u32,  // Share of max health players recover on the way down to the next level (0 = none), from the config
+    pub monsters_avoid_stairs: bool,  // Monsters never step onto either stairs tile, from the config
+    pub monsters_avoid_chests: bool,  // Monsters keep off opened chests too (closed ones always block), from the config
+    pub admin_commands: bool,  // A new_game command (or the admin endpoint) resets the run at any time, from the config
+    pub recording: Option<Replay>,  // Commands recorded since /api/replay/start (None = not recording)
+    pub broadcast_coalesce_ms: u64,  // Coalescing window for connections' updates (0 = off), from the config
+    pub started_at: std::time::Instant,  // Server clock origin for updates' server_time_ms
+    pub turns_since_combat: u32,  // Turns in a row without an attack landing (drives the level's pressure_turns)
+    pub items_collected: u32,  // Items the party picked up on this level (for collect_items win conditions)
+    pub level_has_boss: bool,  // A boss made it onto the level (a kill_boss level without one opens its stairs instead)
+    pub level_won: bool,  // The level's win condition was met, so coming back up to it doesn't win it again
+    pub fired_triggers: std::collections::HashSet<usize>,  // Indices into the level's triggers that already fired
+    pub min_players_to_start: u32,  // Players that must be ready before the lobby starts the game, from the config
+    pub rng: StdRng,  // Source of every random choice (generation, combat, AI), seeded so a replay can repeat them
+    pub seed: u64,  // Seed rng was last set from (at creation, or when the run restarted)
+    pub spawned_count: u64,  // Objects spawned during play so far, numbering their IDs (reset by a new game)
 }
 
 impl GameState {
     pub fn new_with_registry(tile_registry: TileRegistry, object_registry: GameObjectRegistry) -> Self {
-        Self::new_with_level(tile_registry, object_registry, None)
+        Self::new_with_level(tile_registry, object_registry, None, MAX_MONSTERS)
     }
     
-    pub fn new_with_level(tile_registry: TileRegistry, object_registry: GameObjectRegistry, level_config: Option<&crate::config::LevelConfig>) -> Self {
-        let (dungeon, entities, consumables, chests, stairs_pos) = 
-            MapGenerator::generate_map(&tile_registry, &object_registry, level_config);
+    /// A game on `level_config` (defaults without one) whose monsters, the first level's
+    /// included, are capped at `max_entities`
+    pub fn new_with_level(tile_registry: TileRegistry, object_registry: GameObjectRegistry, level_config: Option<&LevelConfig>, max_entities: usize) -> Self {
+        // No players are connected yet, so the initial map is generated unscaled
+        let difficulty_scaling = DifficultyScaling::default();
+        let seed = StdRng::from_entropy().gen();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let generated = MapGenerator::generate_map(&tile_registry, &object_registry, level_config, &difficulty_scaling, 0, max_entities, &mut rng);
+        let level_has_boss = Self::has_boss(&object_registry, &generated.entities);
         
         Self {
-            dungeon,
-            entities,
-            consumables,
-            chests,
+            dungeon: generated.dungeon,
+            entities: generated.entities,
+            consumables: generated.consumables,
+            chests: generated.chests,
             tile_registry,
             object_registry,
-            stairs_position: stairs_pos,
-            player_confirmations: std::collections::HashSet::new(),
-            restart_confirmations: std::collections::HashSet::new(),
+            stairs_position: generated.stairs_position,
+            players: std::collections::HashMap::new(),
             turn_phase: TurnPhase::PlayerPhase,
             players_acted_this_turn: std::collections::HashSet::new(),
             current_turn: 1,
+            difficulty_scaling,
+            level_config: level_config.cloned(),
+            stairs_discovered: false,
+            vault_room: generated.vault_room,
+            vault_discovered: false,
+            stairs_up_position: generated.stairs_up_position,
+            spawn_position: generated.spawn_position,
+            current_level: level_config.map_or(1, |level| level.level_number),
+            level_configs: level_config.into_iter().cloned().collect(),
+            level_cache: std::collections::HashMap::new(),
+            permadeath: false,
+            status: GameStatus::Playing,
+            scoreboard: Vec::new(),
+            shared_party_vision: false,
+            wall_attacks_allowed: false,
+            friendly_fire: false,
+            ai_pathfinding_budget: None,
+            max_entities,
+            rest_heal_percent: 0,
+            monsters_avoid_stairs: false,
+            monsters_avoid_chests: false,
+            admin_commands: false,
+            recording: None,
+            broadcast_coalesce_ms: 0,
+            started_at: std::time::Instant::now(),
+            turns_since_combat: 0,
+            items_collected: 0,
+            level_has_boss,
+            level_won: false,
+            fired_triggers: std::collections::HashSet::new(),
+            min_players_to_start: 0,
+            rng,
+            seed,
+            spawned_count: 0,
         }
     }
 
+    /// A game set up the way the config asks: registries built from it, starting on level 1
+    /// if it defines one (default generation otherwise), with every game-wide option applied
+    pub fn from_config(config: &GameConfig) -> Self {
+        let tile_registry = TileRegistry::load_from_config(config);
+        let object_registry = GameObjectRegistry::load_from_config(config);
+        let first_level = config.levels.iter().find(|l| l.level_number == 1);
+        let mut game = Self::new_with_level(tile_registry, object_registry, first_level, config.monster_cap());
+        game.difficulty_scaling = config.difficulty_scaling.clone();
+        game.level_configs = config.levels.clone();
+        game.permadeath = config.permadeath;
+        game.wall_attacks_allowed = config.wall_attacks_allowed;
+        game.friendly_fire = config.friendly_fire;
+        game.ai_pathfinding_budget = config.ai_pathfinding_budget;
+        game.rest_heal_percent = config.rest_heal_percent;
+        game.monsters_avoid_stairs = config.monsters_avoid_stairs;
+        game.monsters_avoid_chests = config.monsters_avoid_chests;
+        game.admin_commands = config.admin_commands;
+        game.shared_party_vision = config.shared_party_vision;
+        game.broadcast_coalesce_ms = config.broadcast_coalesce_ms;
+        game.open_lobby(config.min_players_to_start);
+        game
+    }
+
+    /// Handle one player's command. Commands are handled one at a time (the state sits behind a
+    /// mutex), and a round goes:
+    /// 1. Each alive player gets exactly one turn-taking action; its effects (damage included)
+    ///    resolve right away. Rejected or unknown actions don't use the turn, and anything sent
+    ///    after acting is ignored until the next round.
+    /// 2. The action of the last alive player still to act runs the AI phase (finish_round), so
+    ///    monsters retaliate in the same update, then the next round starts.
+    ///
+    /// Monsters only ever act in step 2. Confirmations (stairs, restart) don't take a turn.
     pub fn handle_command(&mut self, cmd: &PlayerCommand, player_id: &str) -> (Vec<GameMessage>, bool, bool) {
+        self.record(player_id, ReplayAction::Command(cmd.clone()));
         let mut messages = Vec::new();
         let mut level_complete = false;
         let mut restart_confirmed = false;
         
+        // Monsters that died last tick have been shown as dying: splitting ones leave their
+        // children behind, then the bodies are removed
+        self.spawn_death_children();
+        self.entities.retain(|e| !e.dying);
+        
+        // Lobby: only readying up is accepted until enough players are ready.
+        // Starting generates the level, so reuse level_complete to make clients reload.
+        if self.status == GameStatus::Lobby {
+            if command::lookup(&cmd.action).is_some_and(|spec| spec.kind == CommandKind::Ready) {
+                messages.extend(self.player_ready(player_id));
+                level_complete = self.status == GameStatus::Playing;
+            }
+            return (messages, level_complete, restart_confirmed);
+        }
+        
+        // The run is over: nothing but an explicit new game is accepted.
+        // Starting over changes level, so reuse level_complete to make clients reload.
+        if self.status == GameStatus::GameOver {
+            if let Some(true) = cmd.new_game {
+                messages.push(self.new_game());
+                level_complete = true;
+            }
+            return (messages, level_complete, restart_confirmed);
+        }
+        
+        // With admin commands on, a new game can also be forced in the middle of a run
+        if self.admin_commands && cmd.new_game == Some(true) {
+            messages.push(self.new_game());
+            return (messages, true, restart_confirmed);
+        }
+        
         // Check if all players are dead
         let all_players_dead = self.are_all_players_dead();
         
+        // Permadeath: the run ends instead of restarting
+        if all_players_dead && self.permadeath {
+            messages.extend(self.end_run());
+            return (messages, level_complete, restart_confirmed);
+        }
+        
         // If all players are dead, automatically restart the level
         if all_players_dead {
             self.restart_level();
@@ -72,24 +287,38 @@ impl GameState {
         
         // Handle restart confirmation if present (allowed outside of turn)
         if let Some(true) = cmd.confirm_restart {
-            if let Some(msg) = self.confirm_restart(player_id) {
-                messages.push(msg);
-                restart_confirmed = true;
-            }
+            let (msg, restarted) = self.confirm_restart(player_id);
+            restart_confirmed = restarted;
+            messages.extend(msg);
             return (messages, level_complete, restart_confirmed);
         }
         
         // Handle stairs confirmation if present (allowed outside of turn)
+        // A refusal (system message) leaves everyone where they are
         if let Some(true) = cmd.confirm_stairs {
-            if let Some(msg) = self.confirm_stairs(player_id) {
+            let stairs_messages = self.confirm_stairs(player_id);
+            level_complete = stairs_messages.iter().any(|msg| msg.message_type == MessageType::LevelEvent);
+            messages.extend(stairs_messages);
+            return (messages, level_complete, restart_confirmed);
+        }
+        
+        // Handle stairs up confirmation if present (allowed outside of turn)
+        // Changing level reuses the level_complete flag so clients reload the map
+        if let Some(true) = cmd.confirm_stairs_up {
+            if let Some(msg) = self.confirm_stairs_up(player_id) {
+                level_complete = msg.message_type == MessageType::LevelEvent;
                 messages.push(msg);
-                level_complete = true;
             }
             return (messages, level_complete, restart_confirmed);
         }
         
+        // Everyone still playing already acted, but the round was left unfinished because the
+        // player it waited on left or died: finish it first so this command starts the next one
+        if self.turn_phase == TurnPhase::PlayerPhase && !self.players_acted_this_turn.is_empty() && self.all_players_acted() {
+            messages.extend(self.finish_round());
+        }
+        
         // For movement commands, check if it's the player's turn and they haven't acted yet
-        // Check if it's the player's turn and they haven't acted yet
         if self.turn_phase != TurnPhase::PlayerPhase {
             // Not player phase, ignore movement commands
             return (messages, level_complete, restart_confirmed);
@@ -101,342 +330,1446 @@ impl GameState {
             return (messages, level_complete, restart_confirmed);
         }
         
-        // Find the specific player entity by ID
-        let player_idx = self.entities.iter().position(|e| e.id == player_id && e.controller == EntityController::Player);
+        // Find the specific player entity by ID - fallen players wait for the level to end
+        let player_idx = self.entities.iter().position(|e| e.id == player_id && e.controller == EntityController::Player && e.is_alive());
         
         if let Some(idx) = player_idx {
-            let (dx, dy) = match cmd.action.as_str() {
-                "move_up" => (0, -1),
-                "move_down" => (0, 1),
-                "move_left" => (-1, 0),
-                "move_right" => (1, 0),
-                _ => {
-                    // Still process AI even if player action is invalid
-                    messages.extend(process_ai_turns(&mut self.entities, &self.dungeon, &self.object_registry, &mut self.consumables));
-                    return (messages, level_complete, restart_confirmed);
-                },
-            };
-            
-            // Check if there's an enemy at the target position
-            let entity = &self.entities[idx];
-            let new_x = (entity.x as i32 + dx) as usize;
-            let new_y = (entity.y as i32 + dy) as usize;
-            
-            // Check bounds
-            if new_x < self.dungeon.width && new_y < self.dungeon.height {
-                // Check if there's a closed chest at target position (highest priority)
-                if let Some(chest_idx) = self.chests.iter().position(|c| c.x == new_x && c.y == new_y && !c.is_open) {
-                    // Open chest instead of moving
-                    let chest = &mut self.chests[chest_idx];
-                    chest.is_open = true;
-                    
-                    // Spawn a potion at the chest location
-                    let potion_templates: Vec<&crate::game_object::GameObject> = self.object_registry.get_all_objects()
-                        .into_iter()
-                        .filter(|obj| obj.object_type == "consumable")
-                        .collect();
-                    
-                    if !potion_templates.is_empty() {
-                        use rand::Rng;
-                        let mut rng = rand::thread_rng();
-                        let potion_template = potion_templates[rng.gen_range(0..potion_templates.len())];
-                        
-                        use std::sync::atomic::{AtomicU64, Ordering};
-                        static CONSUMABLE_COUNTER: AtomicU64 = AtomicU64::new(0);
-                        let consumable_id = format!("consumable_{}", CONSUMABLE_COUNTER.fetch_add(1, Ordering::Relaxed));
-                        
-                        let consumable = Consumable {
-                            id: consumable_id,
-                            x: new_x,
-                            y: new_y,
-                            object_id: potion_template.id.clone(),
-                        };
-                        
-                        self.consumables.push(consumable);
-                        messages.push(GameMessage::level_event("Chest opened!".to_string()));
+            let kind = command::lookup(&cmd.action).map(|spec| spec.kind);
+            // Doing anything else cancels an auto-move
+            if kind != Some(CommandKind::AutoMoveTo) {
+                self.player_state_mut(idx).auto_move = None;
+            }
+            let acted = match kind {
+                Some(CommandKind::MoveUp) => self.player_step(idx, 0, -1, &mut messages),
+                Some(CommandKind::MoveDown) => self.player_step(idx, 0, 1, &mut messages),
+                Some(CommandKind::MoveLeft) => self.player_step(idx, -1, 0, &mut messages),
+                Some(CommandKind::MoveRight) => self.player_step(idx, 1, 0, &mut messages),
+                Some(CommandKind::Attack) => {
+                    let position = (self.entities[idx].x, self.entities[idx].y);
+                    match cmd.direction_delta(position) {
+                        Some((dx, dy)) => self.player_attack(idx, dx, dy, &mut messages),
+                        None => false,
                     }
                 }
-                // Check if there's an enemy (AI-controlled entity) at target position
-                else if let Some(target_idx) = self.entities.iter().position(|e| {
-                    e.id != entity.id && 
-                    e.x == new_x && 
-                    e.y == new_y && 
-                    e.is_alive() &&
-                    e.controller == EntityController::AI
-                }) {
-                    // Attack instead of moving
-                    if let Some(msg) = attack_entity(&mut self.entities, idx, target_idx, &self.object_registry, &mut self.consumables) {
-                        messages.push(msg);
-                    }
-                } else {
-                    // No enemy or closed chest, try to move
-                    // Check if there's a chest and if it's walkable in its current state
-                    let can_move = if let Some(chest) = self.chests.iter().find(|c| c.x == new_x && c.y == new_y) {
-                        // Check if chest is walkable in its current state
-                        if let Some(chest_obj) = self.object_registry.get_object(&chest.object_id) {
-                            chest_obj.get_interactable_walkable(chest.is_open)
-                        } else {
-                            // Chest object not found, default to not walkable if closed
-                            chest.is_open
-                        }
-                    } else {
-                        // No chest at this position, can move
-                        true
-                    };
-                    
-                    if can_move {
-                        self.move_entity(idx, dx, dy);
-                    }
-                    
-                    // Check if player stepped on a consumable
-                    let new_x = self.entities[idx].x;
-                    let new_y = self.entities[idx].y;
-                    if let Some(consumable_idx) = self.consumables.iter().position(|c| c.x == new_x && c.y == new_y) {
-                        // Player stepped on a consumable - consume it
-                        let consumable = &self.consumables[consumable_idx];
-                        if let Some(consumable_obj) = self.object_registry.get_object(&consumable.object_id) {
-                            if let Some(healing_power) = consumable_obj.healing_power {
-                                // Heal the player
-                                let old_health = self.entities[idx].current_health;
-                                self.entities[idx].heal(healing_power);
-                                let new_health = self.entities[idx].current_health;
-                                let healed_amount = new_health - old_health;
-                                
-                                // Create a healing message
-                                messages.push(GameMessage::healing(
-                                    consumable_obj.name.clone(),
-                                    self.entities[idx].id.clone(),
-                                    healed_amount,
-                                    new_health,
-                                ));
-                                
-                                // Remove the consumable
-                                self.consumables.remove(consumable_idx);
-                            }
-                        }
-                    }
-                    
-                    // Check if player stepped on stairs
-                    if let Some((stairs_x, stairs_y)) = self.stairs_position {
-                        if new_x == stairs_x && new_y == stairs_y {
-                            // Player stepped on stairs - they need to confirm
-                            // This will be handled by the client showing a confirmation dialog
-                            // For now, we just note that the player is on stairs
-                        }
+                Some(CommandKind::Taunt) => self.player_taunt(idx, &mut messages),
+                Some(CommandKind::ThrowItem) => self.player_throw(idx, cmd, &mut messages),
+                Some(CommandKind::AutoMoveTo) => self.player_auto_move(idx, cmd, &mut messages),
+                // Answered by the connection (api::examine_tile), it never takes a turn
+                Some(CommandKind::Examine) => false,
+                // Only means something in the lobby
+                Some(CommandKind::Ready) => false,
+                Some(CommandKind::LineAttack) => {
+                    let position = (self.entities[idx].x, self.entities[idx].y);
+                    match cmd.direction_delta(position) {
+                        Some((dx, dy)) => self.player_line_attack(idx, dx, dy, &mut messages),
+                        None => false,
                     }
                 }
-            }
-            
-            // Mark this player as having acted this turn (after any action: move, attack, or chest open)
-            self.players_acted_this_turn.insert(player_id.to_string());
-            
-            // Check if all alive players have taken their turn
-            let alive_players: Vec<String> = self.entities.iter()
-                .filter(|e| e.controller == EntityController::Player && e.is_alive())
-                .map(|e| e.id.clone())
-                .collect();
+                // Unknown actions are refused like any other invalid one: monsters only act
+                // once every player has
+                None => false,
+            };
             
-            let all_players_acted = !alive_players.is_empty() && 
-                alive_players.iter().all(|pid| self.players_acted_this_turn.contains(pid));
+            // Rejected actions don't use up the player's turn
+            if !acted {
+                return (messages, level_complete, restart_confirmed);
+            }
+            if messages.iter().any(GameMessage::is_attack) {
+                self.turns_since_combat = 0;
+            }
             
-            if all_players_acted {
-                // All players have acted, now process AI turns
-                self.turn_phase = TurnPhase::AIPhase;
-                
-                if !level_complete && !self.are_all_players_dead() {
-                    messages.extend(process_ai_turns(&mut self.entities, &self.dungeon, &self.object_registry, &mut self.consumables));
+            // Solo play: the only player acting always completes the round, so skip tracking who
+            // has acted. finish_round clears that tracking anyway, so the result is the same.
+            if self.is_solo(player_id) {
+                messages.extend(self.finish_round());
+            } else {
+                // Mark this player as having acted this turn (after any action: move, attack, or chest open),
+                // then check if all alive players have taken their turn
+                self.players_acted_this_turn.insert(player_id.to_string());
+                if self.all_players_acted() {
+                    messages.extend(self.finish_round());
                 }
-                
-                // Start next turn
-                self.turn_phase = TurnPhase::PlayerPhase;
-                self.players_acted_this_turn.clear();
-                self.current_turn += 1;
+            }
+            
+            let win_messages = self.check_win_condition();
+            if !win_messages.is_empty() {
+                messages.extend(win_messages);
+                level_complete = true;
             }
         }
         
         (messages, level_complete, restart_confirmed)
     }
     
-    pub fn are_all_players_dead(&self) -> bool {
-        let alive_players = self.entities.iter()
+    /// Whether the player is the only one in the game. Every player entity has its entry in
+    /// `players`, so this needs no scan; fallen teammates still count (the general path handles them).
+    fn is_solo(&self, player_id: &str) -> bool {
+        self.players.len() == 1 && self.players.contains_key(player_id)
+    }
+    
+    /// Whether every alive player has taken their turn this round (false with nobody alive)
+    fn all_players_acted(&self) -> bool {
+        let mut alive_players = self.entities.iter()
             .filter(|e| e.controller == EntityController::Player && e.is_alive())
-            .count();
-        alive_players == 0 && self.entities.iter().any(|e| e.controller == EntityController::Player)
+            .peekable();
+        alive_players.peek().is_some() && alive_players.all(|e| self.players_acted_this_turn.contains(&e.id))
     }
     
-    pub fn confirm_restart(&mut self, player_id: &str) -> Option<GameMessage> {
-        // Add player to restart confirmations
-        self.restart_confirmations.insert(player_id.to_string());
+    /// All alive players have acted: run the AI phase and start the next turn
+    fn finish_round(&mut self) -> Vec<GameMessage> {
+        let mut messages = Vec::new();
+        self.turn_phase = TurnPhase::AIPhase;
+        self.players_acted_this_turn.clear();
         
-        // Check if all players have confirmed
-        let all_players: Vec<String> = self.entities.iter()
-            .filter(|e| e.controller == EntityController::Player)
-            .map(|e| e.id.clone())
-            .collect();
+        if !self.are_all_players_dead() {
+            let health_before: Vec<u32> = self.entities.iter().map(|e| e.current_health).collect();
+            let blocked_tiles = self.monster_blocked_tiles();
+            let ai_messages = process_ai_turns(&mut self.entities, &self.dungeon, &self.object_registry, &mut self.consumables, self.ai_pathfinding_budget, &blocked_tiles, &mut self.rng);
+            if ai_messages.iter().any(GameMessage::is_attack) {
+                self.turns_since_combat = 0;
+            }
+            messages.extend(ai_messages);
+            // Armor wears down with every hit a player takes
+            let hit_players: Vec<usize> = health_before.iter().enumerate()
+                .filter(|&(idx, &health)| self.entities[idx].controller == EntityController::Player && self.entities[idx].current_health < health)
+                .map(|(idx, _)| idx)
+                .collect();
+            for idx in hit_players {
+                messages.extend(self.wear_equipment(idx, false));
+            }
+            messages.extend(self.check_trapped_players());
+        }
         
-        let all_confirmed = !all_players.is_empty() && all_players.iter().all(|pid| self.restart_confirmations.contains(pid));
+        // Status effects last a number of full rounds
+        for entity in &mut self.entities {
+            entity.tick_status_effects();
+        }
         
-        if all_confirmed {
-            // Reset the game state
-            self.restart_level();
-            return Some(GameMessage::level_event("Level restarted!".to_string()));
+        if self.permadeath && self.are_all_players_dead() {
+            messages.extend(self.end_run());
+            return messages;
         }
         
-        None
+        // Start next turn
+        self.turn_phase = TurnPhase::PlayerPhase;
+        self.current_turn += 1;
+        
+        messages.extend(self.expire_dropped_items());
+        messages.extend(self.spawn_survival_monster());
+        messages.extend(self.apply_pressure());
+        
+        // Out of turns without reaching the stairs - the level is failed
+        if self.turns_remaining() == Some(0) {
+            self.restart_level();
+            messages.push(GameMessage::level_event("Time's up! Level restarted.".to_string()));
+        }
+        messages
     }
     
-    pub fn restart_level(&mut self) {
-        // Save player IDs before clearing entities
-        let player_ids: Vec<String> = self.entities.iter()
-            .filter(|e| e.controller == EntityController::Player)
-            .map(|e| e.id.clone())
+    /// Age dropped items by a turn and remove those that have lain longer than their
+    /// object's despawn_turns
+    fn expire_dropped_items(&mut self) -> Vec<GameMessage> {
+        let mut messages = Vec::new();
+        let object_registry = &self.object_registry;
+        self.consumables.retain_mut(|consumable| {
+            let Some(age) = consumable.dropped_turns_ago.as_mut() else {
+                return true;
+            };
+            *age += 1;
+            let Some(obj) = object_registry.get_object(&consumable.object_id) else {
+                return true;
+            };
+            let expired = obj.despawn_turns.is_some_and(|turns| *age > turns);
+            if expired {
+                messages.push(GameMessage::system(format!("The {} fades away.", obj.name)));
+            }
+            !expired
+        });
+        messages
+    }
+    
+    /// Once a level's `pressure_turns` pass without combat, send a monster from out of sight
+    /// toward the room of the player nearest to it, so hanging back doesn't pay off
+    fn apply_pressure(&mut self) -> Option<GameMessage> {
+        self.turns_since_combat += 1;
+        let pressure_turns = self.level_config.as_ref().and_then(|level| level.pressure_turns)?;
+        if self.turns_since_combat < pressure_turns {
+            return None;
+        }
+        self.turns_since_combat = 0;
+        
+        let players: Vec<(usize, usize)> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player && e.is_alive())
+            .map(|e| (e.x, e.y))
+            .collect();
+        let distance_to = |x: usize, y: usize, (px, py): (usize, usize)| x.abs_diff(px).max(y.abs_diff(py));
+        let candidates: Vec<usize> = self.entities.iter()
+            .enumerate()
+            .filter(|(_, e)| e.controller == EntityController::AI && e.is_alive())
+            .filter(|(_, e)| players.iter().all(|&player| distance_to(e.x, e.y, player) >= OFF_SCREEN_DISTANCE))
+            .map(|(idx, _)| idx)
             .collect();
         
-        // Clear confirmations
-        self.player_confirmations.clear();
-        self.restart_confirmations.clear();
+        let &monster_idx = candidates.choose(&mut self.rng)?;
+        let (x, y) = (self.entities[monster_idx].x, self.entities[monster_idx].y);
+        let &nearest = players.iter().min_by_key(|&&player| distance_to(x, y, player))?;
+        // Head for the middle of the player's room, or straight at them in a corridor
+        let target = self.dungeon.rooms.iter()
+            .find(|room| room.contains(nearest.0, nearest.1))
+            .map_or(nearest, |room| room.center());
+        self.entities[monster_idx].last_seen_player = Some(target);
+        Some(GameMessage::level_event("You hear something moving in the distance...".to_string()))
+    }
+    
+    /// In survival mode, maybe spawn a monster somewhere no player can see. The chance grows
+    /// by 1% every 10 turns on top of the level's base chance.
+    fn spawn_survival_monster(&mut self) -> Option<GameMessage> {
+        let level = self.level_config.as_ref().filter(|level| level.survival_mode.unwrap_or(false))?;
+        if self.monster_room_left() == 0 {
+            return None;
+        }
         
-        // Reset turn system
-        self.turn_phase = TurnPhase::PlayerPhase;
-        self.players_acted_this_turn.clear();
-        self.current_turn = 1;
+        let spawn_percent = (level.survival_spawn_percent.unwrap_or(10) + self.current_turn / 10).min(100);
+        if self.rng.gen_range(0..100) >= spawn_percent {
+            return None;
+        }
         
-        // Remove all entities, consumables, and chests
-        self.entities.clear();
-        self.consumables.clear();
-        self.chests.clear();
+        let templates = MapGenerator::monster_templates(&self.object_registry, Some(level));
+        let template = (*templates.choose(&mut self.rng)?).clone();
         
-        // Generate completely new map (dungeon, monsters, chests, consumables, stairs)
-        // TODO: Use current level config when level system is implemented
-        let (dungeon, mut new_entities, new_consumables, new_chests, stairs_pos) = 
-            MapGenerator::generate_map(&self.tile_registry, &self.object_registry, None);
-        
-        self.dungeon = dungeon;
-        self.consumables = new_consumables;
-        self.chests = new_chests;
-        self.stairs_position = stairs_pos;
-        
-        // Find first player spawn position (from newly generated map)
-        let mut player_x = 1;
-        let mut player_y = 1;
-        for y in 0..self.dungeon.height {
-            for x in 0..self.dungeon.width {
-                if self.dungeon.tiles[y][x].walkable {
-                    player_x = x;
-                    player_y = y;
-                    break;
-                }
+        let players: Vec<(usize, usize)> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player && e.is_alive())
+            .map(|e| (e.x, e.y))
+            .collect();
+        let candidates: Vec<(usize, usize)> = (0..self.dungeon.height)
+            .flat_map(|y| (0..self.dungeon.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.dungeon.is_walkable(x, y))
+            .filter(|&(x, y)| players.iter().all(|&(px, py)| x.abs_diff(px).max(y.abs_diff(py)) >= OFF_SCREEN_DISTANCE))
+            .filter(|&(x, y)| !self.entities.iter().any(|e| e.x == x && e.y == y && e.is_alive()))
+            .filter(|&(x, y)| !self.chests.iter().any(|c| c.x == x && c.y == y))
+            .collect();
+        let &(x, y) = candidates.choose(&mut self.rng)?;
+        
+        let id = format!("survival_monster_{}", self.next_spawn_id());
+        let mut monster = MapGenerator::create_monster(
+            &template,
+            id,
+            (x, y),
+            &self.difficulty_scaling,
+            players.len(),
+            self.current_level,
+            &mut self.rng,
+        );
+        MapGenerator::assign_unique_name(&mut monster, &template, &self.entities, &mut self.rng);
+        if !self.try_spawn_entity(monster) {
+            return None;
+        }
+        
+        Some(GameMessage::level_event(format!("You hear a {} lurking somewhere in the dark...", template.name)))
+    }
+    
+    /// Warn players who can neither move nor attack anything (e.g. walled in by allies)
+    fn check_trapped_players(&self) -> Vec<GameMessage> {
+        let mut messages = Vec::new();
+        for (idx, entity) in self.entities.iter().enumerate() {
+            if entity.controller != EntityController::Player || !entity.is_alive() {
+                continue;
             }
-            if self.dungeon.tiles[player_y][player_x].walkable {
-                break;
+            let has_target = adjacent_entities(&self.entities, idx).iter()
+                .any(|&other| self.entities[other].controller == EntityController::AI);
+            if !has_target && !has_legal_move(&self.entities, &self.dungeon, idx) {
+                logging::debug!("[GAME] Player {} at ({}, {}) is boxed in with no legal moves", entity.id, entity.x, entity.y);
+                messages.push(GameMessage::system(format!("{} is boxed in and cannot move!", entity.id)));
             }
         }
+        messages
+    }
+    
+    /// A move command: one `player_move`, and for hasted players a second step the same way
+    /// if the first one walked onto the tile and the next is clear (haste never bumps or attacks)
+    fn player_step(&mut self, idx: usize, dx: i32, dy: i32, messages: &mut Vec<GameMessage>) -> bool {
+        let (old_x, old_y) = (self.entities[idx].x, self.entities[idx].y);
+        if !self.player_move(idx, dx, dy, messages) {
+            return false;
+        }
+        let (x, y) = (self.entities[idx].x, self.entities[idx].y);
+        let entity = &self.entities[idx];
+        if (x, y) == (old_x, old_y) || !entity.has_status(StatusEffectKind::Hasted) || entity.has_status(StatusEffectKind::Confused) || !entity.is_alive() {
+            return true;
+        }
         
-        // Re-add all players at the spawn location
-        for player_id in player_ids {
-            // Find spawn position next to first player if exists
-            let spawn_pos = if let Some(first_player) = self.entities.iter()
-                .find(|e| e.controller == EntityController::Player) {
-                // Try to spawn adjacent to the first player
-                let adjacent_positions = [
-                    (first_player.x.wrapping_sub(1), first_player.y),
-                    (first_player.x + 1, first_player.y),
-                    (first_player.x, first_player.y.wrapping_sub(1)),
-                    (first_player.x, first_player.y + 1),
-                ];
-                
-                let mut found = false;
-                let mut spawn_x = player_x;
-                let mut spawn_y = player_y;
-                
-                for (x, y) in adjacent_positions.iter() {
-                    if *x < self.dungeon.width && *y < self.dungeon.height {
-                        if self.dungeon.tiles[*y][*x].walkable {
-                            let occupied = self.entities.iter().any(|e| e.x == *x && e.y == *y);
-                            if !occupied {
-                                spawn_x = *x;
-                                spawn_y = *y;
-                                found = true;
-                                break;
-                            }
-                        }
-                    }
+        let (dx, dy) = (x as i32 - old_x as i32, y as i32 - old_y as i32);
+        let (next_x, next_y) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+        let occupied = self.entities.iter().any(|e| e.x == next_x && e.y == next_y && e.is_alive())
+            || self.chests.iter().any(|c| c.x == next_x && c.y == next_y && !c.is_open);
+        if !occupied && self.dungeon.validate_move(&self.entities[idx], dx, dy) {
+            self.player_move(idx, dx, dy, messages);
+        }
+        true
+    }
+    
+    /// Move the player one step, or bump into whatever is there: a closed chest opens
+    /// and an enemy gets attacked. Returns false if the move was rejected.
+    fn player_move(&mut self, idx: usize, dx: i32, dy: i32, messages: &mut Vec<GameMessage>) -> bool {
+        // Confused players stumble in a random direction instead (bumping whatever is there)
+        let (dx, dy) = if self.entities[idx].has_status(StatusEffectKind::Confused) {
+            match random_legal_direction(&self.entities[idx], &self.dungeon, &mut self.rng) {
+                Some(direction) if direction != (dx, dy) => {
+                    messages.push(GameMessage::system(format!("{} stumbles around in confusion!", self.entities[idx].id)));
+                    direction
                 }
-                
-                if found {
-                    (spawn_x, spawn_y)
+                _ => (dx, dy),
+            }
+        } else {
+            (dx, dy)
+        };
+        
+        // Reject illegal moves before mutating anything (doesn't use up the player's turn)
+        let entity = &self.entities[idx];
+        if !self.dungeon.validate_move(entity, dx, dy) {
+            if dx.abs() > 1 || dy.abs() > 1 {
+                logging::warning!("[GAME] Rejected suspicious move ({}, {}) from {}", dx, dy, entity.id);
+            }
+            return false;
+        }
+        
+        // Check if there's an enemy at the target position
+        let new_x = (entity.x as i32 + dx) as usize;
+        let new_y = (entity.y as i32 + dy) as usize;
+        
+        // Check if there's a closed chest, door or other interactable at target position (highest priority);
+        // walkable ones (shrines) are stepped onto instead
+        if let Some(object_idx) = self.chests.iter().position(|c| {
+            c.x == new_x && c.y == new_y && !c.is_open
+                && !self.object_registry.get_object(&c.object_id).is_some_and(|obj| obj.walkable)
+        }) {
+            return self.interact_with(idx, object_idx, messages);
+        }
+        
+        // Check if there's an enemy (AI-controlled entity) at target position
+        if let Some(target_idx) = self.entities.iter().position(|e| {
+            e.id != entity.id && 
+            e.x == new_x && 
+            e.y == new_y && 
+            e.is_alive() &&
+            e.controller == EntityController::AI
+        }) {
+            // Attack instead of moving
+            if let Some(msg) = attack_entity(&mut self.entities, &self.dungeon, idx, target_idx, &self.object_registry, &mut self.consumables, &mut self.rng) {
+                messages.push(msg);
+            }
+        } else {
+            // No enemy or closed chest, try to move
+            // Check if there's a chest and if it's walkable in its current state
+            let can_move = if let Some(chest) = self.chests.iter().find(|c| c.x == new_x && c.y == new_y) {
+                // Check if chest is walkable in its current state
+                if let Some(chest_obj) = self.object_registry.get_object(&chest.object_id) {
+                    chest_obj.get_interactable_walkable(chest.is_open)
                 } else {
-                    (player_x, player_y)
+                    // Chest object not found, default to not walkable if closed
+                    chest.is_open
                 }
             } else {
-                (player_x, player_y)
+                // No chest at this position, can move
+                true
             };
             
-            // Add player at spawn position
-            if let Some(player_template) = self.object_registry.get_object("player") {
-                use crate::entity::Entity;
-                let player_entity = Entity::new(
-                    player_id.clone(),
-                    spawn_pos.0,
-                    spawn_pos.1,
-                    "player".to_string(),
-                    player_template.attack.unwrap_or(10),
-                    player_template.defense.unwrap_or(0),
-                    player_template.attack_spread_percent.unwrap_or(20),
-                    player_template.crit_chance_percent.unwrap_or(0),
-                    player_template.crit_damage_percent.unwrap_or(150),
-                    player_template.health.unwrap_or(100),
-                    EntityController::Player,
-                );
-                self.entities.push(player_entity);
+            if can_move {
+                self.move_entity(idx, dx, dy);
+                messages.extend(self.discover_surroundings(idx));
+                messages.extend(self.fire_triggers(idx));
+                
+                // A walkable interactable (shrine) triggers when stepped onto
+                let (x, y) = (self.entities[idx].x, self.entities[idx].y);
+                if let Some(object_idx) = self.chests.iter().position(|c| c.x == x && c.y == y && !c.is_open) {
+                    self.interact_with(idx, object_idx, messages);
+                }
+            }
+            
+            // Check if player stepped on a consumable
+            let new_x = self.entities[idx].x;
+            let new_y = self.entities[idx].y;
+            let items_before = self.consumables.len();
+            if let Some(consumable_idx) = self.consumables.iter().position(|c| c.x == new_x && c.y == new_y) {
+                // Player stepped on a consumable - consume it
+                let consumable = &self.consumables[consumable_idx];
+                if let Some(consumable_obj) = self.object_registry.get_object(&consumable.object_id) {
+                    let player_id = self.entities[idx].id.clone();
+                    if consumable_obj.object_type == "equipment" && self.players.entry(player_id.clone()).or_default().equip(consumable_obj) {
+                        // Equipment is put on straight away when its slot is free
+                        messages.push(GameMessage::system(format!("You equip the {}.", consumable_obj.name)));
+                        self.consumables.remove(consumable_idx);
+                        self.update_equipment_stats(idx);
+                    } else if consumable_obj.throwable.unwrap_or(false) || consumable_obj.object_type == "key" || consumable_obj.object_type == "equipment" {
+                        // Throwables, keys and spare equipment go into the inventory to be used later
+                        let player = self.players.entry(player_id).or_default();
+                        player.add_to_inventory(consumable_obj);
+                        let total = player.inventory_count(&consumable_obj.id);
+                        messages.push(GameMessage::pickup(consumable_obj.name.clone(), total, consumable_obj.rarity.clone()));
+                        self.consumables.remove(consumable_idx);
+                    } else if let (Some(healing_power), Some(radius)) = (consumable_obj.healing_power, consumable_obj.heal_radius) {
+                        // Area consumable - heals every player in range
+                        let heal_messages = heal_allies(&mut self.entities, idx, radius, healing_power, &consumable_obj.name, &self.object_registry);
+                        if heal_messages.is_empty() {
+                            // Nobody was hurt, still tell the player it was used up
+                            let health = self.entities[idx].current_health;
+                            messages.push(GameMessage::healing(consumable_obj.name.clone(), self.entities[idx].id.clone(), 0, health));
+                        }
+                        messages.extend(heal_messages);
+                        self.consumables.remove(consumable_idx);
+                    } else if let Some(healing_power) = consumable_obj.healing_power {
+                        // Heal the player
+                        let old_health = self.entities[idx].current_health;
+                        self.entities[idx].heal(healing_power);
+                        let new_health = self.entities[idx].current_health;
+                        let healed_amount = new_health - old_health;
+                        
+                        // Create a healing message
+                        messages.push(GameMessage::healing(
+                            consumable_obj.name.clone(),
+                            self.entities[idx].id.clone(),
+                            healed_amount,
+                            new_health,
+                        ));
+                        
+                        // Remove the consumable
+                        self.consumables.remove(consumable_idx);
+                    }
+                }
+            }
+            self.items_collected += (items_before - self.consumables.len()) as u32;
+            
+            // Check if player stepped on stairs
+            if let Some((stairs_x, stairs_y)) = self.stairs_position {
+                if new_x == stairs_x && new_y == stairs_y {
+                    // Player stepped on stairs - they need to confirm
+                    // This will be handled by the client showing a confirmation dialog
+                    // For now, we just note that the player is on stairs
+                }
             }
         }
         
-        // Add the monsters from the generated map
-        self.entities.extend(new_entities);
+        true
     }
     
-    pub fn confirm_stairs(&mut self, player_id: &str) -> Option<GameMessage> {
-        // Add player to confirmations
-        self.player_confirmations.insert(player_id.to_string());
-        
-        // Check if all players have confirmed
-        let all_players: Vec<String> = self.entities.iter()
-            .filter(|e| e.controller == EntityController::Player && e.is_alive())
+    /// Take one step of an auto-move toward the command's target, or toward the remembered
+    /// destination when it has none. Stops (without using the turn) at the destination, when a
+    /// monster comes into view that wasn't there before, or when the player got hurt since the last step.
+    fn player_auto_move(&mut self, idx: usize, cmd: &PlayerCommand, messages: &mut Vec<GameMessage>) -> bool {
+        // Same radius as the client's viewport and monster aggro range
+        const VIEW_RADIUS: usize = 5;
+        let (x, y) = (self.entities[idx].x, self.entities[idx].y);
+        let in_view: Vec<String> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::AI && e.is_alive())
+            .filter(|e| e.x.abs_diff(x).max(e.y.abs_diff(y)) <= VIEW_RADIUS)
             .map(|e| e.id.clone())
             .collect();
         
-        let all_confirmed = all_players.iter().all(|pid| self.player_confirmations.contains(pid));
+        let auto_move = match (cmd.target_x, cmd.target_y) {
+            (Some(target_x), Some(target_y)) => AutoMove {
+                target: (target_x, target_y),
+                health: self.entities[idx].current_health,
+                monsters_in_view: in_view.clone(),
+            },
+            _ => match self.player_state_mut(idx).auto_move.take() {
+                Some(auto_move) => auto_move,
+                None => {
+                    messages.push(GameMessage::system("Auto-move where? (target_x/target_y required)".to_string()));
+                    return false;
+                }
+            },
+        };
         
-        if all_confirmed {
-            return Some(GameMessage::level_event("Level complete! All players confirmed. Preparing next level...".to_string()));
+        let player_id = self.entities[idx].id.clone();
+        if self.entities[idx].current_health < auto_move.health {
+            messages.push(GameMessage::system(format!("{} stops moving after taking damage.", player_id)));
+            return false;
+        }
+        if in_view.iter().any(|id| !auto_move.monsters_in_view.contains(id)) {
+            messages.push(GameMessage::system(format!("{} stops moving: a monster comes into view!", player_id)));
+            return false;
+        }
+        let (target_x, target_y) = auto_move.target;
+        if (x, y) == (target_x, target_y) {
+            return false;
+        }
+        let Some((dx, dy)) = find_path_step(&self.entities, &self.dungeon, x, y, target_x, target_y, idx) else {
+            messages.push(GameMessage::system("There's no way to get there.".to_string()));
+            return false;
+        };
+        if !self.player_move(idx, dx, dy, messages) {
+            return false;
         }
         
-        None
+        // Keep going next time unless this step arrived
+        if (self.entities[idx].x, self.entities[idx].y) != (target_x, target_y) {
+            let health = self.entities[idx].current_health;
+            self.player_state_mut(idx).auto_move = Some(AutoMove {
+                target: auto_move.target,
+                health,
+                monsters_in_view: in_view,
+            });
+        }
+        true
     }
     
-    pub fn add_player(&mut self, player_id: String) -> Option<usize> {
-        // Get player object template from registry - must have id "player"
-        let player_obj = self.object_registry.get_object("player");
+    /// Explicitly attack the adjacent tile in the given direction without moving.
+    /// Returns false (turn not used) if there's no enemy to attack there.
+    fn player_attack(&mut self, idx: usize, dx: i32, dy: i32, messages: &mut Vec<GameMessage>) -> bool {
+        // Melee attacks reach one orthogonally adjacent tile
+        if dx.abs() + dy.abs() != 1 {
+            return false;
+        }
         
-        if let Some(player_template) = player_obj {
-            // Find spawn position: next to first player if exists, otherwise first walkable tile
-            let mut spawn_x = 1;
-            let mut spawn_y = 1;
-            let mut found = false;
-            
-            // First, try to find an existing player to spawn next to
-            if let Some(first_player) = self.entities.iter().find(|e| e.controller == EntityController::Player && e.is_alive()) {
-                // Try to spawn adjacent to the first player
-                let adjacent_positions = [
-                    (first_player.x.wrapping_sub(1), first_player.y),     // Left
-                    (first_player.x + 1, first_player.y),                // Right
-                    (first_player.x, first_player.y.wrapping_sub(1)),    // Up
-                    (first_player.x, first_player.y + 1),                // Down
-                ];
-                
-                for (x, y) in adjacent_positions.iter() {
-                    if *x < self.dungeon.width && *y < self.dungeon.height {
+        let target_x = self.entities[idx].x as i32 + dx;
+        let target_y = self.entities[idx].y as i32 + dy;
+        let target_idx = self.entities.iter().position(|e| {
+            e.x as i32 == target_x &&
+            e.y as i32 == target_y &&
+            e.is_alive() &&
+            e.controller == EntityController::AI
+        });
+        
+        match target_idx {
+            Some(target_idx) => {
+                if let Some(msg) = attack_entity(&mut self.entities, &self.dungeon, idx, target_idx, &self.object_registry, &mut self.consumables, &mut self.rng) {
+                    messages.push(msg);
+                }
+                messages.extend(self.wear_equipment(idx, true));
+                true
+            }
+            // Swinging at a wall is either a wasted turn or refused, depending on the config
+            None if target_x < 0 || target_y < 0 || !self.dungeon.is_walkable(target_x as usize, target_y as usize) => {
+                self.entities[idx].face(dx, dy);
+                if self.wall_attacks_allowed {
+                    messages.push(GameMessage::system("Your attack glances harmlessly off the wall.".to_string()));
+                    true
+                } else {
+                    messages.push(GameMessage::system("You can't attack a wall.".to_string()));
+                    false
+                }
+            }
+            None => {
+                messages.push(GameMessage::system("Nothing to attack there.".to_string()));
+                false
+            }
+        }
+    }
+    
+    /// Piercing attack along a line, if the player's character has a line attack range.
+    /// Returns whether anything was hit (a miss doesn't use up the turn).
+    fn player_line_attack(&mut self, idx: usize, dx: i32, dy: i32, messages: &mut Vec<GameMessage>) -> bool {
+        let has_line_attack = self.object_registry.get_object(&self.entities[idx].object_id)
+            .is_some_and(|obj| obj.line_attack_range.is_some());
+        if !has_line_attack {
+            messages.push(GameMessage::system("You have no piercing attack.".to_string()));
+            return false;
+        }
+        
+        let hits = attack_line(&mut self.entities, &self.dungeon, idx, (dx, dy), self.friendly_fire, &self.object_registry, &mut self.consumables, &mut self.rng);
+        if hits.is_empty() {
+            messages.push(GameMessage::system("Nothing to hit in that direction.".to_string()));
+            return false;
+        }
+        messages.extend(hits);
+        messages.extend(self.wear_equipment(idx, true));
+        true
+    }
+    
+    /// Make every monster within the player's taunt_radius target them for taunt_turns turns.
+    /// Passive monsters are provoked too. Fails (without using the turn) if nothing is in reach.
+    fn player_taunt(&mut self, idx: usize, messages: &mut Vec<GameMessage>) -> bool {
+        let Some((radius, turns)) = self.object_registry.get_object(&self.entities[idx].object_id)
+            .and_then(|obj| obj.taunt_radius.map(|radius| (radius as usize, obj.taunt_turns.unwrap_or(3))))
+        else {
+            messages.push(GameMessage::system("You can't taunt.".to_string()));
+            return false;
+        };
+        
+        let (x, y) = (self.entities[idx].x, self.entities[idx].y);
+        let taunter_id = self.entities[idx].id.clone();
+        let mut taunted = 0;
+        for monster in self.entities.iter_mut()
+            .filter(|e| e.controller == EntityController::AI && e.is_alive())
+            .filter(|e| e.x.abs_diff(x).max(e.y.abs_diff(y)) <= radius)
+        {
+            monster.forced_target = Some(taunter_id.clone());
+            monster.taunt_turns_remaining = turns;
+            monster.provoked = true;
+            taunted += 1;
+        }
+        
+        if taunted == 0 {
+            messages.push(GameMessage::system("No monsters close enough to taunt.".to_string()));
+            return false;
+        }
+        messages.push(GameMessage::level_event(format!("{} taunts {} monster(s)!", taunter_id, taunted)));
+        true
+    }
+    
+    /// Use a closed interactable (chest, door, lever, ...) the player bumped into, or a walkable
+    /// one (shrine) they stepped onto: it opens and triggers its effect. Locked ones (`requires_key`) need the key, which is used up.
+    /// Returns false if it stays shut.
+    fn interact_with(&mut self, idx: usize, object_idx: usize, messages: &mut Vec<GameMessage>) -> bool {
+        let Some(obj) = self.object_registry.get_object(&self.chests[object_idx].object_id).cloned() else {
+            return false;
+        };
+        
+        if let Some(key_id) = &obj.requires_key {
+            let key_name = self.object_registry.get_object(key_id)
+                .map(|key| key.name.clone())
+                .unwrap_or_else(|| key_id.clone());
+            let player = self.player_state_mut(idx);
+            let Some(slot) = player.inventory.iter().position(|stack| &stack.object_id == key_id) else {
+                messages.push(GameMessage::system(format!("The {} is locked. You need the {}.", obj.name, key_name)));
+                return false;
+            };
+            player.take_from_inventory(slot);
+            messages.push(GameMessage::level_event(format!("You unlock the {} with the {}.", obj.name, key_name)));
+        }
+        
+        // A toggle stays in place and switches on or off, anything else opens for good
+        let toggle = obj.interactable.as_ref().and_then(|interactable| interactable.toggle).unwrap_or(false);
+        let object = &mut self.chests[object_idx];
+        if toggle {
+            object.is_active = !object.is_active;
+        } else {
+            object.is_open = true;
+        }
+        let (x, y, switched_on) = (object.x, object.y, !toggle || object.is_active);
+        
+        // Chests drop loot unless configured otherwise
+        let effect = obj.interactable.as_ref()
+            .and_then(|interactable| interactable.effect.clone())
+            .or_else(|| (obj.object_type == "chest").then_some(InteractionEffect::Loot));
+        match effect {
+            // Switching a toggle back off only undoes opened doors
+            _ if !switched_on && !matches!(effect, Some(InteractionEffect::OpenDoors { .. })) => {
+                messages.push(GameMessage::level_event(format!("You switch the {} off.", obj.name)));
+            }
+            Some(InteractionEffect::Loot) => self.drop_loot(x, y, messages),
+            Some(InteractionEffect::Heal { amount }) => {
+                let target_name = self.entities[idx].display_name(&self.object_registry);
+                let old_health = self.entities[idx].current_health;
+                self.entities[idx].heal(amount);
+                let new_health = self.entities[idx].current_health;
+                messages.push(GameMessage::healing(obj.name.clone(), target_name, new_health - old_health, new_health));
+            }
+            Some(InteractionEffect::OpenDoors { object_id }) => {
+                // Switched off, the doors shut again (except where someone stands in the way)
+                let occupied: Vec<(usize, usize)> = self.entities.iter()
+                    .filter(|e| e.is_alive())
+                    .map(|e| (e.x, e.y))
+                    .collect();
+                let mut changed = 0;
+                for door in self.chests.iter_mut().filter(|c| c.object_id == object_id && c.is_open != switched_on) {
+                    if switched_on || !occupied.contains(&(door.x, door.y)) {
+                        door.is_open = switched_on;
+                        changed += 1;
+                    }
+                }
+                messages.push(GameMessage::level_event(match (changed > 0, switched_on) {
+                    (true, true) => format!("You use the {}. Somewhere, something opens.", obj.name),
+                    (true, false) => format!("You use the {}. Somewhere, something closes.", obj.name),
+                    (false, _) => format!("You use the {}. Nothing happens.", obj.name),
+                }));
+            }
+            Some(InteractionEffect::SpawnMonsters { object_id, count }) => {
+                let spawned = self.spawn_monsters_near(&object_id, count, x, y);
+                if spawned > 0 {
+                    messages.push(GameMessage::level_event(format!("The {} awakens something!", obj.name)));
+                }
+            }
+            Some(InteractionEffect::Buff { kind, amount, turns }) => {
+                self.entities[idx].add_buff(kind, amount, turns);
+                let blessing = match kind {
+                    StatusEffectKind::Strengthened => format!("+{} attack", amount),
+                    StatusEffectKind::Fortified => format!("+{} defense", amount),
+                    StatusEffectKind::Hasted => "haste".to_string(),
+                    StatusEffectKind::Confused => "confusion".to_string(),
+                };
+                messages.push(GameMessage::level_event(format!(
+                    "The {} grants {} {} for {} turns. It falls silent.", obj.name, self.entities[idx].display_name(&self.object_registry), blessing, turns
+                )));
+            }
+            None if toggle => messages.push(GameMessage::level_event(format!("You switch the {} on.", obj.name))),
+            None => messages.push(GameMessage::level_event(format!("{} opened!", obj.name))),
+        }
+        true
+    }
+    
+    /// Drop a random consumable at (x, y), as when a chest is opened
+    fn drop_loot(&mut self, x: usize, y: usize, messages: &mut Vec<GameMessage>) {
+        if let Some(object_id) = self.object_registry.random_loot(&mut self.rng).map(|template| template.id.clone()) {
+            let consumable_id = format!("consumable_{}", self.next_spawn_id());
+            
+            let consumable = Consumable {
+                id: consumable_id,
+                x,
+                y,
+                object_id,
+                dropped_turns_ago: Some(0),
+            };
+            
+            self.consumables.push(consumable);
+            messages.push(GameMessage::level_event("Chest opened!".to_string()));
+        }
+    }
+    
+    /// Spawn up to `count` monsters of a template on free tiles around (x, y), within the monster cap.
+    /// Returns how many appeared.
+    fn spawn_monsters_near(&mut self, object_id: &str, count: u32, x: usize, y: usize) -> usize {
+        let Some(template) = self.object_registry.get_object(object_id).cloned() else {
+            return 0;
+        };
+        let player_count = self.entities.iter().filter(|e| e.controller == EntityController::Player).count();
+        let mut spawned = 0;
+        for i in 0..count {
+            let Some((mx, my)) = self.free_tile_near(x, y) else {
+                break;
+            };
+            let mut monster = MapGenerator::create_monster(
+                &template,
+                format!("summoned_{}_{}_{}", self.current_turn, object_id, i),
+                (mx, my),
+                &self.difficulty_scaling,
+                player_count,
+                self.current_level,
+                &mut self.rng,
+            );
+            MapGenerator::assign_unique_name(&mut monster, &template, &self.entities, &mut self.rng);
+            if !self.try_spawn_entity(monster) {
+                break;
+            }
+            spawned += 1;
+        }
+        spawned
+    }
+    
+    /// Leave the `on_death_spawn` children of dying monsters on free tiles around them. Children
+    /// are unscaled by player count and depth.
+    fn spawn_death_children(&mut self) {
+        let dying: Vec<(String, String, usize, usize)> = self.entities.iter()
+            .filter(|e| e.dying)
+            .map(|e| (e.id.clone(), e.object_id.clone(), e.x, e.y))
+            .collect();
+        for (dead_id, object_id, x, y) in dying {
+            let Some(spawn) = self.object_registry.get_object(&object_id).and_then(|obj| obj.on_death_spawn.clone()) else {
+                continue;
+            };
+            let Some(child_template) = self.object_registry.get_object(&spawn.object_id).cloned() else {
+                continue;
+            };
+            for i in 0..spawn.count {
+                let Some((cx, cy)) = self.free_tile_near(x, y) else {
+                    break;
+                };
+                let mut child = MapGenerator::create_monster(
+                    &child_template,
+                    format!("{}_spawn_{}", dead_id, i),
+                    (cx, cy),
+                    &DifficultyScaling::default(),
+                    1,
+                    1,
+                    &mut self.rng,
+                );
+                MapGenerator::assign_unique_name(&mut child, &child_template, &self.entities, &mut self.rng);
+                if !self.try_spawn_entity(child) {
+                    break;
+                }
+            }
+        }
+    }
+    
+    /// How many more monsters fit under the cap
+    fn monster_room_left(&self) -> usize {
+        let alive_monsters = self.entities.iter().filter(|e| e.controller == EntityController::AI && e.is_alive()).count();
+        self.max_entities.saturating_sub(alive_monsters)
+    }
+    
+    /// Number for the ID of something spawned during play. Never repeats within a run, so a
+    /// new ID can't clash with anything still on the map or on a cached level.
+    fn next_spawn_id(&mut self) -> u64 {
+        self.spawned_count += 1;
+        self.spawned_count
+    }
+    
+    /// Add a monster spawned mid-level, the one way summons, splits, triggers and survival
+    /// respawns get on the map. Refused (and logged) when the monster cap is reached or its
+    /// tile is a wall, taken, or a closed chest.
+    fn try_spawn_entity(&mut self, entity: Entity) -> bool {
+        if entity.controller == EntityController::AI && self.monster_room_left() == 0 {
+            logging::debug!("[GAME] Monster cap of {} reached, skipping spawn of {}", self.max_entities, entity.id);
+            return false;
+        }
+        let (x, y) = (entity.x, entity.y);
+        let taken = self.entities.iter().any(|e| e.x == x && e.y == y && e.is_alive())
+            || self.chests.iter().any(|c| c.x == x && c.y == y && !c.is_open);
+        if !self.dungeon.is_walkable(x, y) || taken {
+            logging::debug!("[GAME] Tile ({}, {}) isn't free, skipping spawn of {}", x, y, entity.id);
+            return false;
+        }
+        self.level_has_boss |= Self::has_boss(&self.object_registry, std::slice::from_ref(&entity));
+        self.entities.push(entity);
+        true
+    }
+    
+    /// Whether any of `entities` is a boss monster (`boss = true`)
+    fn has_boss(object_registry: &GameObjectRegistry, entities: &[Entity]) -> bool {
+        entities.iter().any(|e| {
+            e.controller == EntityController::AI
+                && object_registry.get_object(&e.object_id).and_then(|obj| obj.boss).unwrap_or(false)
+        })
+    }
+    
+    /// Throw an inventory item at a target tile within its range and line of sight. Healing items
+    /// heal whoever is there, damaging ones hurt them. The item is used up even if it hits nothing.
+    fn player_throw(&mut self, idx: usize, cmd: &PlayerCommand, messages: &mut Vec<GameMessage>) -> bool {
+        let (Some(target_x), Some(target_y)) = (cmd.target_x, cmd.target_y) else {
+            messages.push(GameMessage::system("Throw where? (target_x/target_y required)".to_string()));
+            return false;
+        };
+        
+        let inventory = self.player_state_mut(idx).inventory.clone();
+        let slot = match &cmd.item {
+            Some(item) => inventory.iter().position(|stack| stack.object_id == *item),
+            None => (!inventory.is_empty()).then_some(0),
+        };
+        let Some(slot) = slot else {
+            messages.push(GameMessage::system("You have nothing like that to throw.".to_string()));
+            return false;
+        };
+        let Some(item) = self.object_registry.get_object(&inventory[slot].object_id).cloned() else {
+            return false;
+        };
+        
+        // Range and line of sight: walls along the way (or at the target) block the throw, windows don't
+        let (from_x, from_y) = (self.entities[idx].x, self.entities[idx].y);
+        let distance = from_x.abs_diff(target_x).max(from_y.abs_diff(target_y));
+        if distance as u32 > item.throw_range.unwrap_or(5) {
+            messages.push(GameMessage::system("That's too far to throw.".to_string()));
+            return false;
+        }
+        let line = bresenham_line((from_x as i32, from_y as i32), (target_x as i32, target_y as i32));
+        if line.iter().skip(1).any(|&(x, y)| self.dungeon.blocks_projectiles(x as usize, y as usize)) {
+            messages.push(GameMessage::system("You can't see a clear path to throw there.".to_string()));
+            return false;
+        }
+        
+        self.player_state_mut(idx).take_from_inventory(slot);
+        
+        let target_idx = self.entities.iter().position(|e| e.x == target_x && e.y == target_y && e.is_alive());
+        let Some(target_idx) = target_idx else {
+            messages.push(GameMessage::level_event(format!("The {} shatters on the floor.", item.name)));
+            return true;
+        };
+        let target_name = self.entities[target_idx].display_name(&self.object_registry);
+        
+        if let Some(healing_power) = item.healing_power.filter(|&power| power > 0) {
+            let old_health = self.entities[target_idx].current_health;
+            self.entities[target_idx].heal(healing_power);
+            let new_health = self.entities[target_idx].current_health;
+            messages.push(GameMessage::healing(item.name.clone(), target_name.clone(), new_health - old_health, new_health));
+        }
+        if let Some(damage) = item.damage.filter(|&damage| damage > 0) {
+            let damage_type = item.damage_type.as_deref().unwrap_or(DEFAULT_DAMAGE_TYPE);
+            let damage = damage_against(damage as i32, damage_type, &self.entities[target_idx]);
+            self.entities[target_idx].provoked = true;
+            let health_after = apply_damage(&mut self.entities, &self.dungeon, target_idx, damage, &self.object_registry, &mut self.consumables, &mut self.rng);
+            messages.push(GameMessage::combat(item.name.clone(), target_name, damage, health_after, health_after == 0));
+        }
+        if let Some(inflicted) = &item.inflicts_status {
+            try_inflict_status(&mut self.entities[target_idx], inflicted, &mut self.rng);
+        }
+        true
+    }
+    
+    /// Turns left (including the current one) before the level's turn limit runs out
+    pub fn turns_remaining(&self) -> Option<u32> {
+        self.level_config.as_ref()
+            .and_then(|level| level.turn_limit)
+            .map(|limit| (limit + 1).saturating_sub(self.current_turn))
+    }
+    
+    /// Enter GameOver and record the run on the scoreboard (only once per run)
+    fn end_run(&mut self) -> Option<GameMessage> {
+        if self.status == GameStatus::GameOver {
+            return None;
+        }
+        self.status = GameStatus::GameOver;
+        
+        let record = RunRecord {
+            players: self.entities.iter()
+                .filter(|e| e.controller == EntityController::Player)
+                .map(|e| e.id.clone())
+                .collect(),
+            level: self.current_level,
+            turns: self.current_turn,
+        };
+        let text = format!("Game over! The party fell on level {} after {} turns.", record.level, record.turns);
+        self.scoreboard.push(record);
+        Some(GameMessage::level_event(text))
+    }
+    
+    /// Tiles monsters won't step onto: chests, doors and levers players couldn't walk onto
+    /// either, plus (if the config asks for it, so they can't block objectives) the stairs
+    /// and opened chests
+    fn monster_blocked_tiles(&self) -> Vec<(usize, usize)> {
+        let mut tiles: Vec<(usize, usize)> = self.chests.iter()
+            .filter(|chest| {
+                let walkable = self.object_registry.get_object(&chest.object_id)
+                    .map_or(chest.is_open, |obj| obj.get_interactable_walkable(chest.is_open));
+                let avoided = self.monsters_avoid_chests
+                    && self.object_registry.get_object(&chest.object_id).is_some_and(|obj| obj.object_type == "chest");
+                !walkable || avoided
+            })
+            .map(|chest| (chest.x, chest.y))
+            .collect();
+        if self.monsters_avoid_stairs {
+            tiles.extend(self.stairs_position);
+            tiles.extend(self.stairs_up_position);
+        }
+        tiles
+    }
+    
+    /// Hold the game in a lobby until `min_players` connected players are ready. The map
+    /// stays empty of monsters until then.
+    pub fn open_lobby(&mut self, min_players: u32) {
+        if min_players <= 1 {
+            return;
+        }
+        self.min_players_to_start = min_players;
+        self.status = GameStatus::Lobby;
+        self.entities.retain(|e| e.controller == EntityController::Player);
+    }
+    
+    /// Connected players and how many of them are ready, while in the lobby
+    pub fn lobby_status(&self) -> Option<LobbyStatus> {
+        if self.status != GameStatus::Lobby {
+            return None;
+        }
+        let connected: Vec<&Entity> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player)
+            .collect();
+        Some(LobbyStatus {
+            connected: connected.len(),
+            ready: connected.iter().filter(|e| self.players.get(&e.id).is_some_and(|player| player.ready)).count(),
+            min_players: self.min_players_to_start,
+        })
+    }
+    
+    /// Entities, chests and items inside each room of the level, in `dungeon.rooms` order.
+    /// Anything standing in a corridor isn't listed.
+    pub fn room_contents(&self) -> Vec<RoomContents> {
+        self.dungeon.rooms.iter()
+            .enumerate()
+            .map(|(room_idx, room)| RoomContents {
+                room: room.clone(),
+                is_vault: self.vault_room == Some(room_idx),
+                entities: self.entities.iter()
+                    .filter(|e| e.is_alive() && room.contains(e.x, e.y))
+                    .map(|e| e.id.clone())
+                    .collect(),
+                chests: self.chests.iter()
+                    .filter(|chest| room.contains(chest.x, chest.y))
+                    .map(|chest| chest.id.clone())
+                    .collect(),
+                consumables: self.consumables.iter()
+                    .filter(|item| room.contains(item.x, item.y))
+                    .map(|item| item.id.clone())
+                    .collect(),
+            })
+            .collect()
+    }
+    
+    /// Mark a player ready. Once enough connected players are, generate the level for them
+    /// and start playing.
+    fn player_ready(&mut self, player_id: &str) -> Vec<GameMessage> {
+        self.players.entry(player_id.to_string()).or_default().ready = true;
+        let Some(lobby) = self.lobby_status() else {
+            return Vec::new();
+        };
+        if lobby.ready < lobby.min_players as usize || lobby.ready < lobby.connected {
+            return vec![GameMessage::system(format!(
+                "{} is ready. ({}/{} ready, {} needed to start)", player_id, lobby.ready, lobby.connected, lobby.min_players
+            ))];
+        }
+        
+        self.status = GameStatus::Playing;
+        self.restart_level();
+        vec![GameMessage::level_event("Everyone is ready. The adventure begins!".to_string())]
+    }
+    
+    /// Start the run over (after GameOver, or forced by an admin) on a fresh seed, drawn from
+    /// the current one so a replay of the run draws the same
+    pub fn new_game(&mut self) -> GameMessage {
+        let seed = self.rng.gen();
+        self.new_game_on(seed)
+    }
+    
+    /// Start the run over on `seed`: forget visited levels and generate a fresh first level.
+    /// Connected players are kept, starting over like on a restart; the scoreboard and any
+    /// recording are kept.
+    pub fn new_game_on(&mut self, seed: u64) -> GameMessage {
+        self.reseed(seed);
+        self.status = GameStatus::Playing;
+        self.level_cache.clear();
+        self.level_config = self.level_configs.iter().find(|l| l.level_number == 1).cloned();
+        self.current_level = 1;
+        self.spawned_count = 0;
+        self.restart_level();
+        GameMessage::level_event("A new run begins!".to_string())
+    }
+    
+    /// Draw every random choice from here on from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.seed = seed;
+    }
+    
+    /// Start recording a replay (dropping any ongoing recording). The run restarts from level 1
+    /// on a fresh seed with the connected players, so the recording can rebuild it from scratch.
+    pub fn start_recording(&mut self) -> GameMessage {
+        let seed = StdRng::from_entropy().gen();
+        let players = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player)
+            .map(|e| e.id.clone())
+            .collect();
+        let message = self.new_game_on(seed);
+        self.recording = Some(Replay::new(seed, players));
+        message
+    }
+    
+    /// Add a step to the ongoing recording, if any
+    pub fn record(&mut self, player_id: &str, action: ReplayAction) {
+        let (turn, level) = (self.current_turn, self.current_level);
+        if let Some(replay) = self.recording.as_mut() {
+            replay.record(turn, level, player_id, action);
+        }
+    }
+    
+    pub fn are_all_players_dead(&self) -> bool {
+        let alive_players = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player && e.is_alive())
+            .count();
+        alive_players == 0 && self.entities.iter().any(|e| e.controller == EntityController::Player)
+    }
+    
+    /// Count `player_id`'s vote to restart a wiped party's level. Returns what to tell them and
+    /// whether this was the last vote needed, so the level restarted.
+    pub fn confirm_restart(&mut self, player_id: &str) -> (Option<GameMessage>, bool) {
+        // Only a wiped party can vote to restart
+        if !self.are_all_players_dead() {
+            return (Some(GameMessage::system("You can only restart once the whole party has fallen.".to_string())), false);
+        }
+        
+        // Add player to restart confirmations
+        self.players.entry(player_id.to_string()).or_default().confirmed_restart = true;
+        
+        // Check if all players have confirmed
+        let all_players: Vec<String> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player)
+            .map(|e| e.id.clone())
+            .collect();
+        
+        let all_confirmed = !all_players.is_empty() && all_players.iter().all(|pid| self.players.get(pid).is_some_and(|player| player.confirmed_restart));
+        
+        if all_confirmed {
+            // Reset the game state
+            self.restart_level();
+            return (Some(GameMessage::level_event("Level restarted!".to_string())), true);
+        }
+        
+        (None, false)
+    }
+    
+    pub fn restart_level(&mut self) {
+        // Save player IDs before clearing entities
+        let player_ids: Vec<String> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player)
+            .map(|e| e.id.clone())
+            .collect();
+        
+        // Clear confirmations and rooms discovered on the old map
+        for player in self.players.values_mut() {
+            player.reset_for_level();
+        }
+        
+        // Reset turn system
+        self.turn_phase = TurnPhase::PlayerPhase;
+        self.players_acted_this_turn.clear();
+        self.current_turn = 1;
+        self.turns_since_combat = 0;
+        self.items_collected = 0;
+        
+        // New map, nothing discovered or triggered yet
+        self.stairs_discovered = false;
+        self.vault_discovered = false;
+        self.fired_triggers.clear();
+        
+        // Remove all entities, consumables, and chests
+        self.entities.clear();
+        self.consumables.clear();
+        self.chests.clear();
+        
+        // Generate completely new map (dungeon, monsters, chests, consumables, stairs)
+        // Monsters are scaled for the players that are connected right now
+        let generated = 
+            MapGenerator::generate_map(&self.tile_registry, &self.object_registry, self.level_config.as_ref(), &self.difficulty_scaling, player_ids.len(), self.max_entities, &mut self.rng);
+        let new_entities = generated.entities;
+        
+        self.dungeon = generated.dungeon;
+        self.consumables = generated.consumables;
+        self.chests = generated.chests;
+        self.stairs_position = generated.stairs_position;
+        self.stairs_up_position = generated.stairs_up_position;
+        self.spawn_position = generated.spawn_position;
+        self.vault_room = generated.vault_room;
+        
+        // Player spawn position from the newly generated map
+        let (player_x, player_y) = self.spawn_position;
+        
+        // Re-add all players at the spawn location
+        for player_id in player_ids {
+            // Find spawn position next to first player if exists
+            let spawn_pos = if let Some(first_player) = self.entities.iter()
+                .find(|e| e.controller == EntityController::Player) {
+                // Try to spawn adjacent to the first player
+                let adjacent_positions = [
+                    (first_player.x.wrapping_sub(1), first_player.y),
+                    (first_player.x + 1, first_player.y),
+                    (first_player.x, first_player.y.wrapping_sub(1)),
+                    (first_player.x, first_player.y + 1),
+                ];
+                
+                let mut found = false;
+                let mut spawn_x = player_x;
+                let mut spawn_y = player_y;
+                
+                for (x, y) in adjacent_positions.iter() {
+                    if *x < self.dungeon.width && *y < self.dungeon.height {
+                        if self.dungeon.tiles[*y][*x].walkable {
+                            let occupied = self.entities.iter().any(|e| e.x == *x && e.y == *y);
+                            if !occupied {
+                                spawn_x = *x;
+                                spawn_y = *y;
+                                found = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+                
+                if found {
+                    (spawn_x, spawn_y)
+                } else {
+                    (player_x, player_y)
+                }
+            } else {
+                (player_x, player_y)
+            };
+            
+            // Add player at spawn position
+            if let Some(player_template) = self.object_registry.get_object("player") {
+                use crate::entity::Entity;
+                let mut player_entity = Entity::new(
+                    player_id.clone(),
+                    spawn_pos.0,
+                    spawn_pos.1,
+                    "player".to_string(),
+                    player_template.attack.unwrap_or(10),
+                    player_template.defense.unwrap_or(0),
+                    player_template.attack_spread_percent.unwrap_or(20),
+                    player_template.crit_chance_percent.unwrap_or(0),
+                    player_template.crit_damage_percent.unwrap_or(150),
+                    player_template.health.unwrap_or(100),
+                    EntityController::Player,
+                );
+                player_entity.set_damage_traits(player_template);
+                self.entities.push(player_entity);
+                // Restarting starts everyone over, inventory included
+                self.players.insert(player_id.clone(), PlayerState::new(player_template));
+            }
+        }
+        
+        // Add the monsters from the generated map
+        self.level_has_boss = Self::has_boss(&self.object_registry, &new_entities);
+        self.level_won = false;
+        self.entities.extend(new_entities);
+    }
+    
+    /// Alive monsters on a level that requires a full clear before the stairs open (None on other levels)
+    pub fn monsters_remaining(&self) -> Option<usize> {
+        self.level_config.as_ref().filter(|level| level.require_clear.unwrap_or(false))?;
+        Some(self.entities.iter()
+            .filter(|e| e.controller == EntityController::AI && e.is_alive())
+            .count())
+    }
+    
+    /// Progress toward the level's win condition (None = won by reaching the stairs). Once the
+    /// condition has been met, or on a kill_boss level whose boss never got placed, the stairs
+    /// are the way on.
+    pub fn win_progress(&self) -> Option<WinProgress> {
+        let condition = self.level_config.as_ref()?.win_condition?;
+        if self.level_won {
+            return None;
+        }
+        let remaining = match condition {
+            WinCondition::ReachStairs => return None,
+            WinCondition::KillBoss if !self.level_has_boss => return None,
+            WinCondition::KillBoss => self.entities.iter()
+                .filter(|e| e.controller == EntityController::AI && e.is_alive())
+                .filter(|e| self.object_registry.get_object(&e.object_id).and_then(|obj| obj.boss).unwrap_or(false))
+                .count() as u32,
+            WinCondition::SurviveTurns(turns) => turns.saturating_sub(self.current_turn - 1),
+            WinCondition::CollectItems(items) => items.saturating_sub(self.items_collected),
+        };
+        Some(WinProgress { condition, remaining })
+    }
+    
+    /// Take the party down a level once the level's win condition (other than the stairs) is met
+    fn check_win_condition(&mut self) -> Vec<GameMessage> {
+        let Some(progress) = self.win_progress().filter(|progress| progress.remaining == 0) else {
+            return Vec::new();
+        };
+        let reason = match progress.condition {
+            WinCondition::KillBoss => "The boss has fallen!",
+            WinCondition::SurviveTurns(_) => "You survived!",
+            WinCondition::CollectItems(_) => "Every item collected!",
+            WinCondition::ReachStairs => return Vec::new(),
+        };
+        self.level_won = true;
+        self.descend(format!("Level complete! {} Descending to level {}...", reason, self.current_level + 1))
+    }
+    
+    /// Announce the way down, let the party rest and take it to the next level
+    fn descend(&mut self, announcement: String) -> Vec<GameMessage> {
+        let mut messages = vec![GameMessage::level_event(announcement)];
+        messages.extend(self.rest_party());
+        self.change_level(self.current_level + 1);
+        messages
+    }
+    
+    /// Heal every surviving player by the config's rest_heal_percent of their max health,
+    /// the rest they get between floors (nothing when it's 0)
+    fn rest_party(&mut self) -> Vec<GameMessage> {
+        if self.rest_heal_percent == 0 {
+            return Vec::new();
+        }
+        let mut messages = Vec::new();
+        for player in self.entities.iter_mut().filter(|e| e.controller == EntityController::Player && e.is_alive()) {
+            let old_health = player.current_health;
+            player.heal(player.max_health * self.rest_heal_percent.min(100) / 100);
+            let new_health = player.current_health;
+            messages.push(GameMessage::healing("Resting".to_string(), player.id.clone(), new_health - old_health, new_health));
+        }
+        messages
+    }
+    
+    /// Whether the player is standing on `position` (false if there's no such tile)
+    fn player_is_on(&self, player_id: &str, position: Option<(usize, usize)>) -> bool {
+        position.is_some_and(|(x, y)| self.entities.iter().any(|e| e.id == player_id && e.x == x && e.y == y))
+    }
+    
+    pub fn confirm_stairs(&mut self, player_id: &str) -> Vec<GameMessage> {
+        if !self.player_is_on(player_id, self.stairs_position) {
+            return vec![GameMessage::system("You need to stand on the stairs down to descend.".to_string())];
+        }
+        if let Some(remaining) = self.monsters_remaining().filter(|&remaining| remaining > 0) {
+            return vec![GameMessage::system(format!("Defeat all enemies first. ({} remaining)", remaining))];
+        }
+        if self.win_progress().is_some() {
+            return vec![GameMessage::system("The stairs are sealed. This level is won another way.".to_string())];
+        }
+        
+        // Add player to confirmations
+        self.players.entry(player_id.to_string()).or_default().confirmed_stairs = true;
+        
+        // Check if all players have confirmed
+        let all_players: Vec<String> = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player && e.is_alive())
+            .map(|e| e.id.clone())
+            .collect();
+        
+        let all_confirmed = all_players.iter().all(|pid| self.players.get(pid).is_some_and(|player| player.confirmed_stairs));
+        
+        if all_confirmed {
+            return self.descend(format!("Level complete! All players confirmed. Descending to level {}...", self.current_level + 1));
+        }
+        
+        Vec::new()
+    }
+    
+    pub fn confirm_stairs_up(&mut self, player_id: &str) -> Option<GameMessage> {
+        // Nowhere to go back to from the first level
+        if self.stairs_up_position.is_none() || self.current_level <= 1 {
+            return Some(GameMessage::system("There is no way back up from here.".to_string()));
+        }
+        if !self.player_is_on(player_id, self.stairs_up_position) {
+            return Some(GameMessage::system("You need to stand on the stairs up to climb back.".to_string()));
+        }
+        
+        // Add player to confirmations
+        self.players.entry(player_id.to_string()).or_default().confirmed_stairs_up = true;
+        
+        // Check if all players have confirmed
+        let all_confirmed = self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player && e.is_alive())
+            .all(|e| self.players.get(&e.id).is_some_and(|player| player.confirmed_stairs_up));
+        
+        if all_confirmed {
+            let previous_level = self.current_level - 1;
+            self.change_level(previous_level);
+            return Some(GameMessage::level_event(format!("All players confirmed. Climbing back up to level {}...", previous_level)));
+        }
+        
+        None
+    }
+    
+    /// Take the party to another level. The level being left is cached so that coming back
+    /// finds it as it was; the target level is restored from the cache or generated fresh.
+    /// Players arrive next to the stairs leading back to where they came from.
+    fn change_level(&mut self, level_number: u32) {
+        let going_down = level_number > self.current_level;
+        
+        let (players, monsters): (Vec<Entity>, Vec<Entity>) = std::mem::take(&mut self.entities)
+            .into_iter()
+            .partition(|e| e.controller == EntityController::Player);
+        
+        let level = match self.level_cache.remove(&level_number) {
+            Some(cached) => cached,
+            None => {
+                let level_config = self.level_configs.iter().find(|l| l.level_number == level_number).cloned();
+                let generated: GeneratedMap = MapGenerator::generate_map(
+                    &self.tile_registry, &self.object_registry, level_config.as_ref(), &self.difficulty_scaling, players.len(), self.max_entities, &mut self.rng);
+                let has_boss = Self::has_boss(&self.object_registry, &generated.entities);
+                CachedLevel {
+                    dungeon: generated.dungeon,
+                    monsters: generated.entities,
+                    consumables: generated.consumables,
+                    chests: generated.chests,
+                    stairs_position: generated.stairs_position,
+                    stairs_up_position: generated.stairs_up_position,
+                    level_config,
+                    discovered_rooms: std::collections::HashMap::new(),
+                    stairs_discovered: false,
+                    fired_triggers: std::collections::HashSet::new(),
+                    vault_room: generated.vault_room,
+                    vault_discovered: false,
+                    has_boss,
+                    won: false,
+                }
+            }
+        };
+        
+        let left_behind = CachedLevel {
+            dungeon: std::mem::replace(&mut self.dungeon, level.dungeon),
+            monsters: monsters.into_iter().filter(|e| e.is_alive()).collect(),
+            consumables: std::mem::replace(&mut self.consumables, level.consumables),
+            chests: std::mem::replace(&mut self.chests, level.chests),
+            stairs_position: std::mem::replace(&mut self.stairs_position, level.stairs_position),
+            stairs_up_position: std::mem::replace(&mut self.stairs_up_position, level.stairs_up_position),
+            level_config: std::mem::replace(&mut self.level_config, level.level_config),
+            // Exploration stays with the level, so coming back doesn't hide it again
+            discovered_rooms: self.players.iter_mut()
+                .map(|(id, player)| (id.clone(), std::mem::take(&mut player.discovered_rooms)))
+                .collect(),
+            stairs_discovered: self.stairs_discovered,
+            fired_triggers: std::mem::replace(&mut self.fired_triggers, level.fired_triggers),
+            vault_room: std::mem::replace(&mut self.vault_room, level.vault_room),
+            vault_discovered: self.vault_discovered,
+            has_boss: self.level_has_boss,
+            won: self.level_won,
+        };
+        self.level_cache.insert(self.current_level, left_behind);
+        self.entities = level.monsters;
+        self.current_level = level_number;
+        
+        // Fresh turn and confirmations for the new level, with whatever was explored on an earlier visit
+        for player in self.players.values_mut() {
+            player.reset_for_level();
+        }
+        for (id, rooms) in level.discovered_rooms {
+            if let Some(player) = self.players.get_mut(&id) {
+                player.discovered_rooms = rooms;
+            }
+        }
+        self.turn_phase = TurnPhase::PlayerPhase;
+        self.players_acted_this_turn.clear();
+        self.current_turn = 1;
+        self.turns_since_combat = 0;
+        self.items_collected = 0;
+        self.stairs_discovered = level.stairs_discovered;
+        self.vault_discovered = level.vault_discovered;
+        self.level_has_boss = level.has_boss;
+        self.level_won = level.won;
+        
+        // Arrive next to the stairs leading back, or at the first walkable tile without them
+        let arrival = if going_down { self.stairs_up_position } else { self.stairs_position };
+        self.spawn_position = arrival
+            .and_then(|(x, y)| self.free_tile_near(x, y))
+            .or_else(|| self.free_tile_near(0, 0))
+            .unwrap_or((1, 1));
+        
+        for mut player in players {
+            let (x, y) = self.free_tile_near(self.spawn_position.0, self.spawn_position.1).unwrap_or(self.spawn_position);
+            player.x = x;
+            player.y = y;
+            self.entities.push(player);
+        }
+    }
+    
+    /// Closest walkable tile to (x, y) that isn't occupied, a stairs tile, or a closed chest
+    fn free_tile_near(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let max_radius = self.dungeon.width.max(self.dungeon.height) as i32;
+        for radius in 0..=max_radius {
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    // Only look at the ring at this radius
+                    if dx.abs() != radius && dy.abs() != radius {
+                        continue;
+                    }
+                    let tx = x as i32 + dx;
+                    let ty = y as i32 + dy;
+                    if tx < 0 || ty < 0 || !self.dungeon.is_walkable(tx as usize, ty as usize) {
+                        continue;
+                    }
+                    let pos = (tx as usize, ty as usize);
+                    let blocked = self.stairs_position == Some(pos)
+                        || self.stairs_up_position == Some(pos)
+                        || self.entities.iter().any(|e| e.x == pos.0 && e.y == pos.1 && e.is_alive())
+                        || self.chests.iter().any(|c| c.x == pos.0 && c.y == pos.1 && !c.is_open);
+                    if !blocked {
+                        return Some(pos);
+                    }
+                }
+            }
+        }
+        None
+    }
+    
+    pub fn add_player(&mut self, player_id: String) -> Option<usize> {
+        // Get player object template from registry - must have id "player"
+        let player_obj = self.object_registry.get_object("player");
+        
+        if let Some(player_template) = player_obj {
+            // Find spawn position: next to first player if exists, otherwise first walkable tile
+            let mut spawn_x = 1;
+            let mut spawn_y = 1;
+            let mut found = false;
+            
+            // First, try to find an existing player to spawn next to
+            if let Some(first_player) = self.entities.iter().find(|e| e.controller == EntityController::Player && e.is_alive()) {
+                // Try to spawn adjacent to the first player
+                let adjacent_positions = [
+                    (first_player.x.wrapping_sub(1), first_player.y),     // Left
+                    (first_player.x + 1, first_player.y),                // Right
+                    (first_player.x, first_player.y.wrapping_sub(1)),    // Up
+                    (first_player.x, first_player.y + 1),                // Down
+                ];
+                
+                for (x, y) in adjacent_positions.iter() {
+                    if *x < self.dungeon.width && *y < self.dungeon.height {
                         if self.dungeon.tiles[*y][*x].walkable {
                             // Check if position is occupied
                             let occupied = self.entities.iter().any(|e: &Entity| e.x == *x && e.y == *y && e.is_alive());
@@ -451,6 +1784,15 @@ impl GameState {
                 }
             }
             
+            // Otherwise join at the level's spawn point (next to the stairs the party arrived by)
+            if !found {
+                if let Some((x, y)) = self.free_tile_near(self.spawn_position.0, self.spawn_position.1) {
+                    spawn_x = x;
+                    spawn_y = y;
+                    found = true;
+                }
+            }
+            
             // If we didn't find a spot next to first player, find first available walkable tile
             if !found {
                 for y in 0..self.dungeon.height {
@@ -513,8 +1855,8 @@ impl GameState {
                 })
                 .unwrap_or(150);  // Default 150% crit damage
             
-            let player = Entity::new(
-                player_id,
+            let mut player = Entity::new(
+                player_id.clone(),
                 spawn_x,
                 spawn_y,
                 player_template.id.clone(),
@@ -526,18 +1868,143 @@ impl GameState {
                 max_health,
                 EntityController::Player,
             );
+            player.set_damage_traits(player_template);
+            self.players.insert(player_id, PlayerState::new(player_template));
             
+            self.record(&player.id, ReplayAction::Join);
             let idx = self.entities.len();
             self.entities.push(player);
             Some(idx)
         } else {
             None
         }
-    }
-    
-    pub fn remove_player(&mut self, player_id: &str) {
-        // Remove player entity completely from the game
-        self.entities.retain(|e| !(e.id == player_id && e.controller == EntityController::Player));
+    }
+    
+    pub fn remove_player(&mut self, player_id: &str) {
+        // Remove player entity completely from the game
+        let count_before = self.entities.len();
+        self.entities.retain(|e| !(e.id == player_id && e.controller == EntityController::Player));
+        self.players.remove(player_id);
+        // Connections clean up more than once, only the first counts
+        if self.entities.len() < count_before {
+            self.record(player_id, ReplayAction::Leave);
+        }
+    }
+    
+    /// Wear down a player's equipped weapon (`weapons`) or armor by one use, announcing it if
+    /// it breaks (the player fights on without it)
+    fn wear_equipment(&mut self, idx: usize, weapons: bool) -> Vec<GameMessage> {
+        let Some(object_id) = self.player_state_mut(idx).wear_equipment(weapons) else {
+            return Vec::new();
+        };
+        self.update_equipment_stats(idx);
+        let name = self.object_registry.get_object(&object_id).map(|obj| obj.name.clone()).unwrap_or(object_id);
+        vec![GameMessage::system(format!("Your {} breaks!", name))]
+    }
+    
+    /// Bring the player entity's equipment bonuses in line with what it has equipped
+    fn update_equipment_stats(&mut self, idx: usize) {
+        let (attack, defense) = self.players.get(&self.entities[idx].id)
+            .map_or((0, 0), |player| player.equipment_bonus(&self.object_registry));
+        self.entities[idx].equipment_attack = attack;
+        self.entities[idx].equipment_defense = defense;
+    }
+    
+    /// Player-only state of the player entity at `idx`
+    fn player_state_mut(&mut self, idx: usize) -> &mut PlayerState {
+        self.players.entry(self.entities[idx].id.clone()).or_default()
+    }
+    
+    /// Indices of the rooms a player has explored, or with shared party vision, the rooms any
+    /// alive player has explored
+    pub fn explored_rooms(&self, player_id: &str) -> std::collections::HashSet<usize> {
+        if !self.shared_party_vision {
+            return self.players.get(player_id).map(|player| player.discovered_rooms.clone()).unwrap_or_default();
+        }
+        self.entities.iter()
+            .filter(|e| e.controller == EntityController::Player && (e.is_alive() || e.id == player_id))
+            .filter_map(|e| self.players.get(&e.id))
+            .flat_map(|player| &player.discovered_rooms)
+            .copied()
+            .collect()
+    }
+    
+    /// Announce rooms a player enters for the first time, and the stairs the first time
+    /// any player enters their room or steps on them
+    fn discover_surroundings(&mut self, entity_idx: usize) -> Vec<GameMessage> {
+        let mut messages = Vec::new();
+        let entity = &self.entities[entity_idx];
+        let (x, y) = (entity.x, entity.y);
+        let player_id = entity.id.clone();
+        
+        let room_idx = self.dungeon.rooms.iter().position(|room| room.contains(x, y));
+        if let Some(room_idx) = room_idx {
+            let newly_entered = self.player_state_mut(entity_idx).discovered_rooms.insert(room_idx);
+            if newly_entered {
+                messages.push(GameMessage::level_event(format!(
+                    "{} enters {}.",
+                    player_id,
+                    self.dungeon.rooms[room_idx].describe()
+                )));
+            }
+            if !self.vault_discovered && self.vault_room == Some(room_idx) {
+                self.vault_discovered = true;
+                messages.push(GameMessage::level_event(format!("{} found a treasure vault!", player_id)));
+            }
+        }
+        
+        if let Some((stairs_x, stairs_y)) = self.stairs_position {
+            let on_stairs = x == stairs_x && y == stairs_y;
+            let in_stairs_room = room_idx.is_some_and(|idx| self.dungeon.rooms[idx].contains(stairs_x, stairs_y));
+            if !self.stairs_discovered && (on_stairs || in_stairs_room) {
+                self.stairs_discovered = true;
+                messages.push(GameMessage::level_event(format!("{} discovered the stairs down!", player_id)));
+            }
+        }
+        
+        messages
+    }
+    
+    /// Fire the level's triggers whose region the player is standing in, once each
+    fn fire_triggers(&mut self, entity_idx: usize) -> Vec<GameMessage> {
+        let mut messages = Vec::new();
+        let (x, y) = (self.entities[entity_idx].x, self.entities[entity_idx].y);
+        let triggers: Vec<(usize, TileTrigger)> = self.level_config.iter()
+            .flat_map(|level| level.triggers.iter().flatten())
+            .cloned()
+            .enumerate()
+            .filter(|(idx, trigger)| trigger.contains(x, y) && !self.fired_triggers.contains(idx))
+            .collect();
+        
+        for (idx, trigger) in triggers {
+            self.fired_triggers.insert(idx);
+            logging::debug!("[GAME] {} fired trigger {} on level {}", self.entities[entity_idx].id, idx, self.current_level);
+            match trigger.action {
+                TriggerAction::SpawnMonsters { object_id, count } => {
+                    let center = (trigger.x + trigger.width / 2, trigger.y + trigger.height / 2);
+                    let spawned = self.spawn_monsters_near(&object_id, count, center.0, center.1);
+                    if spawned > 0 {
+                        messages.push(GameMessage::level_event("It's an ambush!".to_string()));
+                    }
+                }
+                TriggerAction::OpenDoors { object_id } => {
+                    for door in self.chests.iter_mut().filter(|c| c.object_id == object_id) {
+                        door.is_open = true;
+                    }
+                    messages.push(GameMessage::level_event("Somewhere, something opens.".to_string()));
+                }
+                TriggerAction::CloseDoors { object_id } => {
+                    let entities = &self.entities;
+                    for door in self.chests.iter_mut().filter(|c| c.object_id == object_id) {
+                        let blocked = entities.iter().any(|e| e.is_alive() && e.x == door.x && e.y == door.y);
+                        door.is_open = door.is_open && blocked;
+                    }
+                    messages.push(GameMessage::level_event("Somewhere, something slams shut.".to_string()));
+                }
+                TriggerAction::Message { text } => messages.push(GameMessage::level_event(text)),
+            }
+        }
+        messages
     }
     
     fn move_entity(&mut self, entity_idx: usize, dx: i32, dy: i32) {
@@ -545,33 +2012,15 @@ impl GameState {
             return;
         }
         
-        // Update facing direction based on horizontal movement
-        if dx > 0 {
-            // Moving right
-            self.entities[entity_idx].facing_right = true;
-        } else if dx < 0 {
-            // Moving left
-            self.entities[entity_idx].facing_right = false;
-        }
-        // If dx == 0, keep current facing direction
+        // Update facing direction based on movement
+        self.entities[entity_idx].face(dx, dy);
         
         let entity = &self.entities[entity_idx];
-        let new_x = entity.x as i32 + dx;
-        let new_y = entity.y as i32 + dy;
         
-        if new_x >= 0 && new_y >= 0 {
-            let new_x = new_x as usize;
-            let new_y = new_y as usize;
-            
-            // Check bounds
-            if new_x >= self.dungeon.width || new_y >= self.dungeon.height {
-                return;
-            }
-            
-            // Check if tile is walkable
-            if !self.dungeon.is_walkable(new_x, new_y) {
-                return;
-            }
+        // Check range, bounds and walkability
+        if self.dungeon.validate_move(entity, dx, dy) {
+            let new_x = (entity.x as i32 + dx) as usize;
+            let new_y = (entity.y as i32 + dy) as usize;
             
             // Check if another entity is at that position (but allow attacking enemies)
             let entity_id = self.entities[entity_idx].id.clone();
@@ -587,3 +2036,1064 @@ impl GameState {
     }
 }
 
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::api::create_default_config;
+    use crate::config::GameConfig;
+    use crate::dungeon::Room;
+    use crate::dungeon::tests::dungeon_from;
+    use crate::entity::Direction;
+    
+    /// A level for tests: a few rooms with at most one orc each and a chest, with the `extra`
+    /// TOML fields added or overriding those
+    pub(crate) fn level(extra: &str) -> LevelConfig {
+        let mut fields: toml::Table = toml::from_str(
+            "level_number = 1\nmin_rooms = 4\nmax_rooms = 6\nmin_monsters_per_room = 0\nmax_monsters_per_room = 1\nchest_count = 1\nallowed_monsters = [\"orc\"]"
+        ).unwrap();
+        fields.extend(toml::from_str::<toml::Table>(extra).unwrap());
+        fields.try_into().unwrap()
+    }
+    
+    /// A game of the default config playing `level`, with player "p1" joined
+    pub(crate) fn game_on(level: LevelConfig) -> GameState {
+        game_with(create_default_config(), level)
+    }
+    
+    /// A game of `config` playing `level`, with player "p1" joined
+    pub(crate) fn game_with(mut config: GameConfig, level: LevelConfig) -> GameState {
+        config.levels = vec![level];
+        let mut game = GameState::from_config(&config);
+        game.add_player("p1".to_string());
+        game
+    }
+    
+    /// Clear the map down to `rows` (see `dungeon_from`), with the players at `players` and
+    /// a monster of `object_id` at each of `monsters`
+    pub(crate) fn stage(game: &mut GameState, rows: &[&str], players: &[(usize, usize)], monsters: &[(&str, usize, usize)]) {
+        game.dungeon = dungeon_from(rows);
+        game.consumables.clear();
+        game.chests.clear();
+        game.stairs_position = None;
+        game.stairs_up_position = None;
+        game.entities.retain(|e| e.controller == EntityController::Player);
+        game.level_has_boss = false;
+        for (entity, &(x, y)) in game.entities.iter_mut().zip(players) {
+            (entity.x, entity.y) = (x, y);
+        }
+        for (i, &(object_id, x, y)) in monsters.iter().enumerate() {
+            let template = game.object_registry.get_object(object_id).unwrap().clone();
+            let monster = MapGenerator::create_monster(&template, format!("monster_{}", i), (x, y), &game.difficulty_scaling, 1, 1, &mut game.rng);
+            assert!(game.try_spawn_entity(monster));
+        }
+    }
+    
+    pub(crate) fn command(action: &str) -> PlayerCommand {
+        serde_json::from_value(serde_json::json!({ "action": action })).unwrap()
+    }
+    
+    pub(crate) fn directed(action: &str, direction: &str) -> PlayerCommand {
+        serde_json::from_value(serde_json::json!({ "action": action, "direction": direction })).unwrap()
+    }
+    
+    /// A confirmation (or other flag) sent without an action, e.g. `confirm_stairs` or `new_game`
+    pub(crate) fn flagged(flag: &str) -> PlayerCommand {
+        serde_json::from_value(serde_json::json!({ "action": "", flag: true })).unwrap()
+    }
+    
+    fn health(game: &GameState, id: &str) -> u32 {
+        game.entities.iter().find(|e| e.id == id).unwrap().current_health
+    }
+    
+    fn monster_ids(game: &GameState) -> Vec<String> {
+        game.entities.iter()
+            .filter(|e| e.controller == EntityController::AI)
+            .map(|e| e.id.clone())
+            .collect()
+    }
+    
+    #[test]
+    fn survival_monsters_keep_appearing_with_unique_ids() {
+        let mut game = game_on(level("survival_mode = true\nsurvival_spawn_percent = 100"));
+        game.max_entities = 100;
+        let before = monster_ids(&game).len();
+        
+        for _ in 0..30 {
+            // Several spawns a turn as well as across turns
+            game.current_turn += 1;
+            assert!(game.spawn_survival_monster().is_some());
+            assert!(game.spawn_survival_monster().is_some());
+        }
+        
+        let ids = monster_ids(&game);
+        assert_eq!(ids.len(), before + 60);
+        let unique: std::collections::HashSet<&String> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len());
+    }
+    
+    #[test]
+    fn an_explicit_attack_hits_the_adjacent_monster_without_moving() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#...#", "#####"], &[(1, 1)], &[("orc", 1, 2)]);
+        let turn = game.current_turn;
+        
+        game.handle_command(&directed("attack", "down"), "p1");
+        assert!(health(&game, "monster_0") < game.entities[1].max_health);
+        assert_eq!((game.entities[0].x, game.entities[0].y), (1, 1));
+        assert_eq!(game.current_turn, turn + 1);
+        
+        // Target coordinates work as well as a direction
+        let before = health(&game, "monster_0");
+        let at_orc = serde_json::from_value(serde_json::json!({ "action": "attack", "target_x": 1, "target_y": 2 })).unwrap();
+        game.handle_command(&at_orc, "p1");
+        assert!(health(&game, "monster_0") < before);
+    }
+    
+    #[test]
+    fn an_explicit_attack_at_nothing_keeps_the_turn() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 3, 1)]);
+        let turn = game.current_turn;
+        
+        let (messages, _, _) = game.handle_command(&directed("attack", "right"), "p1");
+        assert_eq!(messages[0].text, "Nothing to attack there.");
+        // A monster out of reach can't be hit either
+        let at_orc = serde_json::from_value(serde_json::json!({ "action": "attack", "target_x": 3, "target_y": 1 })).unwrap();
+        game.handle_command(&at_orc, "p1");
+        assert_eq!(game.current_turn, turn);
+        assert_eq!((game.entities[0].x, game.entities[0].y), (1, 1));
+        assert_eq!(health(&game, "monster_0"), game.entities[1].max_health);
+    }
+    
+    /// The default config plus a one-hit "slime" that splits into two "small_slime"s
+    fn config_with_slime() -> GameConfig {
+        let mut config = create_default_config();
+        let mut slime = crate::game_object::GameObject::new("slime".to_string(), "Slime".to_string(), "character".to_string(), true, 0, 0)
+            .with_health(1);
+        slime.monster = Some(true);
+        slime.on_death_spawn = Some(crate::game_object::object::OnDeathSpawn { object_id: "small_slime".to_string(), count: 2 });
+        let mut small_slime = crate::game_object::GameObject::new("small_slime".to_string(), "Small Slime".to_string(), "character".to_string(), true, 0, 0);
+        small_slime.monster = Some(true);
+        config.game_objects.extend([slime, small_slime]);
+        config
+    }
+    
+    fn small_slimes(game: &GameState) -> Vec<&Entity> {
+        game.entities.iter().filter(|e| e.object_id == "small_slime" && e.is_alive()).collect()
+    }
+    
+    #[test]
+    fn a_killed_slime_splits_in_two() {
+        let mut game = game_with(config_with_slime(), level(""));
+        stage(&mut game, &["######", "#....#", "#....#", "######"], &[(1, 1)], &[("slime", 2, 1)]);
+        
+        game.handle_command(&directed("attack", "right"), "p1");
+        assert!(small_slimes(&game).is_empty());
+        
+        // The children appear around it when the body is cleared, on the next command
+        game.handle_command(&command("move_down"), "p1");
+        let children = small_slimes(&game);
+        assert_eq!(children.len(), 2);
+        assert!(children.iter().all(|child| child.x.abs_diff(2) <= 2 && child.y.abs_diff(1) <= 2));
+        assert!(!game.entities.iter().any(|e| e.id == "monster_0"));
+    }
+    
+    #[test]
+    fn a_split_respects_the_monster_cap() {
+        let mut game = game_with(config_with_slime(), level(""));
+        stage(&mut game, &["######", "#....#", "#....#", "######"], &[(1, 1)], &[("slime", 2, 1)]);
+        game.max_entities = 1;
+        
+        game.handle_command(&directed("attack", "right"), "p1");
+        game.handle_command(&command("move_down"), "p1");
+        assert_eq!(small_slimes(&game).len(), 1);
+    }
+    
+    fn throw_at(item: &str, x: usize, y: usize) -> PlayerCommand {
+        serde_json::from_value(serde_json::json!({ "action": "throw_item", "item": item, "target_x": x, "target_y": y })).unwrap()
+    }
+    
+    fn give(game: &mut GameState, player_id: &str, item: &str) {
+        let template = game.object_registry.get_object(item).unwrap().clone();
+        game.players.entry(player_id.to_string()).or_default().add_to_inventory(&template);
+    }
+    
+    #[test]
+    fn a_thrown_potion_heals_a_distant_ally() {
+        let mut game = game_on(level(""));
+        game.add_player("p2".to_string());
+        stage(&mut game, &["######", "#....#", "######"], &[(1, 1), (4, 1)], &[]);
+        game.entities[1].current_health = 10;
+        give(&mut game, "p1", "health_potion");
+        
+        game.handle_command(&throw_at("health_potion", 4, 1), "p1");
+        assert_eq!(health(&game, "p2"), 30);
+        assert_eq!(game.players["p1"].inventory_count("health_potion"), 0);
+    }
+    
+    #[test]
+    fn a_thrown_bomb_hurts_a_monster_but_not_through_a_wall() {
+        let mut config = create_default_config();
+        let mut bomb = crate::game_object::GameObject::new("bomb".to_string(), "Bomb".to_string(), "consumable".to_string(), true, 0, 0);
+        bomb.damage = Some(15);
+        config.game_objects.push(bomb);
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["######", "#..#.#", "######"], &[(1, 1)], &[("orc", 2, 1), ("orc", 4, 1)]);
+        give(&mut game, "p1", "bomb");
+        
+        let (messages, _, _) = game.handle_command(&throw_at("bomb", 4, 1), "p1");
+        assert_eq!(messages[0].text, "You can't see a clear path to throw there.");
+        assert_eq!(game.players["p1"].inventory_count("bomb"), 1);
+        
+        let max_health = game.entities[1].max_health;
+        game.handle_command(&throw_at("bomb", 2, 1), "p1");
+        assert_eq!(health(&game, "monster_0"), max_health - 15);
+        assert_eq!(game.players["p1"].inventory_count("bomb"), 0);
+    }
+    
+    /// A game in which p1, on 1 health, steps up to an orc on a level with the `level_extra` fields
+    fn fatal_step(permadeath: bool, level_extra: &str) -> GameState {
+        let mut config = create_default_config();
+        config.permadeath = permadeath;
+        let mut game = game_with(config, level(level_extra));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 3, 1)]);
+        game.entities[0].current_health = 1;
+        game.handle_command(&command("move_right"), "p1");
+        game
+    }
+    
+    #[test]
+    fn a_permadeath_wipe_ends_the_run_until_a_new_game() {
+        let mut game = fatal_step(true, "");
+        assert_eq!(game.status, GameStatus::GameOver);
+        assert_eq!(game.scoreboard.len(), 1);
+        assert!(crate::api::game_state_to_update(&game, Some("p1")).game_over);
+        
+        // Nothing but a new game is accepted
+        let turn = game.current_turn;
+        game.handle_command(&command("move_left"), "p1");
+        assert_eq!(game.current_turn, turn);
+        assert_eq!(game.status, GameStatus::GameOver);
+        
+        let new_game = flagged("new_game");
+        game.handle_command(&new_game, "p1");
+        assert_eq!(game.status, GameStatus::Playing);
+        assert_eq!(game.current_level, 1);
+        assert!(game.entities[0].is_alive());
+        assert_eq!(game.scoreboard.len(), 1);
+    }
+    
+    #[test]
+    fn a_wipe_without_permadeath_is_not_game_over() {
+        // Survival levels follow the configured permadeath too
+        for level_extra in ["", "survival_mode = true"] {
+            let game = fatal_step(false, level_extra);
+            assert!(!game.entities[0].is_alive());
+            assert_eq!(game.status, GameStatus::Playing);
+            assert!(game.scoreboard.is_empty());
+        }
+    }
+    
+    /// The default config plus an "iron_door" locked with an "iron_key", which orcs carry
+    fn config_with_locked_door() -> GameConfig {
+        let mut config = create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.drops_on_death = Some("iron_key".to_string());
+        }
+        config.game_objects.push(crate::game_object::GameObject::new("iron_key".to_string(), "Iron Key".to_string(), "key".to_string(), true, 0, 0));
+        let mut door = crate::game_object::GameObject::new("iron_door".to_string(), "Iron Door".to_string(), "door".to_string(), false, 0, 0);
+        door.requires_key = Some("iron_key".to_string());
+        config.game_objects.push(door);
+        config
+    }
+    
+    fn door_at(x: usize, y: usize) -> Chest {
+        Chest { id: "door_0".to_string(), x, y, object_id: "iron_door".to_string(), is_open: false, is_active: false }
+    }
+    
+    #[test]
+    fn a_locked_door_stays_shut_without_its_key() {
+        let mut game = game_with(config_with_locked_door(), level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        game.chests.push(door_at(2, 1));
+        let turn = game.current_turn;
+        
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        assert_eq!(messages[0].text, "The Iron Door is locked. You need the Iron Key.");
+        assert!(!game.chests[0].is_open);
+        assert_eq!((game.entities[0].x, game.entities[0].y), (1, 1));
+        assert_eq!(game.current_turn, turn);
+    }
+    
+    #[test]
+    fn the_key_a_monster_drops_opens_the_door_and_is_used_up() {
+        let mut game = game_with(config_with_locked_door(), level(""));
+        stage(&mut game, &["######", "#....#", "######"], &[(1, 1)], &[("orc", 2, 1)]);
+        game.entities[1].current_health = 1;
+        game.chests.push(door_at(3, 1));
+        
+        game.handle_command(&directed("attack", "right"), "p1");
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!(game.players["p1"].inventory_count("iron_key"), 1);
+        
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(messages.iter().any(|msg| msg.text == "You unlock the Iron Door with the Iron Key."));
+        assert!(game.chests[0].is_open);
+        assert_eq!(game.players["p1"].inventory_count("iron_key"), 0);
+        
+        // Opened, it lets the player through (doors needn't be marked interactable)
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!((game.entities[0].x, game.entities[0].y), (3, 1));
+    }
+    
+    #[test]
+    fn picking_up_an_item_reports_how_many_are_carried() {
+        let mut game = game_with(config_with_locked_door(), level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        for x in [2, 3] {
+            game.consumables.push(Consumable { id: format!("key_{}", x), x, y: 1, object_id: "iron_key".to_string(), dropped_turns_ago: None });
+        }
+        
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(messages.iter().any(|m| m.text == "Picked up Iron Key (1)"));
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(messages.iter().any(|m| m.text == "Picked up Iron Key (2)"));
+    }
+    
+    #[test]
+    fn a_confused_player_stumbles_off_the_commanded_direction() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#...#", "#...#", "#####"], &[(2, 2)], &[]);
+        game.reseed(1);
+        game.entities[0].add_status(StatusEffectKind::Confused, 20);
+        
+        let mut landed = std::collections::HashSet::new();
+        let mut stumbled = 0;
+        for _ in 0..10 {
+            (game.entities[0].x, game.entities[0].y) = (2, 2);
+            let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+            stumbled += messages.iter().filter(|msg| msg.text == "p1 stumbles around in confusion!").count();
+            landed.insert((game.entities[0].x, game.entities[0].y));
+        }
+        assert!(stumbled > 0);
+        assert!(landed.len() > 1);
+        assert!(landed.iter().all(|&(x, y)| x.abs_diff(2) + y.abs_diff(2) == 1));
+    }
+    
+    #[test]
+    fn a_lever_opens_a_remote_door_and_shuts_it_again() {
+        let mut config = create_default_config();
+        let mut lever = crate::game_object::GameObject::new("lever".to_string(), "Lever".to_string(), "lever".to_string(), false, 0, 0);
+        lever.interactable = Some(crate::game_object::InteractableData {
+            effect: Some(crate::game_object::InteractionEffect::OpenDoors { object_id: "gate".to_string() }),
+            toggle: Some(true),
+        });
+        let mut gate = crate::game_object::GameObject::new("gate".to_string(), "Gate".to_string(), "door".to_string(), false, 0, 0);
+        gate.interactable = Some(crate::game_object::InteractableData::default());
+        config.game_objects.extend([lever, gate]);
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["#######", "#.....#", "#.....#", "#######"], &[(1, 2)], &[]);
+        game.chests.push(Chest { id: "lever_0".to_string(), x: 1, y: 1, object_id: "lever".to_string(), is_open: false, is_active: false });
+        game.chests.push(Chest { id: "gate_0".to_string(), x: 4, y: 2, object_id: "gate".to_string(), is_open: false, is_active: false });
+        
+        let (messages, _, _) = game.handle_command(&command("move_up"), "p1");
+        assert!(messages.iter().any(|msg| msg.text == "You use the Lever. Somewhere, something opens."));
+        assert!(game.chests[0].is_active && !game.chests[0].is_open);  // The lever stays in the way
+        assert!(game.chests[1].is_open);
+        assert_eq!((game.entities[0].x, game.entities[0].y), (1, 2));
+        
+        // Switched back off, the gate shuts again
+        let (messages, _, _) = game.handle_command(&command("move_up"), "p1");
+        assert!(messages.iter().any(|msg| msg.text == "You use the Lever. Somewhere, something closes."));
+        assert!(!game.chests[0].is_active && !game.chests[1].is_open);
+        
+        // Then on again, the gate can be walked through
+        game.handle_command(&command("move_up"), "p1");
+        for _ in 0..4 {
+            game.handle_command(&command("move_right"), "p1");
+        }
+        assert_eq!((game.entities[0].x, game.entities[0].y), (5, 2));
+    }
+    
+    #[test]
+    fn shared_vision_shows_a_distant_teammate_the_rooms_a_player_explores() {
+        for shared in [false, true] {
+            let mut game = game_on(level(""));
+            game.add_player("p2".to_string());
+            game.shared_party_vision = shared;
+            stage(&mut game, &["###########", "#.........#", "###########"], &[(4, 1), (8, 1)], &[]);
+            game.dungeon.rooms = vec![
+                Room { x: 1, y: 1, width: 3, height: 1 },
+                Room { x: 7, y: 1, width: 3, height: 1 },
+            ];
+            
+            game.handle_command(&command("move_left"), "p1");
+            assert!(game.explored_rooms("p1").contains(&0));
+            assert_eq!(game.explored_rooms("p2").contains(&0), shared);
+        }
+    }
+    
+    #[test]
+    fn auto_move_reaches_its_target_over_several_turns() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#######", "#.....#", "#####.#", "#.....#", "#######"], &[(1, 1)], &[]);
+        let start: PlayerCommand = serde_json::from_value(serde_json::json!({ "action": "auto_move_to", "target_x": 1, "target_y": 3 })).unwrap();
+        
+        game.handle_command(&start, "p1");
+        let mut turns = 1;
+        while (game.entities[0].x, game.entities[0].y) != (1, 3) && turns < 20 {
+            game.handle_command(&command("auto_move_to"), "p1");
+            turns += 1;
+        }
+        assert_eq!((game.entities[0].x, game.entities[0].y), (1, 3));
+        assert_eq!(turns, 10);  // The shortest way round the wall
+        assert!(game.players["p1"].auto_move.is_none());
+    }
+    
+    #[test]
+    fn auto_move_stops_on_damage_or_a_monster_coming_into_view() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["##################", "#................#", "##################"], &[(1, 1)], &[("orc", 16, 1)]);
+        let start: PlayerCommand = serde_json::from_value(serde_json::json!({ "action": "auto_move_to", "target_x": 10, "target_y": 1 })).unwrap();
+        
+        game.handle_command(&start, "p1");
+        game.entities[0].current_health -= 1;
+        let (messages, _, _) = game.handle_command(&command("auto_move_to"), "p1");
+        assert_eq!(messages[0].text, "p1 stops moving after taking damage.");
+        assert_eq!((game.entities[0].x, game.entities[0].y), (2, 1));
+        
+        game.handle_command(&start, "p1");
+        (game.entities[1].x, game.entities[1].y) = (7, 1);
+        let (messages, _, _) = game.handle_command(&command("auto_move_to"), "p1");
+        assert_eq!(messages[0].text, "p1 stops moving: a monster comes into view!");
+        assert_eq!((game.entities[0].x, game.entities[0].y), (3, 1));
+        assert!(game.players["p1"].auto_move.is_none());
+    }
+    
+    #[test]
+    fn a_quiet_level_sends_a_distant_monster_toward_the_players() {
+        for pressure in [false, true] {
+            let mut game = game_on(level(if pressure { "pressure_turns = 3" } else { "" }));
+            stage(&mut game, &["######################", "#....................#", "######################"], &[(1, 1)], &[("orc", 18, 1)]);
+            let mut heard = false;
+            for turn in 0..3 {
+                let (messages, _, _) = game.handle_command(&command(if turn % 2 == 0 { "move_right" } else { "move_left" }), "p1");
+                heard |= messages.iter().any(|m| m.text == "You hear something moving in the distance...");
+            }
+            
+            assert_eq!(heard, pressure);
+            // The orc heads for the player's corridor tile (there are no rooms)
+            assert_eq!(game.entities[1].last_seen_player, pressure.then_some((2, 1)));
+        }
+    }
+    
+    #[test]
+    fn a_level_that_requires_a_clear_keeps_the_stairs_shut_while_monsters_live() {
+        let mut game = game_on(level("require_clear = true"));
+        stage(&mut game, &["########", "#......#", "########"], &[(1, 1)], &[("orc", 6, 1)]);
+        game.stairs_position = Some((1, 1));
+        let confirm = flagged("confirm_stairs");
+        
+        let (messages, level_complete, _) = game.handle_command(&confirm, "p1");
+        assert!(!level_complete);
+        assert_eq!(messages.iter().map(|m| m.text.as_str()).collect::<Vec<_>>(), vec!["Defeat all enemies first. (1 remaining)"]);
+        assert_eq!(game.monsters_remaining(), Some(1));
+        assert_eq!(game.current_level, 1);
+        
+        game.entities[1].current_health = 0;
+        assert_eq!(game.monsters_remaining(), Some(0));
+        let (_, level_complete, _) = game.handle_command(&confirm, "p1");
+        assert!(level_complete);
+        assert_eq!(game.current_level, 2);
+    }
+    
+    #[test]
+    fn picking_up_a_rare_item_says_so() {
+        let mut config = create_default_config();
+        let mut dagger = crate::game_object::GameObject::new("jeweled_dagger".to_string(), "Jeweled Dagger".to_string(), "consumable".to_string(), true, 0, 0);
+        dagger.throwable = Some(true);
+        dagger.rarity = Some("rare".to_string());
+        config.game_objects.push(dagger);
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        game.consumables.push(Consumable { id: "dagger_0".to_string(), x: 2, y: 1, object_id: "jeweled_dagger".to_string(), dropped_turns_ago: None });
+        
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        let pickup = messages.iter().find(|m| m.text.starts_with("Picked up")).unwrap();
+        assert_eq!(pickup.text, "Picked up Jeweled Dagger (1) [rare]");
+        assert_eq!(pickup.rarity.as_deref(), Some("rare"));
+    }
+    
+    #[test]
+    fn attacking_a_wall_is_refused_or_wastes_the_turn() {
+        for allowed in [false, true] {
+            let mut game = game_on(level(""));
+            game.wall_attacks_allowed = allowed;
+            stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+            
+            let (messages, _, _) = game.handle_command(&directed("attack", "up"), "p1");
+            let expected = if allowed { "Your attack glances harmlessly off the wall." } else { "You can't attack a wall." };
+            assert!(messages.iter().any(|m| m.text == expected));
+            assert_eq!(game.current_turn, if allowed { 2 } else { 1 });
+            assert_eq!(game.entities[0].facing, Direction::Up);
+        }
+    }
+    
+    #[test]
+    fn returning_to_a_level_restores_what_was_explored() {
+        let mut game = game_on(level(""));
+        game.players.get_mut("p1").unwrap().discovered_rooms.extend([0, 1]);
+        let explored = game.explored_rooms("p1");
+        let p1 = |game: &GameState| game.entities.iter().position(|e| e.id == "p1").unwrap();
+        let idx = p1(&game);
+        (game.entities[idx].x, game.entities[idx].y) = game.stairs_position.unwrap();
+        game.confirm_stairs("p1");
+        assert_eq!(game.current_level, 2);
+        assert!(game.explored_rooms("p1").len() <= 1);  // At most the room the party arrived in
+        
+        // Climb straight back up from where the party arrived
+        let idx = p1(&game);
+        game.stairs_up_position = Some((game.entities[idx].x, game.entities[idx].y));
+        game.confirm_stairs_up("p1");
+        assert_eq!(game.current_level, 1);
+        assert_eq!(game.explored_rooms("p1"), explored);
+    }
+    
+    #[test]
+    fn a_killed_monster_leaves_its_gear_to_loot() {
+        let mut config = create_default_config();
+        let mut axe = crate::game_object::GameObject::new("orc_axe".to_string(), "Orc Axe".to_string(), "equipment".to_string(), true, 0, 0);
+        axe.attack = Some(4);
+        config.game_objects.push(axe);
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.equipment = Some(vec!["orc_axe".to_string()]);
+            obj.equipment_drop_percent = Some(100);
+        }
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 2, 1)]);
+        game.entities[1].current_health = 1;
+        
+        game.handle_command(&directed("attack", "right"), "p1");
+        let (axe_x, axe_y) = game.consumables.iter().find(|c| c.object_id == "orc_axe").map(|c| (c.x, c.y)).expect("the axe drops");
+        assert_eq!(axe_y, 1);  // On the orc's tile, or beside it if a potion dropped there first
+        
+        // Step onto it from the side
+        let (from_x, action) = if axe_x == 3 { (2, "move_right") } else { (axe_x + 1, "move_left") };
+        game.entities[0].x = from_x;
+        game.handle_command(&command(action), "p1");
+        assert_eq!(game.players["p1"].weapon.as_ref().map(|weapon| weapon.object_id.as_str()), Some("orc_axe"));
+    }
+    
+    #[test]
+    fn the_lobby_waits_for_enough_ready_players() {
+        let mut game = game_on(level(""));
+        game.open_lobby(2);
+        let start = (game.entities[0].x, game.entities[0].y);
+        assert!(game.entities.iter().all(|e| e.controller == EntityController::Player));
+        
+        // Moving does nothing and one ready player isn't enough
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!((game.entities[0].x, game.entities[0].y), start);
+        let (messages, level_complete, _) = game.handle_command(&command("ready"), "p1");
+        assert!(!level_complete);
+        assert_eq!(messages[0].text, "p1 is ready. (1/1 ready, 2 needed to start)");
+        
+        game.add_player("p2".to_string());
+        let lobby = game.lobby_status().unwrap();
+        assert_eq!((lobby.connected, lobby.ready, lobby.min_players), (2, 1, 2));
+        assert_eq!(game.current_turn, 1);
+    }
+    
+    #[test]
+    fn the_game_starts_once_everyone_is_ready() {
+        let mut game = game_on(level("min_monsters_per_room = 1"));
+        game.add_player("p2".to_string());
+        game.open_lobby(2);
+        game.handle_command(&command("ready"), "p1");
+        
+        let (messages, level_complete, _) = game.handle_command(&command("ready"), "p2");
+        assert!(level_complete);
+        assert_eq!(messages[0].text, "Everyone is ready. The adventure begins!");
+        assert_eq!(game.status, GameStatus::Playing);
+        assert!(game.lobby_status().is_none());
+        assert!(game.entities.iter().any(|e| e.controller == EntityController::AI));
+    }
+    
+    #[test]
+    fn monsters_can_be_made_to_route_around_the_stairs() {
+        for avoid in [false, true] {
+            let mut game = game_on(level(""));
+            game.monsters_avoid_stairs = avoid;
+            stage(&mut game, &["#######", "#.....#", "#.....#", "#######"], &[(5, 1)], &[("orc", 1, 1)]);
+            game.stairs_position = Some((3, 1));
+            
+            let mut stepped_on_stairs = false;
+            for turn in 0..4 {
+                game.handle_command(&command(if turn % 2 == 0 { "move_down" } else { "move_up" }), "p1");
+                stepped_on_stairs |= (game.entities[1].x, game.entities[1].y) == (3, 1);
+            }
+            assert_eq!(stepped_on_stairs, !avoid);
+            assert!(game.entities[1].x >= 4, "the orc still reaches the player");
+        }
+    }
+    
+    #[test]
+    fn confirming_the_stairs_needs_standing_on_them() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        game.stairs_position = Some((3, 1));
+        let confirm = flagged("confirm_stairs");
+        
+        let (messages, level_complete, _) = game.handle_command(&confirm, "p1");
+        assert!(!level_complete);
+        assert_eq!(messages[0].text, "You need to stand on the stairs down to descend.");
+        assert!(!game.players["p1"].confirmed_stairs);
+        assert_eq!(game.current_level, 1);
+    }
+    
+    #[test]
+    fn a_restart_only_counts_once_the_party_has_fallen() {
+        let mut game = game_on(level(""));
+        let confirm = flagged("confirm_restart");
+        
+        let (messages, _, restart_confirmed) = game.handle_command(&confirm, "p1");
+        assert!(!restart_confirmed);
+        assert_eq!(messages[0].text, "You can only restart once the whole party has fallen.");
+        assert!(!game.players["p1"].confirmed_restart);
+        
+        game.entities.iter_mut().find(|e| e.id == "p1").unwrap().current_health = 0;
+        let (msg, restarted) = game.confirm_restart("p1");
+        assert_eq!(msg.map(|msg| msg.text), Some("Level restarted!".to_string()));
+        assert!(restarted);
+    }
+    
+    #[test]
+    fn entering_a_trigger_region_spawns_its_monsters_once() {
+        // Passive orcs, so the player can walk out and back in
+        let mut config = create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.hostile_on_sight = Some(false);
+        }
+        let level = level("triggers = [{ x = 5, y = 1, width = 2, height = 3, action = { type = \"spawn_monsters\", object_id = \"orc\", count = 2 } }]");
+        let mut game = game_with(config, level);
+        stage(&mut game, &["##########", "#........#", "#........#", "#........#", "##########"], &[(1, 2)], &[]);
+        let monsters = |game: &GameState| game.entities.iter().filter(|e| e.controller == EntityController::AI).count();
+        
+        for _ in 0..3 {
+            game.handle_command(&command("move_right"), "p1");
+        }
+        assert_eq!(monsters(&game), 0);
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        assert_eq!(monsters(&game), 2);
+        assert!(messages.iter().any(|m| m.text == "It's an ambush!"));
+        
+        // Out of the way of the way back in
+        for (orc, y) in game.entities.iter_mut().filter(|e| e.controller == EntityController::AI).zip([1, 3]) {
+            (orc.x, orc.y) = (8, y);
+        }
+        game.handle_command(&command("move_left"), "p1");
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!((game.entities[0].x, game.entities[0].y), (5, 2));
+        assert_eq!(monsters(&game), 2);
+    }
+    
+    #[test]
+    fn spawns_beyond_the_monster_cap_are_rejected() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["######", "#....#", "######"], &[(1, 1)], &[("orc", 2, 1), ("orc", 3, 1)]);
+        game.max_entities = 2;
+        let orc = |id: &str| {
+            let template = game.object_registry.get_object("orc").unwrap();
+            MapGenerator::create_monster(template, id.to_string(), (4, 1), &DifficultyScaling::default(), 1, 1, &mut StdRng::seed_from_u64(1))
+        };
+        let (extra, replacement) = (orc("extra"), orc("replacement"));
+        
+        assert!(!game.try_spawn_entity(extra));
+        assert_eq!(game.entities.len(), 3);
+        
+        // A dead monster frees its place under the cap
+        game.entities[1].current_health = 0;
+        assert!(game.try_spawn_entity(replacement));
+    }
+    
+    #[test]
+    fn generated_levels_respect_the_monster_cap() {
+        let mut config = create_default_config();
+        config.max_entities = Some(3);
+        let game = game_with(config, level("min_monsters_per_room = 3\nmax_monsters_per_room = 3"));
+        assert_eq!(game.entities.iter().filter(|e| e.controller == EntityController::AI).count(), 3);
+    }
+    
+    #[test]
+    fn descending_heals_the_party_by_the_rest_percent() {
+        for (rest_heal_percent, healed) in [(0, 0), (50, 5), (100, 8)] {
+            let mut config = create_default_config();
+            config.rest_heal_percent = rest_heal_percent;
+            let mut game = game_with(config, level(""));
+            stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+            game.stairs_position = Some((1, 1));
+            let player = game.entities.iter_mut().find(|e| e.id == "p1").unwrap();
+            player.max_health = 10;
+            player.current_health = 2;
+            let confirm = flagged("confirm_stairs");
+            
+            let (messages, level_complete, _) = game.handle_command(&confirm, "p1");
+            assert!(level_complete);
+            assert_eq!(game.current_level, 2);
+            assert_eq!(game.entities.iter().find(|e| e.id == "p1").unwrap().current_health, 2 + healed);
+            let rest = messages.iter().find(|m| m.attacker.as_deref() == Some("Resting"));
+            assert_eq!(rest.is_some(), rest_heal_percent > 0);  // No message without a rest
+        }
+    }
+    
+    #[test]
+    fn monsters_route_around_closed_chests_and_opened_ones_if_asked() {
+        // monsters_avoid_chests only decides about opened chests, closed ones always block
+        for (is_open, avoid, blocked) in [(false, false, true), (false, true, true), (true, false, false), (true, true, true)] {
+            let mut config = create_default_config();
+            config.game_objects.push(crate::game_object::GameObject::new("chest".to_string(), "Chest".to_string(), "chest".to_string(), false, 0, 0));
+            config.monsters_avoid_chests = avoid;
+            let mut game = game_with(config, level(""));
+            stage(&mut game, &["#######", "#.....#", "#.....#", "#######"], &[(5, 1)], &[("orc", 1, 1)]);
+            game.chests.push(Chest { id: "chest_0".to_string(), x: 3, y: 1, object_id: "chest".to_string(), is_open, is_active: false });
+            
+            let mut stepped_on_chest = false;
+            for turn in 0..4 {
+                game.handle_command(&command(if turn % 2 == 0 { "move_down" } else { "move_up" }), "p1");
+                stepped_on_chest |= (game.entities[1].x, game.entities[1].y) == (3, 1);
+            }
+            assert_eq!(stepped_on_chest, !blocked, "open: {}, avoid: {}", is_open, avoid);
+            assert!(game.entities[1].x >= 4, "the orc still reaches the player");
+        }
+    }
+    
+    #[test]
+    fn taunted_monsters_chase_the_taunter_over_a_closer_player() {
+        let mut config = create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "player") {
+            obj.taunt_radius = Some(5);
+        }
+        let mut game = game_with(config, level(""));
+        game.add_player("p2".to_string());
+        stage(&mut game, &["##########", "#........#", "##########"], &[(1, 1), (6, 1)], &[("orc", 5, 1)]);
+        
+        let (messages, _, _) = game.handle_command(&command("taunt"), "p1");
+        assert_eq!(messages[0].text, "p1 taunts 1 monster(s)!");
+        game.handle_command(&command("move_right"), "p2");
+        
+        // The orc walks off toward p1 instead of following p2 next door
+        let orc = &game.entities[2];
+        assert_eq!((orc.x, orc.y), (4, 1));
+        assert_eq!(orc.forced_target.as_deref(), Some("p1"));
+        assert_eq!(health(&game, "p2"), game.entities[1].max_health);
+    }
+    
+    #[test]
+    fn dropped_items_fade_after_their_despawn_turns() {
+        let mut config = create_default_config();
+        let mut herb = crate::game_object::GameObject::new("herb".to_string(), "Herb".to_string(), "consumable".to_string(), true, 0, 0);
+        herb.despawn_turns = Some(2);
+        config.game_objects.push(herb);
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["#######", "#.....#", "#.....#", "#######"], &[(1, 1)], &[]);
+        let herb_at = |x, dropped_turns_ago| Consumable { id: format!("herb_{}", x), x, y: 1, object_id: "herb".to_string(), dropped_turns_ago };
+        game.consumables = vec![herb_at(4, Some(0)), herb_at(5, None)];  // Dropped by a monster, placed with the level
+        
+        for turn in 1..=3 {
+            let (messages, _, _) = game.handle_command(&command(if turn % 2 == 1 { "move_down" } else { "move_up" }), "p1");
+            let faded = messages.iter().any(|m| m.text == "The Herb fades away.");
+            assert_eq!(faded, turn == 3);
+            assert_eq!(game.consumables.len(), if turn < 3 { 2 } else { 1 });
+        }
+        assert_eq!(game.consumables[0].id, "herb_5");  // Level items never fade
+    }
+    
+    #[test]
+    fn an_admin_new_game_resets_the_run_but_keeps_the_players() {
+        let mut game = game_on(level(""));
+        game.add_player("p2".to_string());
+        let new_game = flagged("new_game");
+        game.change_level(2);
+        assert!(game.level_cache.contains_key(&1));
+        game.spawned_count = 5;
+        game.entities.iter_mut().find(|e| e.id == "p1").unwrap().current_health = 1;
+        let seed = game.seed;
+        
+        // Ignored without admin commands
+        game.handle_command(&new_game, "p1");
+        assert_eq!(game.current_level, 2);
+        
+        game.admin_commands = true;
+        let (messages, level_complete, _) = game.handle_command(&new_game, "p1");
+        assert!(level_complete);
+        assert_eq!(messages[0].text, "A new run begins!");
+        assert_eq!(game.current_level, 1);
+        assert!(game.level_cache.is_empty());
+        assert_eq!(game.spawned_count, 0);
+        assert_ne!(game.seed, seed);
+        assert!(game.players.contains_key("p1") && game.players.contains_key("p2"));
+        let player = game.entities.iter().find(|e| e.id == "p1").unwrap();
+        assert_eq!(player.current_health, player.max_health);
+    }
+    
+    #[test]
+    fn room_contents_buckets_everything_by_room() {
+        let mut game = game_on(level(""));
+        stage(&mut game, &["##########", "#........#", "##########"], &[(1, 1)], &[("orc", 7, 1), ("orc", 4, 1)]);
+        game.dungeon.rooms = vec![Room { x: 1, y: 1, width: 3, height: 1 }, Room { x: 6, y: 1, width: 3, height: 1 }];  // A corridor at x 4-5
+        game.chests.push(Chest { id: "chest_0".to_string(), x: 2, y: 1, object_id: "chest".to_string(), is_open: false, is_active: false });
+        game.consumables.push(Consumable { id: "potion_0".to_string(), x: 8, y: 1, object_id: "potion".to_string(), dropped_turns_ago: None });
+        
+        let contents = game.room_contents();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].entities, vec!["p1"]);
+        assert_eq!(contents[0].chests, vec!["chest_0"]);
+        assert!(contents[0].consumables.is_empty());
+        assert_eq!(contents[1].entities, vec!["monster_0"]);  // monster_1 is out in the corridor
+        assert!(contents[1].chests.is_empty());
+        assert_eq!(contents[1].consumables, vec!["potion_0"]);
+    }
+    
+    #[test]
+    fn stepping_on_a_shrine_grants_a_buff_once_that_wears_off_on_schedule() {
+        let mut config = create_default_config();
+        let mut shrine = crate::game_object::GameObject::new("shrine".to_string(), "Shrine".to_string(), "shrine".to_string(), true, 0, 0);
+        shrine.interactable = Some(crate::game_object::InteractableData {
+            effect: Some(crate::game_object::InteractionEffect::Buff { kind: StatusEffectKind::Strengthened, amount: 3, turns: 3 }),
+            toggle: None,
+        });
+        config.game_objects.push(shrine);
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        game.chests.push(Chest { id: "shrine_0".to_string(), x: 2, y: 1, object_id: "shrine".to_string(), is_open: false, is_active: false });
+        let base_attack = game.entities[0].attack;
+        let name = game.entities[0].display_name(&game.object_registry);
+        
+        let (messages, _, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(messages.iter().any(|msg| msg.text == format!("The Shrine grants {} +3 attack for 3 turns. It falls silent.", name)));
+        assert_eq!((game.entities[0].x, game.entities[0].y), (2, 1));
+        assert!(game.chests[0].is_open);
+        
+        // The turn it was granted counts as the first of the three; stepping back on does nothing
+        for (turn, move_action) in ["move_left", "move_right"].into_iter().enumerate() {
+            assert_eq!(game.entities[0].effective_attack(), base_attack + 3, "turn {}", turn);
+            let (messages, _, _) = game.handle_command(&command(move_action), "p1");
+            assert!(!messages.iter().any(|msg| msg.text.contains("Shrine")));
+        }
+        assert_eq!((game.entities[0].x, game.entities[0].y), (2, 1));
+        assert_eq!(game.entities[0].effective_attack(), base_attack);
+        assert!(game.entities[0].status_effects.is_empty());
+    }
+    
+    #[test]
+    fn a_boxed_in_player_is_told() {
+        let mut game = game_on(level(""));
+        game.dungeon = dungeon_from(&["###", "#.#", "###"]);
+        game.entities.retain(|e| e.controller == EntityController::Player);
+        (game.entities[0].x, game.entities[0].y) = (1, 1);
+        assert_eq!(game.check_trapped_players().len(), 1);
+        
+        game.dungeon = dungeon_from(&["####", "#..#", "####"]);
+        assert!(game.check_trapped_players().is_empty());
+    }
+    
+    #[test]
+    fn a_broken_weapon_reverts_to_unarmed_attack() {
+        let mut config = create_default_config();
+        let mut sword = crate::game_object::GameObject::new("sword".to_string(), "Sword".to_string(), "equipment".to_string(), true, 0, 0);
+        sword.attack = Some(7);
+        sword.durability = Some(3);
+        config.game_objects.push(sword);
+        let mut game = game_with(config, level(""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("orc", 3, 1)]);
+        let unarmed = game.entities[0].effective_attack();
+        
+        // Picking the sword up puts it on
+        game.consumables.push(Consumable { id: "sword_1".to_string(), x: 2, y: 1, object_id: "sword".to_string(), dropped_turns_ago: None });
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!(game.entities[0].effective_attack(), unarmed + 7);
+        
+        game.entities[1].current_health = 10_000;
+        (game.entities[1].x, game.entities[1].y) = (3, 1);
+        for _ in 0..2 {
+            game.handle_command(&directed("attack", "right"), "p1");
+            assert_eq!(game.entities[0].effective_attack(), unarmed + 7);
+        }
+        let (messages, _, _) = game.handle_command(&directed("attack", "right"), "p1");
+        assert!(messages.iter().any(|msg| msg.text.contains("Your Sword breaks!")));
+        assert_eq!(game.entities[0].effective_attack(), unarmed);
+        assert!(game.players["p1"].weapon.is_none());
+    }
+    
+    /// The default config plus a one-hit boss, "ogre"
+    fn config_with_boss() -> GameConfig {
+        let mut config = create_default_config();
+        let mut ogre = crate::game_object::GameObject::new("ogre".to_string(), "Ogre".to_string(), "character".to_string(), true, 0, 0)
+            .with_health(1);
+        ogre.monster = Some(true);
+        ogre.boss = Some(true);
+        config.game_objects.push(ogre);
+        config
+    }
+    
+    #[test]
+    fn killing_the_boss_wins_a_kill_boss_level() {
+        let mut game = game_with(config_with_boss(), level("win_condition = \"kill_boss\""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("ogre", 2, 1)]);
+        assert_eq!(game.win_progress().map(|progress| progress.remaining), Some(1));
+        
+        let (_, level_complete, _) = game.handle_command(&directed("attack", "right"), "p1");
+        assert!(level_complete);
+        assert_eq!(game.current_level, 2);
+    }
+    
+    #[test]
+    fn a_kill_boss_level_without_a_boss_is_won_by_the_stairs() {
+        let mut game = game_with(config_with_boss(), level("win_condition = \"kill_boss\""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        
+        let (_, level_complete, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(!level_complete);
+        assert_eq!(game.current_level, 1);
+        assert!(game.win_progress().is_none());
+    }
+    
+    #[test]
+    fn a_won_level_is_not_won_again_when_revisited() {
+        let mut game = game_with(config_with_boss(), level("win_condition = \"kill_boss\""));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[("ogre", 2, 1)]);
+        game.handle_command(&directed("attack", "right"), "p1");
+        assert_eq!(game.current_level, 2);
+        
+        game.change_level(1);
+        assert!(game.level_has_boss);
+        game.dungeon = dungeon_from(&["####", "#..#", "####"]);
+        (game.entities[0].x, game.entities[0].y) = (1, 1);
+        let (_, level_complete, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(!level_complete);
+        assert_eq!(game.current_level, 1);
+        assert!(game.win_progress().is_none());
+    }
+    
+    #[test]
+    fn surviving_the_turns_wins_a_survive_turns_level() {
+        let mut game = game_on(level("win_condition = { survive_turns = 3 }"));
+        stage(&mut game, &["####", "#..#", "####"], &[(1, 1)], &[]);
+        
+        for action in ["move_right", "move_left"] {
+            let (_, level_complete, _) = game.handle_command(&command(action), "p1");
+            assert!(!level_complete);
+        }
+        let (_, level_complete, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(level_complete);
+        assert_eq!(game.current_level, 2);
+    }
+    
+    #[test]
+    fn collecting_the_items_wins_a_collect_items_level() {
+        let mut game = game_on(level("win_condition = { collect_items = 2 }"));
+        stage(&mut game, &["#####", "#...#", "#####"], &[(1, 1)], &[]);
+        for (i, x) in [2, 3].into_iter().enumerate() {
+            game.consumables.push(Consumable { id: format!("potion_{}", i), x, y: 1, object_id: "health_potion".to_string(), dropped_turns_ago: None });
+        }
+        
+        let (_, level_complete, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(!level_complete);
+        assert_eq!(game.win_progress().map(|progress| progress.remaining), Some(1));
+        let (_, level_complete, _) = game.handle_command(&command("move_right"), "p1");
+        assert!(level_complete);
+        assert_eq!(game.current_level, 2);
+    }
+    
+    #[test]
+    fn the_solo_fast_path_plays_out_like_the_general_path() {
+        // The same seeded fight twice, the second time with a fallen teammate keeping it off the fast path
+        let play = |with_fallen_teammate: bool| {
+            let mut game = game_on(level(""));
+            if with_fallen_teammate {
+                game.add_player("p2".to_string());
+            }
+            game.reseed(3);
+            stage(&mut game, &["########", "#......#", "#......#", "#......#", "########"], &[(1, 2), (1, 3)], &[("orc", 6, 1), ("orc", 6, 3)]);
+            if with_fallen_teammate {
+                game.entities[1].current_health = 0;
+            }
+            assert_eq!(game.is_solo("p1"), !with_fallen_teammate);
+            
+            let mut rounds = Vec::new();
+            for action in ["move_right", "move_right", "move_right", "move_right", "move_up", "move_right", "move_down", "move_left"] {
+                let (messages, _, _) = game.handle_command(&command(action), "p1");
+                let monsters: Vec<(usize, usize, u32)> = game.entities.iter()
+                    .filter(|e| e.controller == EntityController::AI)
+                    .map(|e| (e.x, e.y, e.current_health))
+                    .collect();
+                let texts: Vec<String> = messages.into_iter().map(|msg| msg.text).collect();
+                rounds.push((game.current_turn, game.turn_phase, health(&game, "p1"), monsters, texts));
+            }
+            assert!(game.players_acted_this_turn.is_empty());
+            rounds
+        };
+        assert_eq!(play(true), play(false));
+    }
+    
+    /// Players p1 at (1, 1) and p2 at (5, 1) with an orc next to p2
+    fn two_player_game() -> GameState {
+        let mut game = game_on(level(""));
+        game.add_player("p2".to_string());
+        stage(&mut game, &["#######", "#.....#", "#######"], &[(1, 1), (5, 1)], &[("orc", 4, 1)]);
+        game
+    }
+    
+    #[test]
+    fn monsters_act_only_once_every_player_has() {
+        let mut game = two_player_game();
+        let turn = game.current_turn;
+        
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!(game.current_turn, turn);
+        assert_eq!(health(&game, "p2"), game.entities[1].max_health);
+        
+        // A second action in the same round is ignored
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!((game.entities[0].x, game.entities[0].y), (2, 1));
+        assert_eq!(game.current_turn, turn);
+        
+        game.handle_command(&command("move_left"), "p2");
+        assert_eq!(game.current_turn, turn + 1);
+        assert!(health(&game, "p2") < game.entities[1].max_health);
+        assert!(game.players_acted_this_turn.is_empty());
+    }
+    
+    #[test]
+    fn unknown_actions_neither_use_the_turn_nor_run_monsters() {
+        let mut game = two_player_game();
+        let turn = game.current_turn;
+        
+        game.handle_command(&command("dance"), "p1");
+        game.handle_command(&command("dance"), "p2");
+        assert_eq!(game.current_turn, turn);
+        assert!(game.players_acted_this_turn.is_empty());
+        assert_eq!(health(&game, "p2"), game.entities[1].max_health);
+        
+        // Both can still take their turn
+        game.handle_command(&command("move_right"), "p1");
+        game.handle_command(&command("move_left"), "p2");
+        assert_eq!(game.current_turn, turn + 1);
+    }
+    
+    #[test]
+    fn a_round_left_by_a_departed_player_is_finished_first() {
+        let mut game = two_player_game();
+        let turn = game.current_turn;
+        
+        game.handle_command(&command("move_right"), "p1");
+        game.remove_player("p2");
+        assert_eq!(game.current_turn, turn);
+        
+        // p1's next command closes the old round, then takes a turn of its own
+        game.handle_command(&command("move_right"), "p1");
+        assert_eq!(game.current_turn, turn + 2);
+        assert!(game.players_acted_this_turn.is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::OnceLock;
+
+/// How much the server logs to stderr, set with the `LOG_LEVEL` env var
+/// (`error`, `warn`, `info` or `debug`; default `info`). Each level includes the ones above it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,  // Connections, replays - one line per event, fine for production
+    Debug,  // Per-message and map generation details
+}
+
+impl Level {
+    /// Level named by `LOG_LEVEL`; unset or unknown values fall back to `info`
+    fn from_env(value: Option<&str>) -> Self {
+        match value.map(|value| value.trim().to_ascii_lowercase()).as_deref() {
+            Some("error") => Level::Error,
+            Some("warn") | Some("warning") => Level::Warn,
+            Some("debug") | Some("trace") => Level::Debug,
+            _ => Level::Info,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+}
+
+/// Most detailed level that gets written (read from the environment once)
+pub fn max_level() -> Level {
+    static MAX_LEVEL: OnceLock<Level> = OnceLock::new();
+    *MAX_LEVEL.get_or_init(|| Level::from_env(std::env::var("LOG_LEVEL").ok().as_deref()))
+}
+
+pub fn enabled(level: Level) -> bool {
+    level <= max_level()
+}
+
+/// Write one line to stderr. Use the macros instead, which skip formatting for disabled levels.
+pub fn write(level: Level, args: std::fmt::Arguments) {
+    eprintln!("{} {}", level.label(), args);
+}
+
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($level) {
+            $crate::logging::write($level, format_args!($($arg)*));
+        }
+    };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::logging::log!($crate::logging::Level::Error, $($arg)*) };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => { $crate::logging::log!($crate::logging::Level::Warn, $($arg)*) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::logging::log!($crate::logging::Level::Info, $($arg)*) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::logging::log!($crate::logging::Level::Debug, $($arg)*) };
+}
+
+pub(crate) use {debug, error, info, log, warning};
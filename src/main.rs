@@ -1,5 +1,5 @@
 use axum::{
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use tower_http::services::ServeDir;
@@ -21,6 +21,13 @@ mod combat;
 mod ai;
 mod map_generator;
 mod game_state;
+mod command;
+mod update_schema;
+mod metrics;
+mod replay;
+mod player;
+mod wire_format;
+mod logging;
 
 // SharedState and Tx are used via api module
 use game_state::GameState;
@@ -33,35 +40,54 @@ async fn main() {
         Ok(cfg) => {
             cfg
         }
+        // Only create a default if there's no file - never overwrite one that failed to load
+        Err(e) if e.is_not_found() => {
+            logging::info!("game_config.toml not found. Creating default config.");
+            let default_config = api::create_default_config();
+            let _ = default_config.save("game_config.toml");
+            default_config
+        }
         Err(e) => {
-            // Check if file exists - only create default if it doesn't exist
-            let config_path = std::path::Path::new("game_config.toml");
-            if !config_path.exists() {
-                eprintln!("game_config.toml not found. Creating default config.");
-                let default_config = api::create_default_config();
-                let _ = default_config.save("game_config.toml");
-                default_config
-            } else {
-                // File exists but has parse errors - don't overwrite it!
-                eprintln!("ERROR: Could not parse game_config.toml: {}", e);
-                eprintln!("The file exists but has errors. Please fix it manually or use the editor.");
-                eprintln!("Server will exit to prevent data loss.");
-                std::process::exit(1);
-            }
+            logging::error!("Could not load game_config.toml: {}", e);
+            logging::error!("The file exists but has errors. Please fix it manually or use the editor.");
+            logging::error!("Server will exit to prevent data loss.");
+            std::process::exit(1);
         }
     };
 
-    let tile_registry = tile_registry::TileRegistry::load_from_config(&config);
-    let object_registry = game_object::GameObjectRegistry::load_from_config(&config);
-    let state = std::sync::Arc::new(Mutex::new(GameState::new_with_registry(tile_registry, object_registry)));
+    let game_state = GameState::from_config(&config);
+    // Warn about level themes referring to missing tiles (they're skipped during generation)
+    for level in &config.levels {
+        if let Some(theme) = &level.tile_theme {
+            for error in theme.validate(&game_state.tile_registry) {
+                logging::warning!("level {} tile_theme: {}", level.level_number, error);
+            }
+        }
+    }
+    // Typos in object IDs otherwise only show up as things silently not spawning
+    for reference in config.find_dangling_references() {
+        logging::warning!("undefined object referenced by {}", reference);
+    }
+    let state = std::sync::Arc::new(Mutex::new(game_state));
     let (tx, _rx) = tokio::sync::broadcast::channel(100);
 
     let app = Router::new()
         .route("/", get(api::index))
         .route("/ws", get(api::websocket_handler))
         .route("/api/map", get(api::generate_map_endpoint))
+        .route("/api/level/spawn", post(api::level_spawn_endpoint))
         .route("/api/schema", get(api::schema_endpoint))
+        .route("/api/schema/update", get(api::update_schema_endpoint))
         .route("/api/config", get(api::config_endpoint))
+        .route("/api/config/dangling", get(api::dangling_references_endpoint))
+        .route("/api/spritesheets", get(api::sprite_sheets_endpoint))
+        .route("/api/commands", get(api::commands_endpoint))
+        .route("/api/replay/start", post(api::replay_start_endpoint))
+        .route("/api/replay/stop", post(api::replay_stop_endpoint))
+        .route("/api/replay/play", post(api::replay_play_endpoint))
+        .route("/api/admin/new_game", post(api::admin_new_game_endpoint))
+        .route("/api/admin/rooms", get(api::admin_rooms_endpoint))
+        .route("/metrics", get(api::metrics_endpoint))
         .nest_service("/assets", ServeDir::new("assets"))
         .nest_service("/client", ServeDir::new("client"))
         .with_state((state, tx));
@@ -69,13 +95,13 @@ async fn main() {
     let listener = match tokio::net::TcpListener::bind("0.0.0.0:3000").await {
         Ok(listener) => listener,
         Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
-            eprintln!("Error: Port 3000 is already in use.");
-            eprintln!("Please stop the existing server or use a different port.");
-            eprintln!("You can kill the process with: lsof -ti :3000 | xargs kill -9");
+            logging::error!("Port 3000 is already in use.");
+            logging::error!("Please stop the existing server or use a different port.");
+            logging::error!("You can kill the process with: lsof -ti :3000 | xargs kill -9");
             std::process::exit(1);
         }
         Err(e) => {
-            eprintln!("Failed to bind to port 3000: {}", e);
+            logging::error!("Failed to bind to port 3000: {}", e);
             std::process::exit(1);
         }
     };
@@ -1,86 +1,106 @@
-use crate::dungeon::{Dungeon, Room};
+use crate::dungeon::{Dungeon, DungeonOptions, Room};
 use crate::tile_registry::TileRegistry;
 use crate::game_object::{GameObject, GameObjectRegistry};
 use crate::entity::{Entity, EntityController};
 use crate::consumable::Consumable;
 use crate::chest::Chest;
-use crate::config::LevelConfig;
-use std::fs::OpenOptions;
-use std::io::Write;
+use crate::config::{DifficultyScaling, LevelConfig, StairsPlacement};
+use crate::logging;
+use std::collections::BTreeSet;
+use rand::Rng;
+use rand::seq::SliceRandom;
 
-fn log_debug(msg: &str) {
-    eprintln!("{}", msg);
-    if let Ok(mut file) = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("server_debug.log")
-    {
-        let _ = writeln!(file, "{}", msg);
-    }
+/// Everything placed on a freshly generated level
+pub struct GeneratedMap {
+    pub dungeon: Dungeon,
+    pub entities: Vec<Entity>,  // Monsters only - players are added separately
+    pub consumables: Vec<Consumable>,
+    pub chests: Vec<Chest>,
+    pub stairs_position: Option<(usize, usize)>,  // Stairs down (goal)
+    pub stairs_up_position: Option<(usize, usize)>,  // Stairs back up, if the level has them
+    pub spawn_position: (usize, usize),  // Where players start
+    pub vault_room: Option<usize>,  // Index of the treasure vault in dungeon.rooms (None = no vault)
 }
 
+/// Size of generated maps in tiles
+pub const MAP_WIDTH: usize = 80;
+pub const MAP_HEIGHT: usize = 50;
+
 pub struct MapGenerator;
 
 impl MapGenerator {
-    /// Generate a new game map with all entities, monsters, chests, and stairs placed
+    /// Generate a new game map with all entities, monsters, chests, and stairs placed.
+    /// Monster counts and stats are scaled by `difficulty_scaling` for the given player count
+    /// and, with depth scaling, for the level's number. At most `monster_cap` monsters are placed.
+    /// Every random choice is drawn from `rng`, so the same seed generates the same level.
     pub fn generate_map(
         tile_registry: &TileRegistry,
         object_registry: &GameObjectRegistry,
         level_config: Option<&LevelConfig>,
-    ) -> (Dungeon, Vec<Entity>, Vec<Consumable>, Vec<Chest>, Option<(usize, usize)>) {
-        // Use level config for room count, or defaults
-        let (min_rooms, max_rooms) = if let Some(level) = level_config {
-            log_debug(&format!("[MAP GEN] Using level config: min_rooms={}, max_rooms={}", level.min_rooms, level.max_rooms));
-            (level.min_rooms, level.max_rooms)
+        difficulty_scaling: &DifficultyScaling,
+        player_count: usize,
+        monster_cap: usize,
+        rng: &mut impl Rng,
+    ) -> GeneratedMap {
+        // Use level config for room count and corridor shape, or defaults
+        let dungeon_options = if let Some(level) = level_config {
+            logging::debug!("[MAP GEN] Using level config: min_rooms={}, max_rooms={}", level.min_rooms, level.max_rooms);
+            DungeonOptions {
+                min_rooms: level.min_rooms,
+                max_rooms: level.max_rooms,
+                corridor_width: level.corridor_width.unwrap_or(1) as usize,
+                corridor_winding_percent: level.corridor_winding_percent.unwrap_or(0),
+                tile_theme: level.tile_theme.clone(),
+            }
         } else {
-            log_debug("[MAP GEN] No level config, using defaults: min_rooms=8, max_rooms=12");
-            (8, 12)  // Default values
+            logging::debug!("[MAP GEN] No level config, using defaults: min_rooms=8, max_rooms=12");
+            DungeonOptions::default()
         };
         
-        let dungeon = Dungeon::new_with_room_count(80, 50, tile_registry, min_rooms, max_rooms);
-        log_debug(&format!("[MAP GEN] Generated dungeon with {} rooms", dungeon.rooms.len()));
+        let room_retries = level_config.and_then(|level| level.room_retries).unwrap_or(3);
+        let dungeon = match level_config.and_then(|level| level.target_walkable_percent) {
+            Some(target_percent) => Self::generate_for_walkable_target(tile_registry, &dungeon_options, target_percent, room_retries, rng),
+            None => Self::generate_with_min_rooms(tile_registry, &dungeon_options, room_retries, rng),
+        };
+        logging::debug!("[MAP GEN] Generated dungeon with {} rooms, {:.1}% walkable",
+            dungeon.rooms.len(), dungeon.walkable_percent());
         
-        // Find first floor tile for player spawn
-        let mut player_x = 1;
-        let mut player_y = 1;
-        for y in 0..dungeon.height {
-            for x in 0..dungeon.width {
-                if dungeon.tiles[y][x].walkable {
-                    player_x = x;
-                    player_y = y;
-                    break;
-                }
-            }
-            if dungeon.tiles[player_y][player_x].walkable {
-                break;
-            }
-        }
+        // Spawn players in the middle of the first room, or on the first floor tile if there's none
+        let (player_x, player_y) = dungeon.rooms.first()
+            .map(|room| room.center())
+            .filter(|&(x, y)| dungeon.is_walkable(x, y))
+            .unwrap_or_else(|| Self::first_walkable_tile(&dungeon));
         
         let mut entities = Vec::new();
+        let depth = level_config.map_or(1, |level| level.level_number);
+        // Nothing random is placed this close to the spawn (0 = just not on the spawn tile)
+        let safe_radius = level_config.and_then(|level| level.spawn_safe_radius).unwrap_or(0) as usize;
         
         // Don't create a default player entity - players will be added when they connect
         
-        // Spawn monsters in each room
-        let monster_templates = if let Some(level) = level_config {
-            // Filter monsters to only those allowed in this level
-            if level.allowed_monsters.is_empty() {
-                // If allowed_monsters is empty, spawn no monsters
-                Vec::new()
-            } else {
-                let allowed: std::collections::HashSet<&str> = level.allowed_monsters.iter().map(|s| s.as_str()).collect();
-                object_registry.get_monster_characters()
-                    .into_iter()
-                    .filter(|obj| allowed.contains(obj.id.as_str()))
-                    .collect()
-            }
+        // Place stairs where the level wants them (survival levels have no way out)
+        let survival = level_config.is_some_and(|level| level.survival_mode.unwrap_or(false));
+        let stairs_pos = if survival {
+            None
         } else {
-            // Use all monsters if no level config
-            object_registry.get_monster_characters()
+            let placement = level_config.and_then(|level| level.stairs_placement).unwrap_or_default();
+            Self::place_stairs(&dungeon, player_x, player_y, object_registry, placement, rng)
+        };
+        
+        // Place stairs back up next to the player spawn if this level has them
+        let stairs_up_pos = if level_config.is_some_and(|level| level.stairs_up.unwrap_or(false)) {
+            Self::place_stairs_up(&dungeon, player_x, player_y, stairs_pos, object_registry)
+        } else {
+            None
         };
         
+        // Maybe make a room the treasure vault, away from where players arrive and leave
+        let vault_room = Self::pick_vault_room(&dungeon, level_config, &[Some((player_x, player_y)), stairs_pos, stairs_up_pos], rng);
+        
+        // Spawn monsters in each room
+        let monster_templates = Self::monster_templates(object_registry, level_config);
+        
         if !monster_templates.is_empty() {
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
             let mut monster_id_counter = 0;
             
             // Get min/max monsters per room from level config
@@ -90,7 +110,23 @@ impl MapGenerator {
                 (1, 1)  // Default: 1 monster per room
             };
             
-            for room in &dungeon.rooms {
+            if difficulty_scaling.enabled {
+                logging::debug!("[MAP GEN] Scaling monsters for {} players", player_count);
+            }
+            let leash_to_room = level_config.is_some_and(|level| level.leash_to_room.unwrap_or(false));
+            let monster_spacing = level_config.and_then(|level| level.min_monster_spacing).unwrap_or(0);
+            // Hand-placed monsters (bosses) keep their place under the cap
+            let fixed_monsters = level_config.and_then(|level| level.fixed_spawns.as_ref()).into_iter().flatten()
+                .filter(|spawn| object_registry.get_object(&spawn.object_id).is_some_and(|obj| obj.object_type == "character"))
+                .count();
+            let random_cap = monster_cap.saturating_sub(fixed_monsters);
+            
+            // The vault's guards come on top of the usual ones, and tougher
+            let vault_guards = level_config.and_then(|level| level.vault_guards).unwrap_or(2) as usize;
+            let vault_guard_percent = level_config.and_then(|level| level.vault_guard_percent).unwrap_or(150);
+            
+            for (room_idx, room) in dungeon.rooms.iter().enumerate() {
+                let in_vault = vault_room == Some(room_idx);
                 // Find a random walkable position within the room
                 let mut valid_positions = Vec::new();
                 for dy in 0..room.height {
@@ -99,8 +135,8 @@ impl MapGenerator {
                         let y = room.y + dy;
                         if x < dungeon.width && y < dungeon.height {
                             if dungeon.tiles[y][x].walkable {
-                                // Check if position is not occupied by player
-                                if !(x == player_x && y == player_y) {
+                                // Keep clear of the player spawn and its safe radius
+                                if x.abs_diff(player_x).max(y.abs_diff(player_y)) > safe_radius {
                                     // Check if position is not occupied by another entity
                                     let occupied = entities.iter().any(|e: &Entity| e.x == x && e.y == y);
                                     if !occupied {
@@ -114,7 +150,8 @@ impl MapGenerator {
                 
                 // Spawn monsters based on level config
                 let num_monsters = if !valid_positions.is_empty() {
-                    rng.gen_range(min_monsters..=max_monsters) as usize
+                    let base_count = rng.gen_range(min_monsters..=max_monsters) as usize + if in_vault { vault_guards } else { 0 };
+                    difficulty_scaling.scale_monster_count(base_count, player_count)
                 } else {
                     0
                 };
@@ -123,80 +160,54 @@ impl MapGenerator {
                 let monsters_to_spawn = num_monsters.min(valid_positions.len());
                 
                 // Shuffle positions to randomize spawn locations
-                use rand::seq::SliceRandom;
                 let mut shuffled_positions = valid_positions;
-                shuffled_positions.shuffle(&mut rng);
+                shuffled_positions.shuffle(rng);
                 
-                for i in 0..monsters_to_spawn {
-                    let (monster_x, monster_y) = shuffled_positions[i];
+                let mut spawned = 0;
+                for (monster_x, monster_y) in shuffled_positions {
+                    if spawned == monsters_to_spawn {
+                        break;
+                    }
+                    if entities.len() >= random_cap {
+                        logging::debug!("[MAP GEN] Monster cap of {} reached, skipping the rest", monster_cap);
+                        break;
+                    }
+                    // Keep min_monster_spacing from every monster placed so far
+                    if !Self::far_enough((monster_x, monster_y), entities.iter().map(|e| (e.x, e.y)), monster_spacing) {
+                        continue;
+                    }
                     
                     // Select a random monster template
                     let monster_template = monster_templates[rng.gen_range(0..monster_templates.len())];
                     
-                    let max_health = monster_template.health.unwrap_or(50);
-                    // Attack can be top-level field or in properties map
-                    let attack = monster_template.attack
-                        .or_else(|| {
-                            monster_template.properties
-                                .get("attack")
-                                .and_then(|s| s.parse::<i32>().ok())
-                        })
-                        .unwrap_or(5);
-                    
-                    let defense = monster_template.defense
-                        .or_else(|| {
-                            monster_template.properties
-                                .get("defense")
-                                .and_then(|s| s.parse::<i32>().ok())
-                        })
-                        .unwrap_or(0);
-                    
-                    let attack_spread = monster_template.attack_spread_percent
-                        .or_else(|| {
-                            monster_template.properties
-                                .get("attack_spread_percent")
-                                .and_then(|s| s.parse::<u32>().ok())
-                        })
-                        .unwrap_or(20);
-                    
-                    let crit_chance = monster_template.crit_chance_percent
-                        .or_else(|| {
-                            monster_template.properties
-                                .get("crit_chance_percent")
-                                .and_then(|s| s.parse::<u32>().ok())
-                        })
-                        .unwrap_or(0);
-                    
-                    let crit_damage = monster_template.crit_damage_percent
-                        .or_else(|| {
-                            monster_template.properties
-                                .get("crit_damage_percent")
-                                .and_then(|s| s.parse::<u32>().ok())
-                        })
-                        .unwrap_or(150);  // Default 150% crit damage
-                    
-                    let monster = Entity::new(
+                    let mut monster = Self::create_monster(
+                        monster_template,
                         format!("monster_{}", monster_id_counter),
-                        monster_x,
-                        monster_y,
-                        monster_template.id.clone(),
-                        attack,
-                        defense,
-                        attack_spread,
-                        crit_chance,
-                        crit_damage,
-                        max_health,
-                        EntityController::AI,
+                        (monster_x, monster_y),
+                        difficulty_scaling,
+                        player_count,
+                        depth,
+                        rng,
                     );
+                    if monster_template.patrols.unwrap_or(false) {
+                        monster.patrol_route = Self::patrol_route_for_room(&dungeon, room);
+                    }
+                    if leash_to_room {
+                        monster.home_room = Some(room.clone());
+                    }
+                    if in_vault {
+                        monster.max_health = (monster.max_health * vault_guard_percent / 100).max(1);
+                        monster.current_health = monster.max_health;
+                        monster.attack = monster.attack * vault_guard_percent as i32 / 100;
+                    }
+                    Self::assign_unique_name(&mut monster, monster_template, &entities, rng);
                     entities.push(monster);
                     monster_id_counter += 1;
+                    spawned += 1;
                 }
             }
         }
         
-        // Place stairs in the room farthest from player spawn
-        let stairs_pos = Self::place_stairs(&dungeon, player_x, player_y, object_registry);
-        
         // Don't spawn consumables in rooms - they only drop from monsters and chests
         let consumables = Vec::new();
         
@@ -208,8 +219,6 @@ impl MapGenerator {
             .collect();
         
         if !chest_templates.is_empty() {
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
             let mut chest_id_counter = 0;
             
             // Get target chest count from level config
@@ -233,10 +242,11 @@ impl MapGenerator {
                         let y = room.y + dy;
                         if x < dungeon.width && y < dungeon.height {
                             if dungeon.tiles[y][x].walkable {
-                                // Check if position is not occupied
-                                if !(x == player_x && y == player_y) {
+                                // Check if position is not occupied (or too close to the spawn)
+                                if x.abs_diff(player_x).max(y.abs_diff(player_y)) > safe_radius {
                                     let occupied_by_entity = entities.iter().any(|e| e.x == x && e.y == y);
-                                    let occupied_by_stairs = stairs_pos.map_or(false, |(sx, sy)| sx == x && sy == y);
+                                    let occupied_by_stairs = stairs_pos == Some((x, y))
+                                        || stairs_up_pos == Some((x, y));
                                     let occupied_by_consumable = consumables.iter().any(|c: &Consumable| c.x == x && c.y == y);
                                     let occupied_by_chest = chests.iter().any(|c: &Chest| c.x == x && c.y == y);
                                     if !occupied_by_entity && !occupied_by_stairs && !occupied_by_consumable && !occupied_by_chest {
@@ -252,16 +262,28 @@ impl MapGenerator {
                 }
             }
             
-            // Select one position per room (up to target_chest_count rooms)
-            use rand::seq::SliceRandom;
-            let mut room_indices: Vec<usize> = room_positions.keys().cloned().collect();
-            room_indices.shuffle(&mut rng);
-            let rooms_to_use = target_chest_count.min(room_indices.len() as u32) as usize;
+            // Select one position per room (up to target_chest_count rooms), skipping rooms
+            // with no position far enough from the chests already placed
+            let chest_spacing = level_config.and_then(|level| level.min_chest_spacing).unwrap_or(0);
+            // The vault's chests are placed separately below
+            let mut room_indices: Vec<usize> = room_positions.keys()
+                .filter(|&&room_idx| vault_room != Some(room_idx))
+                .cloned()
+                .collect();
+            // Sorted first: the map's order differs between runs, which would defeat seeding
+            room_indices.sort_unstable();
+            room_indices.shuffle(rng);
             
-            for i in 0..rooms_to_use {
-                let room_idx = room_indices[i];
+            for room_idx in room_indices {
+                if chests.len() as u32 >= target_chest_count {
+                    break;
+                }
                 if let Some(positions) = room_positions.get(&room_idx) {
-                    if let Some(&(chest_x, chest_y)) = positions.choose(&mut rng) {
+                    let spaced: Vec<(usize, usize)> = positions.iter()
+                        .copied()
+                        .filter(|&pos| Self::far_enough(pos, chests.iter().map(|c| (c.x, c.y)), chest_spacing))
+                        .collect();
+                    if let Some(&(chest_x, chest_y)) = spaced.choose(rng) {
                         let chest_template = chest_templates[rng.gen_range(0..chest_templates.len())];
                         
                         let chest = Chest {
@@ -270,74 +292,540 @@ impl MapGenerator {
                             y: chest_y,
                             object_id: chest_template.id.clone(),
                             is_open: false,
+                            is_active: false,
                         };
                         chests.push(chest);
                         chest_id_counter += 1;
                     }
                 }
             }
+            
+            // The vault holds a pile of chests on top of the level's count, however close together
+            if let Some(positions) = vault_room.and_then(|room_idx| room_positions.get(&room_idx)) {
+                let vault_chests = level_config.and_then(|level| level.vault_chests).unwrap_or(3) as usize;
+                for &(chest_x, chest_y) in positions.choose_multiple(rng, vault_chests) {
+                    let chest_template = chest_templates[rng.gen_range(0..chest_templates.len())];
+                    chests.push(Chest {
+                        id: format!("chest_{}", chest_id_counter),
+                        x: chest_x,
+                        y: chest_y,
+                        object_id: chest_template.id.clone(),
+                        is_open: false,
+                        is_active: false,
+                    });
+                    chest_id_counter += 1;
+                }
+            }
+        }
+        
+        let mut generated = GeneratedMap {
+            dungeon,
+            entities,
+            consumables,
+            chests,
+            stairs_position: stairs_pos,
+            stairs_up_position: stairs_up_pos,
+            spawn_position: (player_x, player_y),
+            vault_room,
+        };
+        
+        // Doors (and their key) go in after the chests so nothing ends up on a doorway
+        if let (Some(room_idx), Some(door)) = (vault_room, level_config.and_then(|level| level.vault_door.as_ref())) {
+            Self::lock_vault(&mut generated, room_idx, door, dungeon_options.corridor_width, object_registry, rng);
+        }
+        
+        // Hand-placed objects go last so they can take the nearest free tile
+        if let Some(level) = level_config.filter(|level| level.fixed_spawns.is_some()) {
+            Self::place_fixed_spawns(&mut generated, level, object_registry, difficulty_scaling, player_count, monster_cap, rng);
+        }
+        
+        generated
+    }
+    
+    /// Generate a layout, regenerating it up to `retries` times while it has fewer than
+    /// `min_rooms` rooms (room placement gives up after a while on a crowded map). Keeps the
+    /// layout with the most rooms if none reaches the minimum.
+    fn generate_with_min_rooms(tile_registry: &TileRegistry, options: &DungeonOptions, retries: u32, rng: &mut impl Rng) -> Dungeon {
+        let min_rooms = options.min_rooms as usize;
+        let mut best = Dungeon::new_with_options(MAP_WIDTH, MAP_HEIGHT, tile_registry, options, rng);
+        
+        for retry in 1..=retries {
+            if best.rooms.len() >= min_rooms {
+                break;
+            }
+            logging::debug!("[MAP GEN] Only {} of {} rooms fit, regenerating (retry {}/{})",
+                best.rooms.len(), min_rooms, retry, retries);
+            let dungeon = Dungeon::new_with_options(MAP_WIDTH, MAP_HEIGHT, tile_registry, options, rng);
+            if dungeon.rooms.len() > best.rooms.len() {
+                best = dungeon;
+            }
+        }
+        
+        if best.rooms.len() < min_rooms {
+            logging::warning!("[MAP GEN] Only {} of {} rooms fit after {} retries, using the smaller level",
+                best.rooms.len(), min_rooms, retries);
+        }
+        best
+    }
+    
+    /// Generate a few layouts, adding rooms after one that's too cramped and removing rooms after
+    /// one that's too open, and keep the one closest to the target walkable percentage
+    fn generate_for_walkable_target(tile_registry: &TileRegistry, options: &DungeonOptions, target_percent: u32, room_retries: u32, rng: &mut impl Rng) -> Dungeon {
+        const ATTEMPTS: usize = 6;
+        let target = target_percent as f32;
+        let mut options = options.clone();
+        let mut best: Option<Dungeon> = None;
+        
+        for _ in 0..ATTEMPTS {
+            let dungeon = Self::generate_with_min_rooms(tile_registry, &options, room_retries, rng);
+            let percent = dungeon.walkable_percent();
+            logging::debug!("[MAP GEN] Walkable target {}%: {} rooms gave {:.1}%", target_percent, dungeon.rooms.len(), percent);
+            
+            if percent < target {
+                options.min_rooms += 1;
+                options.max_rooms += 1;
+            } else {
+                options.min_rooms = options.min_rooms.saturating_sub(1).max(1);
+                options.max_rooms = options.max_rooms.saturating_sub(1).max(options.min_rooms);
+            }
+            
+            let closer = best.as_ref().is_none_or(|best| (percent - target).abs() < (best.walkable_percent() - target).abs());
+            if closer {
+                best = Some(dungeon);
+            }
+        }
+        best.expect("at least one layout is generated")
+    }
+    
+    /// First floor tile scanning row by row, (1, 1) if the map has none
+    fn first_walkable_tile(dungeon: &Dungeon) -> (usize, usize) {
+        for y in 0..dungeon.height {
+            for x in 0..dungeon.width {
+                if dungeon.tiles[y][x].walkable {
+                    return (x, y);
+                }
+            }
         }
+        (1, 1)
+    }
+    
+    /// Whether `pos` is at least `spacing` tiles (Chebyshev) from every position in `others`
+    fn far_enough(pos: (usize, usize), mut others: impl Iterator<Item = (usize, usize)>, spacing: u32) -> bool {
+        others.all(|(x, y)| x.abs_diff(pos.0).max(y.abs_diff(pos.1)) >= spacing as usize)
+    }
+    
+    /// Roll the level's vault chance and pick a random room for the vault that contains none of
+    /// `keep_clear` (spawn and stairs). None if the roll fails or no room qualifies.
+    fn pick_vault_room(dungeon: &Dungeon, level_config: Option<&LevelConfig>, keep_clear: &[Option<(usize, usize)>], rng: &mut impl Rng) -> Option<usize> {
+        let chance = level_config.and_then(|level| level.vault_chance_percent).unwrap_or(0);
+        if chance == 0 || rng.gen_range(0..100) >= chance {
+            return None;
+        }
+        let candidates: Vec<usize> = dungeon.rooms.iter()
+            .enumerate()
+            .filter(|(_, room)| keep_clear.iter().flatten().all(|&(x, y)| !room.contains(x, y)))
+            .map(|(room_idx, _)| room_idx)
+            .collect();
+        let vault = candidates.choose(rng).copied();
+        if let Some(room_idx) = vault {
+            logging::debug!("[MAP GEN] Room {} is the vault", room_idx);
+        }
+        vault
+    }
+    
+    /// Shut the vault's entrances with closed `door_id` doors. An entrance is a gap at most
+    /// `corridor_width` tiles wide next to the vault; a wider stretch of floor along its edge (a
+    /// corridor hugging the room) is walled in with the vault, so its doors go where the corridor
+    /// turns away. Nothing is locked if the stairs down can't be reached from the spawn without
+    /// passing a door. If the doors need a key, one key per door is dropped on floor players can
+    /// reach from the spawn, in other rooms when possible; the doors are left open if there isn't
+    /// room for all of them.
+    fn lock_vault(map: &mut GeneratedMap, room_idx: usize, door_id: &str, corridor_width: usize, object_registry: &GameObjectRegistry, rng: &mut impl Rng) {
+        const MAX_PASSES: usize = 8;
+        let Some(door) = object_registry.get_object(door_id) else {
+            return;
+        };
+        let room = map.dungeon.rooms[room_idx].clone();
+        let neighbors = |(x, y): (usize, usize)| [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
         
-        (dungeon, entities, consumables, chests, stairs_pos)
+        // Grow the walled-in area over stretches along its edge until only narrow gaps lead in.
+        // Ordered sets keep door IDs and key spots the same for the same seed.
+        let mut vault: BTreeSet<(usize, usize)> = (room.y..room.y + room.height)
+            .flat_map(|y| (room.x..room.x + room.width).map(move |x| (x, y)))
+            .collect();
+        let mut doorways = None;
+        for _ in 0..MAX_PASSES {
+            let outside: BTreeSet<(usize, usize)> = vault.iter()
+                .flat_map(|&pos| neighbors(pos))
+                .filter(|&(x, y)| map.dungeon.is_walkable(x, y) && !vault.contains(&(x, y)))
+                .collect();
+            let (entrances, stretches): (Vec<_>, Vec<_>) = Self::connected_groups(&outside).into_iter()
+                .partition(|group| group.len() <= corridor_width);
+            if stretches.is_empty() {
+                doorways = Some(entrances.concat());
+                break;
+            }
+            let into_other_room = stretches.iter().flatten()
+                .any(|&(x, y)| map.dungeon.rooms.iter().enumerate().any(|(i, other)| i != room_idx && other.contains(x, y)));
+            if into_other_room {
+                break;
+            }
+            vault.extend(stretches.into_iter().flatten());
+        }
+        let Some(doorways) = doorways else {
+            logging::debug!("[MAP GEN] No narrow entrances found for vault room {}, leaving it open", room_idx);
+            return;
+        };
+        
+        // What's left reachable from the spawn once the doors are shut
+        let mut reachable = vec![map.spawn_position];
+        let mut seen: BTreeSet<(usize, usize)> = reachable.iter().copied().collect();
+        let mut next = 0;
+        while next < reachable.len() {
+            let pos = reachable[next];
+            next += 1;
+            for (nx, ny) in neighbors(pos) {
+                if map.dungeon.is_walkable(nx, ny) && !doorways.contains(&(nx, ny)) && !vault.contains(&(nx, ny)) && seen.insert((nx, ny)) {
+                    reachable.push((nx, ny));
+                }
+            }
+        }
+        if vault.contains(&map.spawn_position) || map.stairs_position.is_some_and(|stairs| !seen.contains(&stairs)) {
+            logging::debug!("[MAP GEN] Locking vault room {} would shut the way to the stairs, leaving it open", room_idx);
+            return;
+        }
+        
+        let key = door.requires_key.as_ref().and_then(|key_id| object_registry.get_object(key_id));
+        let mut locked = true;
+        if let Some(key) = key {
+            let free: Vec<(usize, usize)> = reachable.into_iter()
+                .filter(|&pos| pos != map.spawn_position && map.stairs_position != Some(pos) && map.stairs_up_position != Some(pos))
+                .filter(|&pos| !map.entities.iter().any(|e| (e.x, e.y) == pos) && !map.chests.iter().any(|c| (c.x, c.y) == pos))
+                .collect();
+            let in_rooms: Vec<(usize, usize)> = free.iter()
+                .copied()
+                .filter(|&(x, y)| map.dungeon.rooms.iter().any(|other| other.contains(x, y)))
+                .collect();
+            let spots = if in_rooms.len() >= doorways.len() { &in_rooms } else { &free };
+            if spots.len() >= doorways.len() {
+                for (i, &(x, y)) in spots.choose_multiple(rng, doorways.len()).enumerate() {
+                    map.consumables.push(Consumable {
+                        id: format!("vault_key_{}", i),
+                        x,
+                        y,
+                        object_id: key.id.clone(),
+                        dropped_turns_ago: None,
+                    });
+                }
+            } else {
+                logging::warning!("[MAP GEN] Nowhere reachable to leave {} {}s for the vault, leaving its doors open", doorways.len(), key.name);
+                locked = false;
+            }
+        }
+        
+        for (i, (x, y)) in doorways.into_iter().enumerate() {
+            map.chests.push(Chest {
+                id: format!("vault_door_{}", i),
+                x,
+                y,
+                object_id: door.id.clone(),
+                is_open: !locked,
+                is_active: false,
+            });
+        }
+    }
+    
+    /// Split tiles into groups connected orthogonally, each in order
+    fn connected_groups(tiles: &BTreeSet<(usize, usize)>) -> Vec<Vec<(usize, usize)>> {
+        let mut left = tiles.clone();
+        let mut groups = Vec::new();
+        while let Some(start) = left.pop_first() {
+            let mut group = vec![start];
+            let mut next = 0;
+            while next < group.len() {
+                let (x, y) = group[next];
+                next += 1;
+                for neighbor in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)] {
+                    if left.remove(&neighbor) {
+                        group.push(neighbor);
+                    }
+                }
+            }
+            group.sort_unstable();
+            groups.push(group);
+        }
+        groups
+    }
+    
+    /// Whether an object can be hand-placed on a level: monsters, consumables and chests
+    pub fn is_fixed_spawnable(obj: &GameObject) -> bool {
+        obj.is_monster() || obj.is_interactable() || matches!(obj.object_type.as_str(), "consumable" | "key")
+    }
+    
+    /// Place a level's fixed spawns on the generated map, each on the free floor tile nearest
+    /// to its configured position. Unknown or unplaceable objects, and monsters beyond
+    /// `monster_cap`, are skipped.
+    fn place_fixed_spawns(
+        map: &mut GeneratedMap,
+        level: &LevelConfig,
+        object_registry: &GameObjectRegistry,
+        difficulty_scaling: &DifficultyScaling,
+        player_count: usize,
+        monster_cap: usize,
+        rng: &mut impl Rng,
+    ) {
+        for (i, spawn) in level.fixed_spawns.iter().flatten().enumerate() {
+            let Some(template) = object_registry.get_object(&spawn.object_id).filter(|obj| Self::is_fixed_spawnable(obj)) else {
+                logging::debug!("[MAP GEN] Skipping fixed spawn '{}': not a monster, item or interactable", spawn.object_id);
+                continue;
+            };
+            let Some((x, y)) = Self::free_tile_near(map, spawn.x, spawn.y) else {
+                continue;
+            };
+            let id = format!("fixed_{}", i);
+            // Doors, levers etc. are interactables like chests (closed/open), keys lie on the floor like consumables
+            if template.is_interactable() {
+                map.chests.push(Chest { id, x, y, object_id: template.id.clone(), is_open: false, is_active: false });
+            } else if matches!(template.object_type.as_str(), "consumable" | "key") {
+                map.consumables.push(Consumable { id, x, y, object_id: template.id.clone(), dropped_turns_ago: None });
+            } else if map.entities.len() >= monster_cap {
+                logging::warning!("[MAP GEN] Skipping fixed spawn '{}': monster cap of {} reached", spawn.object_id, monster_cap);
+            } else {
+                let mut monster = Self::create_monster(template, id, (x, y), difficulty_scaling, player_count, level.level_number, rng);
+                Self::assign_unique_name(&mut monster, template, &map.entities, rng);
+                map.entities.push(monster);
+            }
+        }
     }
     
-    /// Place stairs in the room farthest from player spawn
+    /// Nearest walkable tile to (x, y) not used by the spawn point, stairs or another object
+    fn free_tile_near(map: &GeneratedMap, x: usize, y: usize) -> Option<(usize, usize)> {
+        let dungeon = &map.dungeon;
+        let max_radius = dungeon.width.max(dungeon.height) as i32;
+        for radius in 0..=max_radius {
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    // Only look at the ring at this radius
+                    if dx.abs() != radius && dy.abs() != radius {
+                        continue;
+                    }
+                    let tx = x as i32 + dx;
+                    let ty = y as i32 + dy;
+                    if tx < 0 || ty < 0 || !dungeon.is_walkable(tx as usize, ty as usize) {
+                        continue;
+                    }
+                    let pos = (tx as usize, ty as usize);
+                    let taken = pos == map.spawn_position
+                        || map.stairs_position == Some(pos)
+                        || map.stairs_up_position == Some(pos)
+                        || map.entities.iter().any(|e| (e.x, e.y) == pos)
+                        || map.consumables.iter().any(|c| (c.x, c.y) == pos)
+                        || map.chests.iter().any(|c| (c.x, c.y) == pos);
+                    if !taken {
+                        return Some(pos);
+                    }
+                }
+            }
+        }
+        None
+    }
+    
+    /// Place stairs according to the level's placement: in a room chosen by its distance from
+    /// the player spawn, or at fixed coordinates
     pub fn place_stairs(
         dungeon: &Dungeon,
         player_x: usize,
         player_y: usize,
         object_registry: &GameObjectRegistry,
+        placement: StairsPlacement,
+        rng: &mut impl Rng,
     ) -> Option<(usize, usize)> {
         // Find stairs object (should be type "goal", not "tile")
-        let stairs_obj = object_registry.get_object("stairs");
-        if stairs_obj.is_none() {
-            return None;
-        }
+        object_registry.get_object("stairs")?;
         
-        // Verify it's not a tile type
-        let obj = stairs_obj.unwrap();
-        if obj.object_type == "tile" {
-        }
+        // Manhattan distance from the player to a room's center
+        let distance = |room: &&Room| {
+            let (center_x, center_y) = room.center();
+            player_x.abs_diff(center_x) + player_y.abs_diff(center_y)
+        };
+        let other_rooms: Vec<&Room> = dungeon.rooms.iter()
+            .filter(|room| !room.contains(player_x, player_y))
+            .collect();
         
-        // Find the room farthest from player spawn
-        let mut farthest_room: Option<&Room> = None;
-        let mut max_distance = 0;
+        let (target_x, target_y) = match placement {
+            StairsPlacement::Farthest => dungeon.rooms.iter().max_by_key(distance)?.center(),
+            StairsPlacement::Nearest => other_rooms.iter().copied().min_by_key(distance)?.center(),
+            StairsPlacement::RandomRoom => other_rooms.choose(rng)?.center(),
+            StairsPlacement::At { x, y } => {
+                if !dungeon.is_walkable(x, y) {
+                    logging::debug!("[MAP GEN] Stairs at ({}, {}) is not walkable on this layout, using the nearest floor", x, y);
+                }
+                (x, y)
+            }
+        };
         
-        for room in &dungeon.rooms {
-            // Calculate distance from player to room center
-            let room_center_x = room.x + room.width / 2;
-            let room_center_y = room.y + room.height / 2;
-            
-            // Use Manhattan distance
-            let dx = if player_x > room_center_x { player_x - room_center_x } else { room_center_x - player_x };
-            let dy = if player_y > room_center_y { player_y - room_center_y } else { room_center_y - player_y };
-            let distance = dx + dy;
-            
-            if distance > max_distance {
-                max_distance = distance;
-                farthest_room = Some(room);
+        // Try the target first, then search nearby (never on the spawn tile itself)
+        for offset in 0..=5i32 {
+            for dy in -offset..=offset {
+                for dx in -offset..=offset {
+                    let x = target_x as i32 + dx;
+                    let y = target_y as i32 + dy;
+                    if x < 0 || y < 0 {
+                        continue;
+                    }
+                    let (x, y) = (x as usize, y as usize);
+                    // Don't replace the tile - the stairs are rendered as an object on top
+                    if dungeon.is_walkable(x, y) && (x, y) != (player_x, player_y) {
+                        return Some((x, y));
+                    }
+                }
             }
         }
         
-        if let Some(room) = farthest_room {
-            // Find a walkable position in the center of the farthest room
-            let center_x = room.x + room.width / 2;
-            let center_y = room.y + room.height / 2;
-            
-            // Try center first, then search nearby
-            for offset in 0..=5 {  // Increased search radius
-                for dy in -(offset as i32)..=(offset as i32) {
-                    for dx in -(offset as i32)..=(offset as i32) {
-                        let x = (center_x as i32 + dx) as usize;
-                        let y = (center_y as i32 + dy) as usize;
-                        
-                        if x < dungeon.width && y < dungeon.height {
-                            if dungeon.tiles[y][x].walkable {
-                                // Don't replace the tile - just return the position
-                                // The stairs will be rendered as an entity/object on top
-                                return Some((x, y));
-                            }
-                        }
+        None
+    }
+    
+    /// Monster templates that can spawn on a level: the level's allowed monsters, or all
+    /// monsters without a level config
+    pub fn monster_templates<'a>(object_registry: &'a GameObjectRegistry, level_config: Option<&LevelConfig>) -> Vec<&'a GameObject> {
+        if let Some(level) = level_config {
+            // Filter monsters to only those allowed in this level
+            if level.allowed_monsters.is_empty() {
+                // If allowed_monsters is empty, spawn no monsters
+                Vec::new()
+            } else {
+                let allowed: std::collections::HashSet<&str> = level.allowed_monsters.iter().map(|s| s.as_str()).collect();
+                object_registry.get_monster_characters()
+                    .into_iter()
+                    .filter(|obj| allowed.contains(obj.id.as_str()))
+                    .collect()
+            }
+        } else {
+            // Use all monsters if no level config
+            object_registry.get_monster_characters()
+        }
+    }
+    
+    /// Give a monster a name from its template's pool that `others` don't use ("Orc: Grolok 2" once it runs out)
+    pub fn assign_unique_name(monster: &mut Entity, template: &GameObject, others: &[Entity], rng: &mut impl Rng) {
+        let Some(pool) = template.name_pool.as_ref().filter(|pool| !pool.is_empty()) else {
+            return;
+        };
+        let taken: Vec<&str> = others.iter().filter_map(|e| e.unique_name.as_deref()).collect();
+        let mut candidates: Vec<String> = pool.iter().map(|name| format!("{}: {}", template.name, name)).collect();
+        candidates.shuffle(rng);
+        
+        let name = candidates.iter()
+            .find(|name| !taken.contains(&name.as_str()))
+            .cloned()
+            .unwrap_or_else(|| {
+                (2..)
+                    .map(|n| format!("{} {}", candidates[0], n))
+                    .find(|name| !taken.contains(&name.as_str()))
+                    .unwrap()
+            });
+        monster.unique_name = Some(name);
+    }
+    
+    /// Create a monster entity from its template, scaled for the number of players
+    pub fn create_monster(
+        monster_template: &GameObject,
+        id: String,
+        (x, y): (usize, usize),
+        difficulty_scaling: &DifficultyScaling,
+        player_count: usize,
+        depth: u32,
+        rng: &mut impl Rng,
+    ) -> Entity {
+        let tier = monster_template.tier.unwrap_or(1);
+        let max_health = difficulty_scaling.scale_health(monster_template.health.unwrap_or(50), player_count);
+        let max_health = difficulty_scaling.depth.scale_health(max_health, tier, depth);
+        // Attack can be top-level field or in properties map
+        let attack = monster_template.attack
+            .or_else(|| {
+                monster_template.properties
+                    .get("attack")
+                    .and_then(|s| s.parse::<i32>().ok())
+            })
+            .unwrap_or(5);
+        let attack = difficulty_scaling.scale_attack(attack, player_count);
+        let attack = difficulty_scaling.depth.scale_attack(attack, tier, depth);
+        
+        let defense = monster_template.defense
+            .or_else(|| {
+                monster_template.properties
+                    .get("defense")
+                    .and_then(|s| s.parse::<i32>().ok())
+            })
+            .unwrap_or(0);
+        
+        let attack_spread = monster_template.attack_spread_percent
+            .or_else(|| {
+                monster_template.properties
+                    .get("attack_spread_percent")
+                    .and_then(|s| s.parse::<u32>().ok())
+            })
+            .unwrap_or(20);
+        
+        let crit_chance = monster_template.crit_chance_percent
+            .or_else(|| {
+                monster_template.properties
+                    .get("crit_chance_percent")
+                    .and_then(|s| s.parse::<u32>().ok())
+            })
+            .unwrap_or(0);
+        
+        let crit_damage = monster_template.crit_damage_percent
+            .or_else(|| {
+                monster_template.properties
+                    .get("crit_damage_percent")
+                    .and_then(|s| s.parse::<u32>().ok())
+            })
+            .unwrap_or(150);  // Default 150% crit damage
+        
+        let mut monster = Entity::new(
+            id,
+            x,
+            y,
+            monster_template.id.clone(),
+            attack,
+            defense,
+            attack_spread,
+            crit_chance,
+            crit_damage,
+            max_health,
+            EntityController::AI,
+        );
+        monster.noclip = monster_template.noclip.unwrap_or(false);
+        monster.set_damage_traits(monster_template);
+        // Each monster picks one of its template's color variants, if it has any
+        monster.tint = monster_template.tint_variants.as_ref()
+            .and_then(|variants| variants.choose(rng))
+            .copied();
+        monster
+    }
+    
+    /// Place stairs up on the walkable tile closest to the player spawn (but not on it)
+    pub fn place_stairs_up(
+        dungeon: &Dungeon,
+        player_x: usize,
+        player_y: usize,
+        stairs_pos: Option<(usize, usize)>,
+        object_registry: &GameObjectRegistry,
+    ) -> Option<(usize, usize)> {
+        object_registry.get_object("stairs_up")?;
+        
+        for radius in 1..=5i32 {
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let x = player_x as i32 + dx;
+                    let y = player_y as i32 + dy;
+                    if x < 0 || y < 0 {
+                        continue;
+                    }
+                    let pos = (x as usize, y as usize);
+                    if dungeon.is_walkable(pos.0, pos.1) && Some(pos) != stairs_pos {
+                        return Some(pos);
                     }
                 }
             }
@@ -346,6 +834,39 @@ impl MapGenerator {
         None
     }
     
+    /// Build a patrol route that loops around the corners of a room.
+    /// Rooms are oval, so each corner snaps to the nearest walkable tile inside the room.
+    pub fn patrol_route_for_room(dungeon: &Dungeon, room: &Room) -> Vec<(usize, usize)> {
+        let corners = [
+            (room.x, room.y),
+            (room.x + room.width - 1, room.y),
+            (room.x + room.width - 1, room.y + room.height - 1),
+            (room.x, room.y + room.height - 1),
+        ];
+        
+        let mut route: Vec<(usize, usize)> = Vec::new();
+        for (corner_x, corner_y) in corners {
+            let mut closest: Option<((usize, usize), usize)> = None;
+            for y in room.y..room.y + room.height {
+                for x in room.x..room.x + room.width {
+                    if !dungeon.is_walkable(x, y) {
+                        continue;
+                    }
+                    let distance = x.abs_diff(corner_x) + y.abs_diff(corner_y);
+                    if closest.is_none_or(|(_, best)| distance < best) {
+                        closest = Some(((x, y), distance));
+                    }
+                }
+            }
+            if let Some((waypoint, _)) = closest {
+                if !route.contains(&waypoint) {
+                    route.push(waypoint);
+                }
+            }
+        }
+        route
+    }
+    
     /// Spawn monsters in all rooms (for restart_level)
     pub fn spawn_monsters(
         dungeon: &Dungeon,
@@ -356,7 +877,6 @@ impl MapGenerator {
     ) {
         let monster_templates = object_registry.get_monster_characters();
         if !monster_templates.is_empty() {
-            use rand::Rng;
             let mut rng = rand::thread_rng();
             let mut monster_id_counter = 0;
             
@@ -383,7 +903,7 @@ impl MapGenerator {
                     let (monster_x, monster_y) = valid_positions[rng.gen_range(0..valid_positions.len())];
                     let monster_template = &monster_templates[rng.gen_range(0..monster_templates.len())];
                     
-                    let monster = Entity::new(
+                    let mut monster = Entity::new(
                         format!("monster_{}", monster_id_counter),
                         monster_x,
                         monster_y,
@@ -396,6 +916,8 @@ impl MapGenerator {
                         monster_template.health.unwrap_or(20),
                         EntityController::AI,
                     );
+                    monster.set_damage_traits(monster_template);
+                    Self::assign_unique_name(&mut monster, monster_template, entities, &mut rng);
                     entities.push(monster);
                     monster_id_counter += 1;
                 }
@@ -419,7 +941,6 @@ impl MapGenerator {
             .collect();
         
         if !chest_templates.is_empty() {
-            use rand::Rng;
             let mut rng = rand::thread_rng();
             let mut chest_id_counter = 0;
             
@@ -457,6 +978,7 @@ impl MapGenerator {
                             y: chest_y,
                             object_id: chest_template.id.clone(),
                             is_open: false,  // Always spawn closed
+                            is_active: false,
                         };
                         chests.push(chest);
                         chest_id_counter += 1;
@@ -467,3 +989,403 @@ impl MapGenerator {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_default_config;
+    use crate::config::{DepthScaling, StatDefaults};
+    use crate::game_object::Tint;
+    use crate::dungeon::tests::dungeon_from;
+    use crate::game_state::tests::level;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    
+    /// The default config's objects plus a chest, and a vault door locked with a key
+    fn registry() -> GameObjectRegistry {
+        let mut config = create_default_config();
+        config.game_objects.push(GameObject::new("chest".to_string(), "Chest".to_string(), "chest".to_string(), false, 0, 0));
+        config.game_objects.push(GameObject::new("vault_key".to_string(), "Vault Key".to_string(), "key".to_string(), true, 0, 0));
+        let mut door = GameObject::new("vault_door".to_string(), "Vault Door".to_string(), "door".to_string(), false, 0, 0);
+        door.requires_key = Some("vault_key".to_string());
+        config.game_objects.push(door);
+        GameObjectRegistry::load_from_config(&config)
+    }
+    
+    /// A hand-drawn map with the spawn room A at (1, 1) to (3, 3), the vault room V at
+    /// (8, 1) to (11, 3), the spawn in the middle of A and the stairs in its corner
+    fn vault_map(rows: &[&str]) -> GeneratedMap {
+        let mut dungeon = dungeon_from(rows);
+        dungeon.rooms = vec![
+            Room { x: 1, y: 1, width: 3, height: 3 },
+            Room { x: 8, y: 1, width: 4, height: 3 },
+        ];
+        GeneratedMap {
+            dungeon,
+            entities: Vec::new(),
+            consumables: Vec::new(),
+            chests: Vec::new(),
+            stairs_position: Some((1, 1)),
+            stairs_up_position: None,
+            spawn_position: (2, 2),
+            vault_room: Some(1),
+        }
+    }
+    
+    fn doors(map: &GeneratedMap) -> Vec<(usize, usize, bool)> {
+        map.chests.iter().map(|door| (door.x, door.y, door.is_open)).collect()
+    }
+    
+    #[test]
+    fn a_vault_room_holds_more_chests_than_any_other_room() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let level = level("chest_count = 2\nvault_chance_percent = 100\nvault_chests = 3");
+        for seed in 0..10 {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+            let vault = map.vault_room.expect("the vault chance is 100%");
+            let chests_in = |room: &Room| map.chests.iter().filter(|c| room.contains(c.x, c.y)).count();
+            let vault_chests = chests_in(&map.dungeon.rooms[vault]);
+            for (i, room) in map.dungeon.rooms.iter().enumerate().filter(|&(i, _)| i != vault) {
+                assert!(vault_chests > chests_in(room), "seed {}: room {} has as many chests as the vault", seed, i);
+            }
+        }
+    }
+    
+    #[test]
+    fn a_corridor_straight_into_the_vault_gets_one_door_and_key() {
+        let mut map = vault_map(&[
+            "##############",
+            "#...####....##",
+            "#..........###",
+            "#...####....##",
+            "##############",
+        ]);
+        MapGenerator::lock_vault(&mut map, 1, "vault_door", 1, &registry(), &mut StdRng::seed_from_u64(1));
+        
+        assert_eq!(doors(&map), vec![(7, 2, false)]);
+        assert_eq!(map.consumables.len(), 1);
+        assert!(map.dungeon.rooms[0].contains(map.consumables[0].x, map.consumables[0].y));
+    }
+    
+    #[test]
+    fn a_corridor_along_the_vault_wall_is_shut_where_it_turns_away() {
+        let mut map = vault_map(&[
+            "##############",
+            "#...####....##",
+            "#...####....##",
+            "#...####....##",
+            "#...........##",
+            "##############",
+        ]);
+        MapGenerator::lock_vault(&mut map, 1, "vault_door", 1, &registry(), &mut StdRng::seed_from_u64(1));
+        
+        assert_eq!(doors(&map), vec![(7, 4, false)]);
+        assert_eq!(map.consumables.len(), 1);
+    }
+    
+    #[test]
+    fn every_vault_door_gets_its_own_key() {
+        let mut map = vault_map(&[
+            "##############",
+            "#...####....##",
+            "#..........###",
+            "#...####....##",
+            "##.######.####",
+            "##........####",
+            "##############",
+        ]);
+        MapGenerator::lock_vault(&mut map, 1, "vault_door", 1, &registry(), &mut StdRng::seed_from_u64(1));
+        
+        assert_eq!(doors(&map), vec![(7, 2, false), (9, 4, false)]);
+        let keys: Vec<(usize, usize)> = map.consumables.iter().map(|key| (key.x, key.y)).collect();
+        assert_eq!(keys.len(), 2);
+        assert_ne!(keys[0], keys[1]);
+        assert!(keys.iter().all(|&(x, y)| map.dungeon.rooms[0].contains(x, y)));
+    }
+    
+    #[test]
+    fn a_vault_between_the_spawn_and_the_stairs_stays_open() {
+        // The only way to the stairs room on the right is through the vault
+        let mut map = vault_map(&[
+            "##################",
+            "#...####....##...#",
+            "#.................#",
+            "#...####....##...#",
+            "##################",
+        ]);
+        map.stairs_position = Some((15, 2));
+        MapGenerator::lock_vault(&mut map, 1, "vault_door", 1, &registry(), &mut StdRng::seed_from_u64(1));
+        
+        assert!(map.chests.is_empty());
+        assert!(map.consumables.is_empty());
+    }
+    
+    #[test]
+    fn more_players_face_more_and_tougher_monsters() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let level = level("min_monsters_per_room = 1");
+        let scaling = DifficultyScaling {
+            enabled: true,
+            health_percent_per_player: 50,
+            monster_count_percent_per_player: 100,
+            ..Default::default()
+        };
+        let totals = |player_count, seed| {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &scaling, player_count, 100, &mut StdRng::seed_from_u64(seed));
+            let monsters: Vec<&Entity> = map.entities.iter().filter(|e| e.controller == EntityController::AI).collect();
+            (monsters.len(), monsters.iter().map(|e| e.max_health).sum::<u32>())
+        };
+        for seed in 0..5 {
+            let (solo_count, solo_health) = totals(1, seed);
+            let (party_count, party_health) = totals(4, seed);
+            assert!(party_count > solo_count, "seed {}: {} monsters for 4 players, {} for 1", seed, party_count, solo_count);
+            assert!(party_health > solo_health);
+        }
+    }
+    
+    #[test]
+    fn a_themed_level_only_uses_its_tiles() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let level = level("tile_theme = { floor_tiles = { floor_stone = 3 }, wall_tiles = { wall_stone_top = 1 } }");
+        for seed in 0..3 {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+            for tile in map.dungeon.tiles.iter().flatten() {
+                assert!(["floor_stone", "wall_stone_top"].contains(&tile.tile_id.as_str()), "seed {}: unthemed tile {}", seed, tile.tile_id);
+            }
+        }
+    }
+    
+    #[test]
+    fn players_spawn_inside_the_first_room() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        for seed in 0..10 {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level("")), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+            let (x, y) = map.spawn_position;
+            assert!(map.dungeon.rooms[0].contains(x, y), "seed {}: spawn ({}, {}) is outside the first room", seed, x, y);
+            assert!(map.dungeon.is_walkable(x, y));
+        }
+    }
+    
+    #[test]
+    fn a_higher_walkable_target_gives_more_open_levels() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let average_walkable = |target_percent: u32| {
+            let level = level(&format!("target_walkable_percent = {}", target_percent));
+            let total: f32 = (0..5)
+                .map(|seed| {
+                    let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+                    map.dungeon.walkable_percent()
+                })
+                .sum();
+            total / 5.0
+        };
+        let cramped = average_walkable(10);
+        let open = average_walkable(40);
+        assert!(open > cramped, "{:.1}% walkable for a 40% target, {:.1}% for 10%", open, cramped);
+    }
+    
+    #[test]
+    fn spawned_monsters_get_distinct_names() {
+        let mut config = create_default_config();
+        for obj in config.game_objects.iter_mut().filter(|obj| obj.id == "orc") {
+            obj.name_pool = Some(vec!["Grolok".to_string(), "Mugra".to_string()]);
+        }
+        let object_registry = GameObjectRegistry::load_from_config(&config);
+        let tile_registry = TileRegistry::load_from_config(&config);
+        let level = level("min_monsters_per_room = 2\nmax_monsters_per_room = 2");
+        let map = MapGenerator::generate_map(&tile_registry, &object_registry, Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(1));
+        
+        // More orcs than names in the pool still get a name of their own
+        let names: Vec<String> = map.entities.iter().map(|e| e.unique_name.clone().expect("named from the pool")).collect();
+        assert!(names.len() > 2);
+        assert!(names.iter().all(|name| name.starts_with("Orc: ")));
+        let distinct: std::collections::HashSet<&String> = names.iter().collect();
+        assert_eq!(distinct.len(), names.len());
+    }
+    
+    #[test]
+    fn monsters_without_a_name_pool_go_by_their_object_name() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let registry = registry();
+        let map = MapGenerator::generate_map(&tile_registry, &registry, Some(&level("min_monsters_per_room = 1")), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(1));
+        assert!(!map.entities.is_empty());
+        assert!(map.entities.iter().all(|e| e.unique_name.is_none() && e.display_name(&registry) == "Orc"));
+    }
+    
+    /// Smallest Chebyshev distance between any two of `positions`
+    fn closest_pair(positions: &[(usize, usize)]) -> usize {
+        let mut closest = usize::MAX;
+        for (i, a) in positions.iter().enumerate() {
+            for b in &positions[i + 1..] {
+                closest = closest.min(a.0.abs_diff(b.0).max(a.1.abs_diff(b.1)));
+            }
+        }
+        closest
+    }
+    
+    #[test]
+    fn chests_and_monsters_keep_their_spacing() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let level = level("chest_count = 6\nmin_chest_spacing = 4\nmin_monsters_per_room = 3\nmax_monsters_per_room = 3\nmin_monster_spacing = 3");
+        for seed in 0..5 {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+            let chests: Vec<(usize, usize)> = map.chests.iter().map(|c| (c.x, c.y)).collect();
+            let monsters: Vec<(usize, usize)> = map.entities.iter().map(|e| (e.x, e.y)).collect();
+            assert!(chests.len() > 1 && monsters.len() > 1);
+            assert!(closest_pair(&chests) >= 4, "seed {}: chests too close", seed);
+            assert!(closest_pair(&monsters) >= 3, "seed {}: monsters too close", seed);
+        }
+    }
+    
+    #[test]
+    fn deeper_levels_spawn_stronger_monsters() {
+        let orc = registry().get_object("orc").unwrap().clone();
+        let spawn = |scaling: &DifficultyScaling, depth| {
+            let monster = MapGenerator::create_monster(&orc, "orc_0".to_string(), (1, 1), scaling, 1, depth, &mut StdRng::seed_from_u64(1));
+            (monster.max_health, monster.attack)
+        };
+        let base = (orc.health.unwrap_or(50), orc.attack.unwrap_or(5));
+        
+        assert_eq!(spawn(&DifficultyScaling::default(), 5), base);
+        let scaling = DifficultyScaling {
+            depth: DepthScaling { enabled: true, health_percent_per_level: 25, attack_percent_per_level: 50 },
+            ..Default::default()
+        };
+        assert_eq!(spawn(&scaling, 1), base);
+        assert_eq!(spawn(&scaling, 5), (base.0 * 2, base.1 * 3));  // 4 levels below its tier 1
+    }
+    
+    #[test]
+    fn nothing_spawns_within_the_safe_radius_of_the_spawn() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let level = level("min_monsters_per_room = 3\nmax_monsters_per_room = 3\nspawn_safe_radius = 4");
+        for seed in 0..10 {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+            let (spawn_x, spawn_y) = map.spawn_position;
+            let near_spawn = |&(x, y): &(usize, usize)| x.abs_diff(spawn_x).max(y.abs_diff(spawn_y)) <= 4;
+            assert!(map.entities.iter().any(|e| e.controller == EntityController::AI));
+            assert!(!map.entities.iter().map(|e| (e.x, e.y)).any(|pos| near_spawn(&pos)), "seed {}: a monster spawned near the spawn", seed);
+            assert!(!map.chests.iter().map(|c| (c.x, c.y)).any(|pos| near_spawn(&pos)), "seed {}: a chest spawned near the spawn", seed);
+        }
+    }
+    
+    /// Three rooms in a row joined by a corridor, the spawn room A first: A centered on
+    /// (2, 2), B on (7, 2) and C on (13, 2)
+    fn three_rooms() -> Dungeon {
+        let mut dungeon = dungeon_from(&[
+            "################",
+            "#...##...###...#",
+            "#..............#",
+            "#...##...###...#",
+            "################",
+        ]);
+        dungeon.rooms = vec![
+            Room { x: 1, y: 1, width: 3, height: 3 },
+            Room { x: 6, y: 1, width: 3, height: 3 },
+            Room { x: 12, y: 1, width: 3, height: 3 },
+        ];
+        dungeon
+    }
+    
+    #[test]
+    fn stairs_go_where_the_level_places_them() {
+        let dungeon = three_rooms();
+        let registry = registry();
+        let place = |placement, seed| MapGenerator::place_stairs(&dungeon, 2, 2, &registry, placement, &mut StdRng::seed_from_u64(seed));
+        
+        assert_eq!(place(StairsPlacement::Farthest, 1), Some((13, 2)));
+        assert_eq!(place(StairsPlacement::Nearest, 1), Some((7, 2)));
+        let random: BTreeSet<Option<(usize, usize)>> = (0..20).map(|seed| place(StairsPlacement::RandomRoom, seed)).collect();
+        assert_eq!(random, BTreeSet::from([Some((7, 2)), Some((13, 2))]));  // Never the spawn room
+        assert_eq!(place(StairsPlacement::At { x: 10, y: 2 }, 1), Some((10, 2)));
+        assert_eq!(place(StairsPlacement::At { x: 4, y: 1 }, 1), Some((3, 1)));  // A wall, so the nearest floor
+    }
+    
+    #[test]
+    fn corridors_use_the_themes_corridor_tile() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let level = level("tile_theme = { floor_tiles = { floor_stone = 1 }, wall_tiles = { wall_stone_top = 1 }, corridor_tile_id = \"floor_dark\" }");
+        assert!(level.tile_theme.as_ref().unwrap().validate(&tile_registry).is_empty());
+        for seed in 0..3 {
+            let map = MapGenerator::generate_map(&tile_registry, &registry(), Some(&level), &DifficultyScaling::default(), 1, 100, &mut StdRng::seed_from_u64(seed));
+            let mut corridor_tiles = 0;
+            for (y, row) in map.dungeon.tiles.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate().filter(|(_, tile)| tile.walkable) {
+                    let in_room = map.dungeon.rooms.iter().any(|room| room.contains(x, y));
+                    let expected = if in_room { "floor_stone" } else { "floor_dark" };
+                    assert_eq!(tile.tile_id, expected, "seed {}: tile ({}, {})", seed, x, y);
+                    corridor_tiles += usize::from(!in_room);
+                }
+            }
+            assert!(corridor_tiles > 0);
+        }
+    }
+    
+    #[test]
+    fn a_corridor_tile_must_be_an_existing_floor() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let errors = |corridor: &str| {
+            level(&format!("tile_theme = {{ corridor_tile_id = \"{}\" }}", corridor)).tile_theme.unwrap().validate(&tile_registry)
+        };
+        assert_eq!(errors("lava"), vec!["corridor tile 'lava' does not exist".to_string()]);
+        assert_eq!(errors("wall_stone_top"), vec!["'wall_stone_top' cannot be used as a corridor tile".to_string()]);
+    }
+    
+    #[test]
+    fn a_crowded_layout_is_regenerated_until_min_rooms_fit() {
+        let tile_registry = TileRegistry::load_from_config(&create_default_config());
+        let options = DungeonOptions { min_rooms: 18, max_rooms: 18, ..Default::default() };
+        let rooms = |retries, seed| MapGenerator::generate_with_min_rooms(&tile_registry, &options, retries, &mut StdRng::seed_from_u64(seed)).rooms.len();
+        
+        assert!((0..20).any(|seed| rooms(0, seed) < 18), "18 rooms should not always fit on the first try");
+        for seed in 0..20 {
+            assert!(rooms(10, seed) >= 18, "seed {}", seed);
+        }
+        
+        // A minimum that can never fit settles for the fullest layout instead of looping
+        let impossible = DungeonOptions { min_rooms: 200, max_rooms: 200, ..Default::default() };
+        let dungeon = MapGenerator::generate_with_min_rooms(&tile_registry, &impossible, 3, &mut StdRng::seed_from_u64(1));
+        assert!(!dungeon.rooms.is_empty() && dungeon.rooms.len() < 200);
+    }
+    
+    #[test]
+    fn spawned_monsters_get_assorted_tints() {
+        let mut orc = registry().get_object("orc").unwrap().clone();
+        let variants = vec![
+            Tint { r: 1.0, g: 0.6, b: 0.6 },
+            Tint { r: 0.6, g: 1.0, b: 0.6 },
+            Tint { r: 0.6, g: 0.6, b: 1.0 },
+        ];
+        orc.tint_variants = Some(variants.clone());
+        let mut rng = StdRng::seed_from_u64(1);
+        let tints: Vec<Tint> = (0..30)
+            .map(|i| MapGenerator::create_monster(&orc, format!("orc_{}", i), (1, 1), &DifficultyScaling::default(), 1, 1, &mut rng).tint.unwrap())
+            .collect();
+        
+        assert!(tints.iter().all(|tint| variants.contains(tint)));
+        assert!(variants.iter().all(|variant| tints.contains(variant)));
+        
+        // Monsters without variants are left untinted
+        orc.tint_variants = None;
+        assert!(MapGenerator::create_monster(&orc, "orc".to_string(), (1, 1), &DifficultyScaling::default(), 1, 1, &mut rng).tint.is_none());
+    }
+    
+    #[test]
+    fn monsters_without_their_own_stats_use_the_configured_defaults() {
+        let mut config = create_default_config();
+        config.defaults.insert("character".to_string(), StatDefaults { attack: Some(9), ..Default::default() });
+        let goblin = GameObject::new("goblin".to_string(), "Goblin".to_string(), "character".to_string(), true, 0, 0);
+        let mut brute = goblin.clone();
+        brute.id = "brute".to_string();
+        brute.attack = Some(12);
+        config.game_objects.extend([goblin, brute]);
+        let registry = GameObjectRegistry::load_from_config(&config);
+        
+        let attack = |object_id: &str| {
+            let template = registry.get_object(object_id).unwrap();
+            MapGenerator::create_monster(template, object_id.to_string(), (1, 1), &DifficultyScaling::default(), 1, 1, &mut StdRng::seed_from_u64(1)).attack
+        };
+        assert_eq!(attack("goblin"), 9);  // Not the built-in 5
+        assert_eq!(attack("brute"), 12);
+    }
+}
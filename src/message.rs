@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::command::CommandSpec;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum MessageType {
@@ -24,6 +25,8 @@ pub struct GameMessage {
     pub target_died: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_crit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<String>,  // Rarity of a picked up item, so the client can color rare finds
 }
 
 // Helper functions to create messages
@@ -44,6 +47,7 @@ impl GameMessage {
             target_health_after: Some(health_after),
             target_died: Some(died),
             is_crit: Some(false),
+            rarity: None,
         }
     }
     
@@ -63,6 +67,7 @@ impl GameMessage {
             target_health_after: Some(health_after),
             target_died: Some(died),
             is_crit: Some(true),
+            rarity: None,
         }
     }
     
@@ -78,6 +83,24 @@ impl GameMessage {
             target_health_after: Some(health_after),
             target_died: Some(false),
             is_crit: None,
+            rarity: None,
+        }
+    }
+    
+    /// An attack that landed (healing is also a Combat message, but never has a crit flag)
+    pub fn is_attack(&self) -> bool {
+        self.message_type == MessageType::Combat && self.is_crit.is_some()
+    }
+    
+    /// Item collected by a player, with how many of it they now carry and its rarity
+    pub fn pickup(item: String, total: u32, rarity: Option<String>) -> Self {
+        let text = match rarity.as_deref() {
+            Some(rarity) if rarity != "common" => format!("Picked up {} ({}) [{}]", item, total, rarity),
+            _ => format!("Picked up {} ({})", item, total),
+        };
+        Self {
+            rarity,
+            ..Self::system(text)
         }
     }
     
@@ -91,6 +114,7 @@ impl GameMessage {
             target_health_after: None,
             target_died: None,
             is_crit: None,
+            rarity: None,
         }
     }
     
@@ -104,6 +128,7 @@ impl GameMessage {
             target_health_after: None,
             target_died: None,
             is_crit: None,
+            rarity: None,
         }
     }
 }
@@ -111,12 +136,56 @@ impl GameMessage {
 // Legacy alias for backward compatibility during transition
 pub type CombatMessage = GameMessage;
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PlayerCommand {
     pub action: String,
     #[serde(default)]
     pub confirm_stairs: Option<bool>,  // Optional confirmation for stairs
     #[serde(default)]
     pub confirm_restart: Option<bool>,  // Optional confirmation for restart after death
+    #[serde(default)]
+    pub confirm_stairs_up: Option<bool>,  // Optional confirmation for going back up to the previous level
+    #[serde(default)]
+    pub new_game: Option<bool>,  // Start a new run after game over (or any time with admin_commands on)
+    #[serde(default)]
+    pub direction: Option<String>,  // Direction for directed actions: "up", "down", "left", "right"
+    #[serde(default)]
+    pub target_x: Option<usize>,  // Target tile for directed actions (alternative to direction)
+    #[serde(default)]
+    pub target_y: Option<usize>,
+    #[serde(default)]
+    pub item: Option<String>,  // Inventory item (object ID) for item actions (None = first usable one)
+    #[serde(default)]
+    pub command_id: Option<String>,  // Client-chosen ID, echoed back as `ack_command_id` in the sender's resulting update
 }
 
+impl PlayerCommand {
+    /// Offset (dx, dy) from `from` that this command is aimed at, taken from
+    /// `direction` if present, otherwise from the target coordinates
+    pub fn direction_delta(&self, from: (usize, usize)) -> Option<(i32, i32)> {
+        if let Some(direction) = &self.direction {
+            return match direction.as_str() {
+                "up" => Some((0, -1)),
+                "down" => Some((0, 1)),
+                "left" => Some((-1, 0)),
+                "right" => Some((1, 0)),
+                _ => None,
+            };
+        }
+        match (self.target_x, self.target_y) {
+            (Some(x), Some(y)) => Some((x as i32 - from.0 as i32, y as i32 - from.1 as i32)),
+            _ => None,
+        }
+    }
+}
+
+/// Non-update messages from the server to a client, tagged by "type"
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Error { message: String },  // Fatal problem with this session - the server closes the socket after sending it
+    Welcome {
+        player_id: String,  // ID assigned to this connection (matches EntityData.id / PlayerData.id)
+        commands: &'static [CommandSpec],  // Every accepted action, same as GET /api/commands
+    },  // First message on a successful join, sent before the initial GameUpdate
+}
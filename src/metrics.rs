@@ -0,0 +1,83 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Server-wide counters for the /metrics endpoint. Atomics, so the socket handlers can
+/// update them without touching the game lock.
+pub struct Metrics {
+    connected_players: AtomicUsize,  // Open websocket connections with a player in the game
+    broadcasts_sent: AtomicU64,  // Game updates pushed to the broadcast channel
+    lagged_resyncs: AtomicU64,  // Times a slow connection missed updates and got a full resync
+    coalesced_updates: AtomicU64,  // Broadcasts merged into another update instead of being sent on their own
+    commands_handled: AtomicU64,  // Player commands run through handle_command
+    command_time_micros: AtomicU64,  // Total time spent in handle_command
+}
+
+pub static METRICS: Metrics = Metrics {
+    connected_players: AtomicUsize::new(0),
+    broadcasts_sent: AtomicU64::new(0),
+    lagged_resyncs: AtomicU64::new(0),
+    coalesced_updates: AtomicU64::new(0),
+    commands_handled: AtomicU64::new(0),
+    command_time_micros: AtomicU64::new(0),
+};
+
+/// Point-in-time view of the metrics, combined with a few numbers read from the game state
+#[derive(Serialize, Debug)]
+pub struct MetricsSnapshot {
+    pub connected_players: usize,
+    pub current_turn: u32,
+    pub current_level: u32,
+    pub entity_count: usize,  // Players and monsters, alive or not
+    pub monster_count: usize,  // Alive monsters
+    pub broadcasts_sent: u64,
+    pub lagged_resyncs: u64,
+    pub coalesced_updates: u64,  // Summed over connections
+    pub commands_handled: u64,
+    pub avg_command_micros: u64,  // Average handle_command time (0 before the first command)
+}
+
+impl Metrics {
+    pub fn player_connected(&self) {
+        self.connected_players.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn player_disconnected(&self) {
+        self.connected_players.fetch_sub(1, Ordering::Relaxed);
+    }
+    
+    pub fn broadcast_sent(&self) {
+        self.broadcasts_sent.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn lagged_resync(&self) {
+        self.lagged_resyncs.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn update_coalesced(&self) {
+        self.coalesced_updates.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn command_handled(&self, elapsed: Duration) {
+        self.commands_handled.fetch_add(1, Ordering::Relaxed);
+        self.command_time_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+    
+    /// Snapshot of the counters; the game-state numbers are filled in by the caller
+    pub fn snapshot(&self, current_turn: u32, current_level: u32, entity_count: usize, monster_count: usize) -> MetricsSnapshot {
+        let commands_handled = self.commands_handled.load(Ordering::Relaxed);
+        let command_time_micros = self.command_time_micros.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            connected_players: self.connected_players.load(Ordering::Relaxed),
+            current_turn,
+            current_level,
+            entity_count,
+            monster_count,
+            broadcasts_sent: self.broadcasts_sent.load(Ordering::Relaxed),
+            lagged_resyncs: self.lagged_resyncs.load(Ordering::Relaxed),
+            coalesced_updates: self.coalesced_updates.load(Ordering::Relaxed),
+            commands_handled,
+            avg_command_micros: command_time_micros.checked_div(commands_handled).unwrap_or(0),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::game_object::{GameObject, GameObjectRegistry};
+
+/// A stack of identical items in a player's inventory
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InventoryItem {
+    pub object_id: String,
+    pub count: u32,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rarity: Option<String>,  // The item's rarity, for coloring it in the client
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub durability: Option<u32>,  // Uses left before the top item of the stack breaks (None = indestructible)
+}
+
+/// A multi-turn walk toward a destination (auto_move_to), advanced one step per command
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoMove {
+    pub target: (usize, usize),
+    pub health: u32,  // Health after the last step - losing any interrupts the walk
+    pub monsters_in_view: Vec<String>,  // Monster IDs already in view - only new ones interrupt
+}
+
+/// Everything about a player that isn't position or combat, kept in `GameState::players`
+/// by player ID next to the player's Entity (which monsters share)
+#[derive(Clone, Debug, Default)]
+pub struct PlayerState {
+    pub inventory: Vec<InventoryItem>,  // Carried item stacks (throwables, keys and spare equipment)
+    pub weapon: Option<InventoryItem>,  // Equipped weapon (equipment with an attack value), adding to the player's attack
+    pub armor: Option<InventoryItem>,  // Equipped armor (equipment with a defense value), adding to the player's defense
+    pub auto_move: Option<AutoMove>,  // Destination being auto-moved to (None = not auto-moving)
+    pub reveals_loot: bool,  // Treasure sense - receives loot positions in rooms not yet explored
+    pub discovered_rooms: HashSet<usize>,  // Room indices entered on the current level
+    pub confirmed_stairs: bool,  // Wants to descend to the next level
+    pub confirmed_stairs_up: bool,  // Wants to go back up a level
+    pub confirmed_restart: bool,  // Wants to restart after the party died
+    pub ready: bool,  // Marked ready in the lobby
+}
+
+impl PlayerState {
+    /// Fresh state for a player spawned from `template`
+    pub fn new(template: &GameObject) -> Self {
+        Self {
+            reveals_loot: template.reveals_loot.unwrap_or(false),
+            ..Self::default()
+        }
+    }
+    
+    /// Clear what only applies to the current level (on level change or restart)
+    pub fn reset_for_level(&mut self) {
+        self.auto_move = None;
+        self.discovered_rooms.clear();
+        self.confirmed_stairs = false;
+        self.confirmed_stairs_up = false;
+        self.confirmed_restart = false;
+    }
+    
+    /// Add one item, topping up an existing stack of it that has room (up to its `max_stack`)
+    /// before starting a new one
+    pub fn add_to_inventory(&mut self, item: &GameObject) {
+        let max_stack = item.max_stack.unwrap_or(1).max(1);
+        let open_stack = self.inventory.iter_mut()
+            .find(|stack| stack.object_id == item.id && stack.count < max_stack);
+        match open_stack {
+            Some(stack) => stack.count += 1,
+            None => self.inventory.push(InventoryItem {
+                object_id: item.id.clone(),
+                count: 1,
+                rarity: item.rarity.clone(),
+                durability: item.durability,
+            }),
+        }
+    }
+    
+    /// How many of an item are carried, across all its stacks
+    pub fn inventory_count(&self, object_id: &str) -> u32 {
+        self.inventory.iter()
+            .filter(|item| item.object_id == object_id)
+            .map(|item| item.count)
+            .sum()
+    }
+    
+    /// Use up one item from the stack at `slot`, dropping the stack once it's empty
+    pub fn take_from_inventory(&mut self, slot: usize) {
+        self.inventory[slot].count -= 1;
+        if self.inventory[slot].count == 0 {
+            self.inventory.remove(slot);
+        }
+    }
+    
+    /// Equip an equipment item if its slot is free: the weapon slot for items with an attack
+    /// value, otherwise the armor slot for ones with a defense value. Returns whether it was
+    /// equipped (an item with neither, or for a taken slot, is left to the inventory).
+    pub fn equip(&mut self, item: &GameObject) -> bool {
+        let slot = if item.attack.is_some() {
+            &mut self.weapon
+        } else if item.defense.is_some() {
+            &mut self.armor
+        } else {
+            return false;
+        };
+        if slot.is_some() {
+            return false;
+        }
+        *slot = Some(InventoryItem {
+            object_id: item.id.clone(),
+            count: 1,
+            rarity: item.rarity.clone(),
+            durability: item.durability,
+        });
+        true
+    }
+    
+    /// Attack and defense the equipped weapon and armor add (0 for an empty slot)
+    pub fn equipment_bonus(&self, object_registry: &GameObjectRegistry) -> (i32, i32) {
+        let stat = |slot: &Option<InventoryItem>, stat: fn(&GameObject) -> Option<i32>| {
+            slot.as_ref()
+                .and_then(|item| object_registry.get_object(&item.object_id))
+                .and_then(stat)
+                .unwrap_or(0)
+        };
+        (stat(&self.weapon, |obj| obj.attack), stat(&self.armor, |obj| obj.defense))
+    }
+    
+    /// Wear the equipped weapon (`weapons`, when the player attacks) or armor (when they're
+    /// hit) down by one use. At zero durability it breaks and its slot empties. Returns the
+    /// object ID of the item that broke, if any.
+    pub fn wear_equipment(&mut self, weapons: bool) -> Option<String> {
+        let slot = if weapons { &mut self.weapon } else { &mut self.armor };
+        let durability = slot.as_ref()?.durability?;
+        if durability > 1 {
+            slot.as_mut()?.durability = Some(durability - 1);
+            return None;
+        }
+        slot.take().map(|item| item.object_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sword(durability: Option<u32>) -> GameObject {
+        let mut sword = GameObject::new("sword".to_string(), "Sword".to_string(), "equipment".to_string(), true, 0, 0);
+        sword.attack = Some(7);
+        sword.durability = durability;
+        sword
+    }
+    
+    #[test]
+    fn equipping_fills_a_free_slot_only() {
+        let mut player = PlayerState::default();
+        assert!(player.equip(&sword(Some(3))));
+        assert!(!player.equip(&sword(Some(3))));
+        assert_eq!(player.weapon.as_ref().map(|item| item.durability), Some(Some(3)));
+        assert!(player.armor.is_none());
+    }
+    
+    #[test]
+    fn a_weapon_breaks_after_its_durability_runs_out() {
+        let mut player = PlayerState::default();
+        player.equip(&sword(Some(3)));
+        // A spare in the inventory isn't worn
+        player.add_to_inventory(&sword(Some(3)));
+        
+        assert_eq!(player.wear_equipment(true), None);
+        assert_eq!(player.wear_equipment(false), None);
+        assert_eq!(player.wear_equipment(true), None);
+        assert_eq!(player.wear_equipment(true), Some("sword".to_string()));
+        assert!(player.weapon.is_none());
+        assert_eq!(player.inventory[0].durability, Some(3));
+    }
+    
+    #[test]
+    fn indestructible_equipment_never_breaks() {
+        let mut player = PlayerState::default();
+        player.equip(&sword(None));
+        for _ in 0..100 {
+            assert_eq!(player.wear_equipment(true), None);
+        }
+        assert!(player.weapon.is_some());
+    }
+    
+    fn potion(max_stack: Option<u32>) -> GameObject {
+        let mut potion = GameObject::new("health_potion".to_string(), "Health Potion".to_string(), "consumable".to_string(), true, 0, 0);
+        potion.max_stack = max_stack;
+        potion
+    }
+    
+    fn stack_counts(player: &PlayerState) -> Vec<u32> {
+        player.inventory.iter().map(|stack| stack.count).collect()
+    }
+    
+    #[test]
+    fn picked_up_items_stack_up_to_their_max() {
+        let mut player = PlayerState::default();
+        for _ in 0..4 {
+            player.add_to_inventory(&potion(Some(3)));
+        }
+        assert_eq!(stack_counts(&player), vec![3, 1]);
+        assert_eq!(player.inventory_count("health_potion"), 4);
+        
+        // Without a max_stack, every item gets its own slot
+        let mut player = PlayerState::default();
+        player.add_to_inventory(&potion(None));
+        player.add_to_inventory(&potion(None));
+        assert_eq!(stack_counts(&player), vec![1, 1]);
+    }
+    
+    #[test]
+    fn using_an_item_takes_one_from_its_stack() {
+        let mut player = PlayerState::default();
+        for _ in 0..3 {
+            player.add_to_inventory(&potion(Some(3)));
+        }
+        player.take_from_inventory(0);
+        assert_eq!(stack_counts(&player), vec![2]);
+        player.take_from_inventory(0);
+        player.take_from_inventory(0);
+        assert!(player.inventory.is_empty());
+    }
+}
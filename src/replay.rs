@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::game_state::GameState;
+use crate::message::PlayerCommand;
+
+/// Directory stopped recordings are written to
+pub const REPLAY_DIR: &str = "replays";
+
+/// What happened in one recorded step
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayAction {
+    Join,  // The player connected
+    Leave,  // The player disconnected
+    NewGame,  // An admin restarted the run
+    Command(PlayerCommand),  // The player's command went through handle_command
+}
+
+/// One recorded step, with the game position it happened at
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayEntry {
+    pub turn: u32,  // Turn number before the step
+    pub level: u32,  // Level being played
+    pub player_id: String,
+    pub action: ReplayAction,
+}
+
+/// A run recorded between `/api/replay/start` and `/api/replay/stop`. Recording restarts the
+/// run on a fresh seed, so the seed, the players connected at the start and every step after
+/// that are enough to play the run again with the same outcome (given the same config).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: u64,  // Seed the run was restarted on
+    pub players: Vec<String>,  // Players connected when the recording started, in join order
+    pub entries: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, players: Vec<String>) -> Self {
+        Self {
+            seed,
+            players,
+            entries: Vec::new(),
+        }
+    }
+    
+    pub fn record(&mut self, turn: u32, level: u32, player_id: &str, action: ReplayAction) {
+        self.entries.push(ReplayEntry {
+            turn,
+            level,
+            player_id: player_id.to_string(),
+            action,
+        });
+    }
+    
+    /// Write the recording as JSON into REPLAY_DIR, returning the file's path
+    pub fn save(&self) -> Result<String, Box<dyn std::error::Error>> {
+        fs::create_dir_all(REPLAY_DIR)?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let path = format!("{}/replay_{}.json", REPLAY_DIR, timestamp);
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+    
+    /// Play the run again on `game`, set up from the same config as the recorded one: the
+    /// starting players join, the run restarts on the recorded seed, then every step is applied
+    /// in order. Fails at the first step whose recorded turn or level doesn't match the game,
+    /// which means the replay has diverged (e.g. the config changed since).
+    pub fn play(&self, game: &mut GameState) -> Result<(), String> {
+        for player_id in &self.players {
+            game.add_player(player_id.clone());
+        }
+        game.new_game_on(self.seed);
+        
+        for (i, entry) in self.entries.iter().enumerate() {
+            if (game.current_turn, game.current_level) != (entry.turn, entry.level) {
+                return Err(format!(
+                    "Replay diverged at step {}: recorded on turn {} of level {}, but the game is on turn {} of level {}",
+                    i, entry.turn, entry.level, game.current_turn, game.current_level
+                ));
+            }
+            match &entry.action {
+                ReplayAction::Join => {
+                    game.add_player(entry.player_id.clone());
+                }
+                ReplayAction::Leave => game.remove_player(&entry.player_id),
+                ReplayAction::NewGame => {
+                    game.new_game();
+                }
+                ReplayAction::Command(command) => {
+                    game.handle_command(command, &entry.player_id);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_default_config;
+    use crate::game_state::tests::command;
+    
+    /// Everything a replay has to reproduce: where the run is and the state of every entity
+    fn snapshot(game: &GameState) -> (u32, u32, serde_json::Value) {
+        (game.current_turn, game.current_level, serde_json::to_value(&game.entities).unwrap())
+    }
+    
+    #[test]
+    fn replay_reproduces_the_final_state() {
+        let config = create_default_config();
+        let mut game = GameState::from_config(&config);
+        game.add_player("p1".to_string());
+        game.start_recording();
+        
+        let actions = ["move_right", "move_down", "move_left", "move_up", "taunt", "move_right", "move_right", "move_down"];
+        for action in actions.iter().cycle().take(60) {
+            game.handle_command(&command(action), "p1");
+        }
+        game.add_player("p2".to_string());
+        for action in &actions {
+            game.handle_command(&command(action), "p2");
+        }
+        let replay = game.recording.take().unwrap();
+        assert!(game.current_turn > 0);
+        
+        let mut replayed = GameState::from_config(&config);
+        replay.play(&mut replayed).unwrap();
+        assert_eq!(snapshot(&replayed), snapshot(&game));
+    }
+    
+    #[test]
+    fn replay_fails_once_it_diverges() {
+        let config = create_default_config();
+        let mut game = GameState::from_config(&config);
+        game.add_player("p1".to_string());
+        game.start_recording();
+        game.handle_command(&command("move_right"), "p1");
+        let mut replay = game.recording.take().unwrap();
+        replay.entries[0].turn += 5;
+        
+        let mut replayed = GameState::from_config(&config);
+        assert!(replay.play(&mut replayed).is_err());
+    }
+}
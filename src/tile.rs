@@ -4,7 +4,8 @@ use rand::Rng;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tile {
-    pub walkable: bool,
+    pub walkable: bool,  // Blocks movement when false
+    pub blocks_projectiles: bool,  // Stops thrown items, line attacks and line of sight (walls, but not windows)
     pub tile_id: String,  // GameObject ID this tile came from (for client-side sprite lookup)
     pub sprite_x: u32,  // X coordinate in sprite sheet (in tiles) - selected sprite (kept for server-side use)
     pub sprite_y: u32,  // Y coordinate in sprite sheet (in tiles) - selected sprite (kept for server-side use)
@@ -15,10 +16,9 @@ pub struct Tile {
 impl From<&GameObject> for Tile {
     fn from(obj: &GameObject) -> Self {
         let sprites = obj.get_sprites_vec();
-        // Select a random sprite from the array
+        // Start on the first sprite - map generation randomizes it (randomize_sprite)
         let selected = if !sprites.is_empty() {
-            let mut rng = rand::thread_rng();
-            sprites[rng.gen_range(0..sprites.len())]  // Copy trait allows this
+            sprites[0]  // Copy trait allows this
         } else {
             // Fallback to legacy fields or default
             SpriteCoord {
@@ -29,6 +29,7 @@ impl From<&GameObject> for Tile {
         
         Self {
             walkable: obj.walkable,
+            blocks_projectiles: obj.blocks_projectiles.unwrap_or(!obj.walkable),
             tile_id: obj.id.clone(),
             sprite_x: selected.x,
             sprite_y: selected.y,
@@ -41,6 +42,7 @@ impl Tile {
     pub fn new(walkable: bool, sprite_x: u32, sprite_y: u32) -> Self {
         Self {
             walkable,
+            blocks_projectiles: !walkable,
             tile_id: format!("tile_{}_{}", sprite_x, sprite_y),  // Fallback ID for legacy tiles
             sprite_x,
             sprite_y,
@@ -51,6 +53,7 @@ impl Tile {
     pub fn new_with_id(walkable: bool, tile_id: String, sprite_x: u32, sprite_y: u32) -> Self {
         Self {
             walkable,
+            blocks_projectiles: !walkable,
             tile_id,
             sprite_x,
             sprite_y,
@@ -60,15 +63,14 @@ impl Tile {
 
     pub fn with_sprites(walkable: bool, sprites: Vec<SpriteCoord>) -> Self {
         let selected = if !sprites.is_empty() {
-            let mut rng = rand::thread_rng();
-            let idx = rng.gen_range(0..sprites.len());
-            sprites[idx]  // Copy trait allows this
+            sprites[0]  // Copy trait allows this
         } else {
             SpriteCoord { x: 0, y: 0 }
         };
         
         Self {
             walkable,
+            blocks_projectiles: !walkable,
             tile_id: format!("tile_{}_{}", selected.x, selected.y),  // Fallback ID
             sprite_x: selected.x,
             sprite_y: selected.y,
@@ -78,15 +80,14 @@ impl Tile {
     
     pub fn with_sprites_and_id(walkable: bool, tile_id: String, sprites: Vec<SpriteCoord>) -> Self {
         let selected = if !sprites.is_empty() {
-            let mut rng = rand::thread_rng();
-            let idx = rng.gen_range(0..sprites.len());
-            sprites[idx]  // Copy trait allows this
+            sprites[0]  // Copy trait allows this
         } else {
             SpriteCoord { x: 0, y: 0 }
         };
         
         Self {
             walkable,
+            blocks_projectiles: !walkable,
             tile_id,
             sprite_x: selected.x,
             sprite_y: selected.y,
@@ -94,9 +95,9 @@ impl Tile {
         }
     }
 
-    pub fn randomize_sprite(&mut self) {
+    /// Pick one of the tile's sprites with the level's `rng`
+    pub fn randomize_sprite(&mut self, rng: &mut impl Rng) {
         if !self.sprites.is_empty() {
-            let mut rng = rand::thread_rng();
             let idx = rng.gen_range(0..self.sprites.len());
             let selected = &self.sprites[idx];
             self.sprite_x = selected.x;
@@ -161,3 +162,22 @@ impl Tile {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn tile(walkable: bool, blocks_projectiles: Option<bool>) -> Tile {
+        let mut obj = GameObject::new("tile".to_string(), "Tile".to_string(), "tile".to_string(), walkable, 0, 0);
+        obj.blocks_projectiles = blocks_projectiles;
+        Tile::from(&obj)
+    }
+    
+    #[test]
+    fn projectile_blocking_defaults_to_not_walkable_unless_set() {
+        let flags = |tile: Tile| (tile.walkable, tile.blocks_projectiles);
+        assert_eq!(flags(tile(true, None)), (true, false));  // Floor
+        assert_eq!(flags(tile(false, None)), (false, true));  // Wall
+        assert_eq!(flags(tile(false, Some(false))), (false, false));  // Window
+        assert_eq!(flags(tile(true, Some(true))), (true, true));  // Smoke
+    }
+}
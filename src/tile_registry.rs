@@ -1,15 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use crate::game_object::GameObject;
 use crate::tile::Tile;
 
 pub struct TileRegistry {
-    objects: HashMap<String, GameObject>,
+    objects: BTreeMap<String, GameObject>,  // Sorted by id so iteration order is stable
 }
 
 impl TileRegistry {
     pub fn new() -> Self {
         Self {
-            objects: HashMap::new(),
+            objects: BTreeMap::new(),
         }
     }
 
@@ -50,6 +50,18 @@ impl TileRegistry {
             .collect()
     }
 
+    /// Tiles for a weighted theme, each repeated by its weight so a uniform pick is weighted.
+    /// Unknown ids and tiles that don't match `walkable` are skipped.
+    pub fn get_themed_tiles(&self, weights: &HashMap<String, u32>, walkable: bool) -> Vec<Tile> {
+        let mut ids: Vec<&String> = weights.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|id| self.get_tile(id).map(|tile| (tile, weights[id])))
+            .filter(|(tile, _)| tile.walkable == walkable)
+            .flat_map(|(tile, weight)| std::iter::repeat_n(tile, weight as usize))
+            .collect()
+    }
+
     // Fallback methods for backward compatibility
     pub fn get_wall_dirt_top(&self) -> Tile {
         self.get_tile("wall_dirt_top")
@@ -0,0 +1,216 @@
+use serde::Serialize;
+
+/// Structural description of a type sent to clients
+#[derive(Serialize, Debug)]
+pub struct TypeSchema {
+    pub name: &'static str,
+    pub kind: &'static str,  // "struct" or "enum"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<TypeField>,  // Struct fields, in serialization order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<&'static str>,  // Enum variants (serialized as plain strings)
+}
+
+#[derive(Serialize, Debug)]
+pub struct TypeField {
+    pub name: &'static str,
+    pub field_type: &'static str,  // Rust type, e.g. "u32", "Option<String>", "Vec<EntityData>", "(usize, usize)"
+    pub optional: bool,  // May be null or missing
+}
+
+/// Description of `GameUpdate` and every type nested in it, for client-side type generation.
+/// Hand-maintained like `GameObjectSchema`: update it whenever a field is added to the
+/// update types in api.rs or message.rs.
+#[derive(Serialize, Debug)]
+pub struct UpdateSchema {
+    pub root: &'static str,
+    pub types: Vec<TypeSchema>,
+}
+
+fn field(name: &'static str, field_type: &'static str) -> TypeField {
+    TypeField { name, field_type, optional: field_type.starts_with("Option<") }
+}
+
+fn struct_type(name: &'static str, fields: Vec<TypeField>) -> TypeSchema {
+    TypeSchema { name, kind: "struct", fields, variants: vec![] }
+}
+
+fn enum_type(name: &'static str, variants: Vec<&'static str>) -> TypeSchema {
+    TypeSchema { name, kind: "enum", fields: vec![], variants }
+}
+
+impl UpdateSchema {
+    pub fn generate() -> Self {
+        let types = vec![
+            struct_type("GameUpdate", vec![
+                field("map", "Vec<Vec<TileData>>"),
+                field("entities", "Vec<EntityData>"),
+                field("consumables", "Vec<ConsumableData>"),
+                field("chests", "Vec<ChestData>"),
+                field("players", "Vec<PlayerData>"),
+                field("current_player_id", "Option<String>"),
+                field("my_position", "Option<(usize, usize)>"),
+                field("width", "usize"),
+                field("height", "usize"),
+                field("messages", "Vec<GameMessage>"),
+                field("stairs_position", "Option<(usize, usize)>"),
+                field("on_stairs", "bool"),
+                field("stairs_up_position", "Option<(usize, usize)>"),
+                field("on_stairs_up", "bool"),
+                field("current_level", "u32"),
+                field("music_track", "Option<String>"),
+                field("ambiance", "Option<String>"),
+                field("level_complete", "bool"),
+                field("all_players_dead", "bool"),
+                field("restart_confirmed", "bool"),
+                field("game_over", "bool"),
+                field("waiting_for_players", "Option<LobbyStatus>"),
+                field("scoreboard", "Vec<RunRecord>"),
+                field("turn_phase", "String"),
+                field("current_turn", "u32"),
+                field("server_time_ms", "u64"),
+                field("is_my_turn", "bool"),
+                field("turn_order", "Vec<TurnOrderEntry>"),
+                field("explored_rooms", "Vec<Room>"),
+                field("sensed_loot", "Vec<(usize, usize)>"),
+                field("turns_remaining", "Option<u32>"),
+                field("monsters_remaining", "Option<usize>"),
+                field("win_progress", "Option<WinProgress>"),
+                field("combat_preview", "Option<Vec<CombatPreview>>"),
+                field("ack_command_id", "Option<String>"),
+                field("examine_result", "Option<ExamineResult>"),
+            ]),
+            struct_type("TileData", vec![
+                field("walkable", "bool"),
+                field("tile_id", "String"),
+            ]),
+            struct_type("EntityData", vec![
+                field("id", "String"),
+                field("object_id", "String"),
+                field("x", "usize"),
+                field("y", "usize"),
+                field("controller", "EntityController"),
+                field("current_health", "u32"),
+                field("max_health", "u32"),
+                field("attack", "i32"),
+                field("defense", "i32"),
+                field("crit_chance_percent", "u32"),
+                field("crit_damage_percent", "u32"),
+                field("facing_right", "bool"),
+                field("facing", "Direction"),
+                field("channel_turns_remaining", "u32"),
+                field("channel_target", "Option<String>"),
+                field("dying", "bool"),
+                field("inventory", "Vec<InventoryItem>"),
+                field("weapon", "Option<InventoryItem>"),
+                field("armor", "Option<InventoryItem>"),
+                field("resistances", "HashMap<String, i32>"),
+                field("status_effects", "Vec<StatusEffect>"),
+                field("unique_name", "Option<String>"),
+                field("tint", "Option<Tint>"),
+            ]),
+            struct_type("Tint", vec![
+                field("r", "f32"),
+                field("g", "f32"),
+                field("b", "f32"),
+            ]),
+            enum_type("EntityController", vec!["Player", "AI"]),
+            enum_type("Direction", vec!["up", "down", "left", "right"]),
+            struct_type("CombatPreview", vec![
+                field("entity_id", "String"),
+                field("x", "usize"),
+                field("y", "usize"),
+                field("damage_dealt", "(u32, u32)"),
+                field("damage_taken", "(u32, u32)"),
+            ]),
+            struct_type("LobbyStatus", vec![
+                field("connected", "usize"),
+                field("ready", "usize"),
+                field("min_players", "u32"),
+            ]),
+            struct_type("WinProgress", vec![
+                field("condition", "WinCondition"),
+                field("remaining", "u32"),
+            ]),
+            enum_type("WinCondition", vec!["reach_stairs", "kill_boss", "survive_turns", "collect_items"]),
+            struct_type("ExamineResult", vec![
+                field("x", "usize"),
+                field("y", "usize"),
+                field("tile", "Option<String>"),
+                field("walkable", "bool"),
+                field("entity", "Option<EntityData>"),
+                field("entity_name", "Option<String>"),
+                field("chest", "Option<ChestData>"),
+                field("item", "Option<ConsumableData>"),
+                field("is_stairs", "bool"),
+                field("is_stairs_up", "bool"),
+                field("description", "String"),
+            ]),
+            struct_type("StatusEffect", vec![
+                field("kind", "String"),
+                field("turns_remaining", "u32"),
+                field("amount", "i32"),
+            ]),
+            struct_type("InventoryItem", vec![
+                field("object_id", "String"),
+                field("count", "u32"),
+                field("rarity", "Option<String>"),
+                field("durability", "Option<u32>"),
+            ]),
+            struct_type("RunRecord", vec![
+                field("players", "Vec<String>"),
+                field("level", "u32"),
+                field("turns", "u32"),
+            ]),
+            struct_type("ConsumableData", vec![
+                field("id", "String"),
+                field("object_id", "String"),
+                field("x", "usize"),
+                field("y", "usize"),
+                field("rarity", "Option<String>"),
+            ]),
+            struct_type("ChestData", vec![
+                field("id", "String"),
+                field("object_id", "String"),
+                field("open_object_id", "Option<String>"),
+                field("x", "usize"),
+                field("y", "usize"),
+                field("is_open", "bool"),
+            ]),
+            struct_type("PlayerData", vec![
+                field("id", "String"),
+                field("name", "String"),
+                field("is_alive", "bool"),
+                field("has_acted_this_turn", "bool"),
+                field("confirmed_stairs", "bool"),
+                field("confirmed_stairs_up", "bool"),
+            ]),
+            struct_type("Room", vec![
+                field("x", "usize"),
+                field("y", "usize"),
+                field("width", "usize"),
+                field("height", "usize"),
+            ]),
+            struct_type("TurnOrderEntry", vec![
+                field("id", "String"),
+                field("name", "String"),
+                field("is_ai", "bool"),
+                field("has_acted", "bool"),
+            ]),
+            struct_type("GameMessage", vec![
+                field("message_type", "MessageType"),
+                field("text", "String"),
+                field("attacker", "Option<String>"),
+                field("target", "Option<String>"),
+                field("damage", "Option<u32>"),
+                field("target_health_after", "Option<u32>"),
+                field("target_died", "Option<bool>"),
+                field("is_crit", "Option<bool>"),
+                field("rarity", "Option<String>"),
+            ]),
+            enum_type("MessageType", vec!["Combat", "LevelEvent", "System"]),
+        ];
+        
+        Self { root: "GameUpdate", types }
+    }
+}
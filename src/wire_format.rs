@@ -0,0 +1,51 @@
+use axum::extract::ws::Message;
+use serde::Serialize;
+
+/// Encoding of server -> client messages, picked per connection with `/ws?format=...`.
+/// Commands from the client are always JSON text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WireFormat {
+    Json,  // Text frames (default, what the browser client uses)
+    MessagePack,  // Binary frames with the same field names as the JSON, a lot smaller for the tile map
+}
+
+impl WireFormat {
+    /// Format named by the `format` query parameter; anything unknown falls back to JSON
+    pub fn from_param(param: Option<&str>) -> Self {
+        match param {
+            Some("msgpack") | Some("messagepack") => WireFormat::MessagePack,
+            _ => WireFormat::Json,
+        }
+    }
+    
+    /// Encode a message as a websocket frame (None if serialization fails)
+    pub fn encode<T: Serialize>(&self, value: &T) -> Option<Message> {
+        match self {
+            WireFormat::Json => serde_json::to_string(value).ok().map(Message::Text),
+            // Named fields so optional (skipped) fields don't shift the others
+            WireFormat::MessagePack => rmp_serde::to_vec_named(value).ok().map(Message::Binary),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{game_state_to_update, GameUpdate};
+    use crate::game_state::tests::{game_on, level};
+    
+    #[test]
+    fn a_game_update_round_trips_through_message_pack() {
+        let update = game_state_to_update(&game_on(level("")), Some("p1"));
+        let Some(Message::Binary(bytes)) = WireFormat::MessagePack.encode(&update) else {
+            panic!("MessagePack is sent as binary frames");
+        };
+        let Some(Message::Text(json)) = WireFormat::Json.encode(&update) else {
+            panic!("JSON is sent as text frames");
+        };
+        
+        let decoded: GameUpdate = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&update).unwrap());
+        assert!(bytes.len() < json.len());
+    }
+}
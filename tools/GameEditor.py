@@ -223,7 +223,7 @@ class GameObjectEditor:
         if "object_type" in self.prop_widgets:
             self.prop_widgets["object_type"].grid_remove()
         type_combo = ttk.Combobox(middle_panel, textvariable=self.prop_vars["object_type"][0], 
-                                  values=["tile", "character", "goal", "consumable", "chest"], width=17)
+                                  values=["tile", "character", "goal", "consumable", "chest", "door", "lever", "key"], width=17)
         type_combo.grid(row=2, column=1, sticky=(tk.W, tk.E), pady=5)
         type_combo.bind("<<ComboboxSelected>>", lambda e: self._on_object_type_changed())
         # Update the widget reference to point to the Combobox
@@ -374,7 +374,7 @@ class GameObjectEditor:
     def _load_interactable_data(self, obj):
         """Show/hide interactable frame based on object type"""
         obj_type = obj.get("object_type", "")
-        if obj_type == "chest":
+        if obj_type in ("chest", "door", "lever"):
             self.interactable_frame.grid()
         else:
             self.interactable_frame.grid_remove()
@@ -406,7 +406,7 @@ class GameObjectEditor:
                     self.prop_widgets[key].grid_remove()
         
         # Show/hide interactable frame based on object type
-        if obj_type == "chest":
+        if obj_type in ("chest", "door", "lever"):
             self.interactable_frame.grid()
         else:
             self.interactable_frame.grid_remove()
@@ -1405,17 +1405,17 @@ class GameObjectEditor:
             self.current_object.pop("sprite_x", None)
             self.current_object.pop("sprite_y", None)
         
-        # Update interactable data - for chest objects, set interactable marker if sprites array has at least 2 sprites
+        # Update interactable data - for chest, door and lever objects, set interactable marker if sprites array has at least 2 sprites
         obj_type = self.current_object.get("object_type", "")
-        if obj_type == "chest":
+        if obj_type in ("chest", "door", "lever"):
             # If we have at least 2 sprites, mark as interactable
             if len(sprites) >= 2:
-                self.current_object["interactable"] = {}  # Empty object - just a marker
+                self.current_object.setdefault("interactable", {})  # Marker, keeping any configured effect
             else:
                 # Remove interactable if not enough sprites
                 self.current_object.pop("interactable", None)
         else:
-            # Remove interactable if not a chest
+            # Remove interactable if not a chest, door or lever
             self.current_object.pop("interactable", None)
         
         # Preserve sprite_sheet if it exists - don't remove it
@@ -1816,13 +1816,22 @@ class GameObjectEditor:
         self.chest_count_var = tk.StringVar()
         ttk.Entry(middle_panel, textvariable=self.chest_count_var, width=20).grid(row=5, column=1, sticky=(tk.W, tk.E), pady=5)
         
+        # Audio hints (forwarded to the client, empty = none)
+        ttk.Label(middle_panel, text="Music Track:").grid(row=6, column=0, sticky=tk.W, pady=5)
+        self.music_track_var = tk.StringVar()
+        ttk.Entry(middle_panel, textvariable=self.music_track_var, width=20).grid(row=6, column=1, sticky=(tk.W, tk.E), pady=5)
+        
+        ttk.Label(middle_panel, text="Ambiance:").grid(row=7, column=0, sticky=tk.W, pady=5)
+        self.ambiance_var = tk.StringVar()
+        ttk.Entry(middle_panel, textvariable=self.ambiance_var, width=20).grid(row=7, column=1, sticky=(tk.W, tk.E), pady=5)
+        
         # Allowed monsters
         ttk.Label(middle_panel, text="Allowed Monsters:", font=("Arial", 10, "bold")).grid(
-            row=6, column=0, columnspan=2, sticky=tk.W, pady=(20, 5))
+            row=8, column=0, columnspan=2, sticky=tk.W, pady=(20, 5))
         
         # Monster checkboxes frame with scrollbar
         monster_checkbox_frame = ttk.Frame(middle_panel)
-        monster_checkbox_frame.grid(row=7, column=0, columnspan=2, sticky=(tk.W, tk.E, tk.N, tk.S), pady=5)
+        monster_checkbox_frame.grid(row=9, column=0, columnspan=2, sticky=(tk.W, tk.E, tk.N, tk.S), pady=5)
         
         # Create a canvas with scrollbar for monster checkboxes
         monster_canvas = tk.Canvas(monster_checkbox_frame, height=150)
@@ -1919,7 +1928,7 @@ class GameObjectEditor:
         self.level_tab.columnconfigure(1, weight=0)
         self.level_tab.rowconfigure(0, weight=1)
         middle_panel.columnconfigure(1, weight=1)
-        middle_panel.rowconfigure(7, weight=1)  # Allow monster checkbox area to expand
+        middle_panel.rowconfigure(9, weight=1)  # Allow monster checkbox area to expand
     
     def refresh_monster_list(self):
         """Populate the monster checkboxes with available monster characters"""
@@ -1990,6 +1999,8 @@ class GameObjectEditor:
         self.min_monsters_var.set(str(level.get("min_monsters_per_room", 1)))
         self.max_monsters_var.set(str(level.get("max_monsters_per_room", 1)))
         self.chest_count_var.set(str(level.get("chest_count", 5)))
+        self.music_track_var.set(level.get("music_track", ""))
+        self.ambiance_var.set(level.get("ambiance", ""))
         
         # Set allowed monsters checkboxes
         allowed = level.get("allowed_monsters", [])
@@ -2082,7 +2093,8 @@ class GameObjectEditor:
         # Auto-save level changes when fields change (set up once)
         if not hasattr(self, '_level_traces_setup'):
             for var in [self.level_number_var, self.min_rooms_var, self.max_rooms_var, 
-                       self.min_monsters_var, self.max_monsters_var, self.chest_count_var]:
+                       self.min_monsters_var, self.max_monsters_var, self.chest_count_var,
+                       self.music_track_var, self.ambiance_var]:
                 var.trace_add("write", lambda *args: self._save_current_level_changes())
             self._level_traces_setup = True
     
@@ -2110,6 +2122,14 @@ class GameObjectEditor:
             level["max_monsters_per_room"] = int(self.max_monsters_var.get())
             level["chest_count"] = int(self.chest_count_var.get())
             
+            # Optional audio hints - drop the key when empty so the server sees None
+            for key, var in [("music_track", self.music_track_var), ("ambiance", self.ambiance_var)]:
+                value = var.get().strip()
+                if value:
+                    level[key] = value
+                else:
+                    level.pop(key, None)
+            
             # Get selected monsters from checkboxes
             allowed_ids = [
                 monster_id 
@@ -2143,11 +2163,15 @@ class GameObjectEditor:
             
             # Make HTTP request to the server's map generation endpoint
             import urllib.request
+            import urllib.parse
             import json
             
-            # Get level number from selected level
+            # Named levels are looked up by name, the rest by level number
             level_num = level.get("level_number", 0)
-            url = f"http://localhost:3000/api/map?level={level_num}"
+            if level.get("name"):
+                url = f"http://localhost:3000/api/map?name={urllib.parse.quote(level['name'])}"
+            else:
+                url = f"http://localhost:3000/api/map?level={level_num}"
             try:
                 self.log_status(f"Requesting map from server...", "info")
                 with urllib.request.urlopen(url, timeout=30) as response: